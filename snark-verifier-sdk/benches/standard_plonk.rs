@@ -13,7 +13,8 @@ use rand::rngs::OsRng;
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 use snark_verifier_sdk::{
-    gen_pk, gen_proof_shplonk, gen_snark_shplonk, AggregationCircuit, CircuitExt, Snark,
+    gen_pk, gen_proof_shplonk, gen_snark_shplonk, AggregationCircuit, AggregationConfigParams,
+    CircuitExt, Snark,
 };
 
 mod application {
@@ -175,7 +176,8 @@ fn gen_application_snark(params: &ParamsKZG<Bn256>) -> Snark {
 }
 
 fn bench(c: &mut Criterion) {
-    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
+    let config_params =
+        AggregationConfigParams::from_path("./configs/example_evm_accumulator.config").unwrap();
     let k = 21;
     let params = halo2_base::utils::fs::gen_srs(k);
     let params_app = {
@@ -188,7 +190,9 @@ fn bench(c: &mut Criterion) {
 
     let start1 = start_timer!(|| "Create aggregation circuit");
     let mut rng = ChaCha20Rng::from_entropy();
-    let agg_circuit = AggregationCircuit::new(&params, snarks, &mut rng);
+    let agg_circuit =
+        AggregationCircuit::new_with_config(config_params, &params, snarks, None, &mut rng)
+            .unwrap();
     end_timer!(start1);
 
     let pk = gen_pk(&params, &agg_circuit, None);
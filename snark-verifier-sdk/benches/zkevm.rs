@@ -113,7 +113,8 @@ fn bench(c: &mut Criterion) {
     #[cfg(feature = "loader_evm")]
     {
         let deployment_code =
-            gen_evm_verifier_shplonk::<AggregationCircuit>(&params, pk.get_vk(), &(), None::<&str>);
+            gen_evm_verifier_shplonk::<AggregationCircuit>(&params, pk.get_vk(), &(), None::<&str>)
+                .unwrap();
 
         let start2 = start_timer!(|| "Create EVM SHPLONK proof");
         let proof = gen_evm_proof_shplonk(
@@ -128,7 +129,8 @@ fn bench(c: &mut Criterion) {
         evm_verify(deployment_code, agg_circuit.instances(), proof);
 
         let deployment_code =
-            gen_evm_verifier_shplonk::<AggregationCircuit>(&params, pk.get_vk(), &(), None::<&str>);
+            gen_evm_verifier_shplonk::<AggregationCircuit>(&params, pk.get_vk(), &(), None::<&str>)
+                .unwrap();
 
         let start2 = start_timer!(|| "Create EVM GWC proof");
         let proof =
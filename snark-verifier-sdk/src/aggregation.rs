@@ -1,6 +1,10 @@
 #![allow(clippy::clone_on_copy)]
 use crate::{
-    types::{BaseFieldEccChip, Halo2Loader, Plonk, PoseidonTranscript, POSEIDON_SPEC},
+    snark::MultiOpen,
+    types::{
+        BaseFieldEccChip, Halo2Loader, KzgAs, KzgBDFG, KzgGWC, Plonk, PoseidonTranscript, Svk,
+        POSEIDON_SPEC,
+    },
     SnarkWitness,
 };
 #[cfg(feature = "display")]
@@ -16,31 +20,44 @@ use halo2_base::{
 };
 use itertools::Itertools;
 use snark_verifier::{
-    loader::halo2::EccInstructions,
+    loader::{halo2::EccInstructions, Loader},
     pcs::{
-        kzg::{KzgAccumulator, KzgAs},
-        AccumulationScheme, MultiOpenScheme, PolynomialCommitmentScheme,
+        kzg::{KzgAccumulator, LimbsEncoding},
+        AccumulationScheme, AccumulatorEncoding, MultiOpenScheme,
     },
+    util::transcript::TranscriptRead,
     verifier::PlonkVerifier,
+    Error, Protocol,
 };
-use std::{fs::File, rc::Rc};
+use std::{io, rc::Rc};
 
 use config::AggregationConfigParams;
 
 pub mod aggregation_circuit;
 pub mod config;
 pub mod multi_aggregation_circuit;
+pub mod tuning;
 
-pub fn load_verify_circuit_degree() -> u32 {
+/// Read [`AggregationConfigParams`] from the file at env var `VERIFY_CONFIG`, or
+/// `./configs/verify_circuit.config` if it's not set.
+pub fn load_verify_circuit_params() -> io::Result<AggregationConfigParams> {
     let path = std::env::var("VERIFY_CONFIG")
         .unwrap_or_else(|_| "./configs/verify_circuit.config".to_string());
-    let params: AggregationConfigParams = serde_json::from_reader(
-        File::open(path.as_str()).unwrap_or_else(|_| panic!("{path} does not exist")),
-    )
-    .unwrap();
-    params.degree
+    AggregationConfigParams::from_path(path)
+}
+
+/// Returns degree of [`load_verify_circuit_params`].
+pub fn load_verify_circuit_degree() -> io::Result<u32> {
+    load_verify_circuit_params().map(|config_params| config_params.degree)
 }
 
+/// Returns the limbs of `accumulator` as `[..lhs.x, ..lhs.y, ..rhs.x, ..rhs.y]`,
+/// which are `4 * num_limbs` cells with `num_limbs` of the chip config.
+///
+/// This is the [`LimbsEncoding`] every SDK aggregation circuit exposes its accumulator with, and
+/// decodes accumulators of previous snarks with. The SDK doesn't support
+/// [`CompressedLimbsEncoding`](snark_verifier::pcs::kzg::CompressedLimbsEncoding), so fewer
+/// instances only apply to circuits built on `snark-verifier` directly.
 pub fn flatten_accumulator<'a>(
     accumulator: KzgAccumulator<G1Affine, Rc<Halo2Loader<'a>>>,
 ) -> Vec<AssignedValue<Fr>> {
@@ -58,28 +75,76 @@ pub fn flatten_accumulator<'a>(
         .collect()
 }
 
+/// Reads the proof of a snark proven with `multi_open` from `transcript`, and succinctly
+/// verifies it with `Kzg<Bn256, Gwc19>` or `Kzg<Bn256, Bdfg21>` accordingly.
+///
+/// Returns an [`Error`] if the proof is malformed.
+pub(crate) fn succinct_verify<L, T, const LIMBS: usize, const BITS: usize>(
+    svk: &Svk,
+    multi_open: MultiOpen,
+    protocol: &Protocol<G1Affine, L>,
+    instances: &[Vec<L::LoadedScalar>],
+    transcript: &mut T,
+) -> Result<Vec<KzgAccumulator<G1Affine, L>>, Error>
+where
+    L: Loader<G1Affine>,
+    T: TranscriptRead<G1Affine, L>,
+    LimbsEncoding<LIMBS, BITS>:
+        AccumulatorEncoding<G1Affine, L, KzgGWC> + AccumulatorEncoding<G1Affine, L, KzgBDFG>,
+{
+    fn succinct_verify_with<MOS, L, T, const LIMBS: usize, const BITS: usize>(
+        svk: &Svk,
+        protocol: &Protocol<G1Affine, L>,
+        instances: &[Vec<L::LoadedScalar>],
+        transcript: &mut T,
+    ) -> Result<Vec<KzgAccumulator<G1Affine, L>>, Error>
+    where
+        L: Loader<G1Affine>,
+        T: TranscriptRead<G1Affine, L>,
+        MOS: MultiOpenScheme<
+            G1Affine,
+            L,
+            SuccinctVerifyingKey = Svk,
+            Accumulator = KzgAccumulator<G1Affine, L>,
+        >,
+        LimbsEncoding<LIMBS, BITS>: AccumulatorEncoding<G1Affine, L, MOS>,
+    {
+        let proof = Plonk::<MOS, LIMBS, BITS>::read_proof(svk, protocol, instances, transcript)?;
+        Plonk::<MOS, LIMBS, BITS>::succinct_verify(svk, protocol, instances, &proof)
+    }
+
+    match multi_open {
+        MultiOpen::Gwc19 => {
+            succinct_verify_with::<KzgGWC, _, _, LIMBS, BITS>(svk, protocol, instances, transcript)
+        }
+        MultiOpen::Bdfg21 => {
+            succinct_verify_with::<KzgBDFG, _, _, LIMBS, BITS>(svk, protocol, instances, transcript)
+        }
+    }
+}
+
 #[allow(clippy::type_complexity)]
 /// Core function used in `synthesize` to aggregate multiple `snarks`.
 ///  
 /// Returns the assigned instances of previous snarks and the new final pair that needs to be verified in a pairing check.
+/// Each snark is succinctly verified according to its multi-open scheme, and the accumulators are accumulated with SHPLONK.
+/// Accumulators of previous snarks are decoded from `LIMBS` limbs of `BITS` bits, which have to match the chip config.
 /// For each previous snark, we concatenate all instances into a single vector. We return a vector of vectors,
 /// one vector per snark, for convenience.
-pub fn aggregate<'a, PCS>(
-    svk: &PCS::SuccinctVerifyingKey,
+///
+/// Returns an [`Error`] if any of the snark proofs or the accumulation proof is malformed.
+pub fn aggregate<'a, const LIMBS: usize, const BITS: usize>(
+    svk: &Svk,
     loader: &Rc<Halo2Loader<'a>>,
     snarks: &[SnarkWitness],
     as_proof: Value<&'_ [u8]>,
-) -> (
-    Vec<Vec<<BaseFieldEccChip as EccInstructions<'a, G1Affine>>::AssignedScalar>>,
-    KzgAccumulator<G1Affine, Rc<Halo2Loader<'a>>>,
-)
-where
-    PCS: PolynomialCommitmentScheme<
-            G1Affine,
-            Rc<Halo2Loader<'a>>,
-            Accumulator = KzgAccumulator<G1Affine, Rc<Halo2Loader<'a>>>,
-        > + MultiOpenScheme<G1Affine, Rc<Halo2Loader<'a>>>,
-{
+) -> Result<
+    (
+        Vec<Vec<<BaseFieldEccChip as EccInstructions<'a, G1Affine>>::AssignedScalar>>,
+        KzgAccumulator<G1Affine, Rc<Halo2Loader<'a>>>,
+    ),
+    Error,
+> {
     let assign_instances = |instances: &[Vec<Value<Fr>>]| {
         instances
             .iter()
@@ -97,35 +162,34 @@ where
         POSEIDON_SPEC.clone(),
     );
 
-    let mut accumulators = snarks
-        .iter()
-        .flat_map(|snark| {
-            let protocol = snark.protocol.loaded(loader);
-            // TODO use 1d vector
-            let instances = assign_instances(&snark.instances);
-
-            // read the transcript and perform Fiat-Shamir
-            // run through verification computation and produce the final pair `succinct`
-            transcript.new_stream(snark.proof());
-            let proof = Plonk::<PCS>::read_proof(svk, &protocol, &instances, &mut transcript);
-            let accumulator = Plonk::<PCS>::succinct_verify(svk, &protocol, &instances, &proof);
+    let mut accumulators = Vec::new();
+    for snark in snarks {
+        let protocol = snark.protocol.loaded(loader);
+        // TODO use 1d vector
+        let instances = assign_instances(&snark.instances);
 
-            previous_instances.push(
-                instances.into_iter().flatten().map(|scalar| scalar.into_assigned()).collect(),
-            );
+        // read the transcript and perform Fiat-Shamir
+        // run through verification computation and produce the final pair `succinct`
+        transcript.new_stream(snark.proof());
+        accumulators.extend(succinct_verify::<_, _, LIMBS, BITS>(
+            svk,
+            snark.multi_open,
+            &protocol,
+            &instances,
+            &mut transcript,
+        )?);
 
-            accumulator
-        })
-        .collect_vec();
+        previous_instances
+            .push(instances.into_iter().flatten().map(|scalar| scalar.into_assigned()).collect());
+    }
 
     let accumulator = if accumulators.len() > 1 {
         transcript.new_stream(as_proof);
-        let proof =
-            KzgAs::<PCS>::read_proof(&Default::default(), &accumulators, &mut transcript).unwrap();
-        KzgAs::<PCS>::verify(&Default::default(), &accumulators, &proof).unwrap()
+        let proof = KzgAs::read_proof(&Default::default(), &accumulators, &mut transcript)?;
+        KzgAs::verify(&Default::default(), &accumulators, &proof)?
     } else {
         accumulators.pop().unwrap()
     };
 
-    (previous_instances, accumulator)
+    Ok((previous_instances, accumulator))
 }
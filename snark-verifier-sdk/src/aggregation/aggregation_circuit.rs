@@ -1,5 +1,3 @@
-use std::fs::File;
-
 use halo2_base::{
     halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner, Value},
@@ -15,24 +13,42 @@ use snark_verifier::{
     loader::native::NativeLoader,
     pcs::{kzg::KzgAccumulator, AccumulationSchemeProver},
     util::arithmetic::fe_to_limbs,
-    verifier::PlonkVerifier,
+    Error,
 };
 
 use crate::{
     aggregation::{
         aggregate,
         config::{AggregationConfig, AggregationConfigParams},
-        flatten_accumulator, POSEIDON_SPEC,
+        flatten_accumulator, succinct_verify,
+        tuning::AggregationCircuitStats,
+        POSEIDON_SPEC,
     },
-    types::{Halo2Loader, KzgAs, KzgBDFG, PoseidonTranscript, Shplonk, Svk},
-    CircuitExt, Snark, SnarkWitness, BITS, LIMBS,
+    types::{Halo2Loader, KzgAs, PoseidonTranscript, Svk},
+    CircuitExt, Snark, SnarkWitness,
 };
 
 /// Aggregation circuit that does not re-expose any public inputs from aggregated snarks
 ///
+/// Snarks proven with GWC19 and SHPLONK can be aggregated together, each of which is verified
+/// according to [`Snark::multi_open`].
+///
 /// This is mostly a reference implementation. In practice one will probably need to re-implement the circuit for one's particular use case with specific instance logic.
+///
+/// The circuit is configured by [`AggregationConfigParams`] as [`Circuit::Params`]. The
+/// accumulator is exposed as `LIMBS` limbs of `BITS` bits for each coordinate by
+/// [`flatten_accumulator`], which have to match `num_limbs` and `limb_bits` of the config.
+///
+/// The limb layout is part of the type rather than the runtime config, since
+/// [`CircuitExt::accumulator_indices`] and the encoding of accumulators of previous snarks are
+/// fixed at compile time. So the circuit type has to be instantiated for each layout, e.g.
+/// `AggregationCircuit::<4, 64>` for 4 limbs of 64 bits, and a config with another layout is
+/// rejected.
 #[derive(Clone)]
-pub struct AggregationCircuit {
+pub struct AggregationCircuit<
+    const LIMBS: usize = { crate::LIMBS },
+    const BITS: usize = { crate::BITS },
+> {
     pub(crate) svk: Svk,
     pub(crate) snarks: Vec<SnarkWitness>,
     // the public instances from previous snarks that were aggregated, now collected as PRIVATE assigned values
@@ -40,34 +56,67 @@ pub struct AggregationCircuit {
     pub(crate) instances: Vec<Fr>,
     // accumulation scheme proof, private input
     pub(crate) as_proof: Value<Vec<u8>>,
+    pub(crate) config_params: AggregationConfigParams,
 }
 
 impl AggregationCircuit {
-    pub fn new(
+    /// Natively verifies the `snarks` and builds an aggregation circuit for them, which is
+    /// configured by `config_params`. If `protocol_digests` is given, it should contain the
+    /// expected [`Protocol::digest`](snark_verifier::Protocol::digest) of each snark.
+    ///
+    /// Returns an [`Error`] if the limb decomposition of `config_params` isn't the default one,
+    /// any of the snarks doesn't match its expected protocol digest, or any of the snark proofs
+    /// is malformed.
+    pub fn new_with_config(
+        config_params: AggregationConfigParams,
         params: &ParamsKZG<Bn256>,
         snarks: impl IntoIterator<Item = Snark>,
+        protocol_digests: Option<&[[u8; 32]]>,
         rng: impl Rng + Send,
-    ) -> Self {
+    ) -> Result<Self, Error> {
+        Self::new_with_limbs(config_params, params, snarks, protocol_digests, rng)
+    }
+}
+
+impl<const LIMBS: usize, const BITS: usize> AggregationCircuit<LIMBS, BITS> {
+    /// Natively verifies the `snarks` and builds an aggregation circuit for them, which is
+    /// configured by `config_params` with accumulators decomposed into `LIMBS` limbs of `BITS`
+    /// bits. If `protocol_digests` is given, it should contain the expected
+    /// [`Protocol::digest`](snark_verifier::Protocol::digest) of each snark.
+    ///
+    /// Returns an [`Error`] if the limb decomposition of `config_params` doesn't match the
+    /// circuit, any of the snarks doesn't match its expected protocol digest, or any of the snark
+    /// proofs is malformed.
+    pub fn new_with_limbs(
+        config_params: AggregationConfigParams,
+        params: &ParamsKZG<Bn256>,
+        snarks: impl IntoIterator<Item = Snark>,
+        protocol_digests: Option<&[[u8; 32]]>,
+        rng: impl Rng + Send,
+    ) -> Result<Self, Error> {
+        check_limbs::<LIMBS, BITS>(&config_params).map_err(Error::AssertionFailure)?;
+
         let svk = params.get_g()[0].into();
         let snarks = snarks.into_iter().collect_vec();
+        if let Some(protocol_digests) = protocol_digests {
+            check_protocol_digests(&snarks, protocol_digests).map_err(Error::AssertionFailure)?;
+        }
 
         // TODO: this is all redundant calculation to get the public output
         // Halo2 should just be able to expose public output to instance column directly
         let mut transcript_read =
             PoseidonTranscript::<NativeLoader, &[u8]>::from_spec(&[], POSEIDON_SPEC.clone());
-        let accumulators = snarks
-            .iter()
-            .flat_map(|snark| {
-                transcript_read.new_stream(snark.proof.as_slice());
-                let proof = Shplonk::read_proof(
-                    &svk,
-                    &snark.protocol,
-                    &snark.instances,
-                    &mut transcript_read,
-                );
-                Shplonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof)
-            })
-            .collect_vec();
+        let mut accumulators = Vec::new();
+        for snark in snarks.iter() {
+            transcript_read.new_stream(snark.proof.as_slice());
+            accumulators.extend(succinct_verify::<_, _, LIMBS, BITS>(
+                &svk,
+                snark.multi_open,
+                &snark.protocol,
+                &snark.instances,
+                &mut transcript_read,
+            )?);
+        }
 
         let (accumulator, as_proof) = {
             let mut transcript_write = PoseidonTranscript::<NativeLoader, Vec<u8>>::from_spec(
@@ -75,21 +124,25 @@ impl AggregationCircuit {
                 POSEIDON_SPEC.clone(),
             );
             // We always use SHPLONK for accumulation scheme when aggregating proofs
-            let accumulator =
-                KzgAs::create_proof(&Default::default(), &accumulators, &mut transcript_write, rng)
-                    .unwrap();
+            let accumulator = KzgAs::create_proof(
+                &Default::default(),
+                &accumulators,
+                &mut transcript_write,
+                rng,
+            )?;
             (accumulator, transcript_write.finalize())
         };
 
         let KzgAccumulator { lhs, rhs } = accumulator;
         let instances = [lhs.x, lhs.y, rhs.x, rhs.y].map(fe_to_limbs::<_, _, LIMBS, BITS>).concat();
 
-        Self {
+        Ok(Self {
             svk,
             snarks: snarks.into_iter().map_into().collect(),
             instances,
             as_proof: Value::known(as_proof),
-        }
+            config_params,
+        })
     }
 
     pub fn instance(&self) -> Vec<Fr> {
@@ -107,9 +160,41 @@ impl AggregationCircuit {
     pub fn as_proof(&self) -> Value<&[u8]> {
         self.as_proof.as_ref().map(Vec::as_slice)
     }
+
+    pub fn config_params(&self) -> &AggregationConfigParams {
+        &self.config_params
+    }
+}
+
+fn check_limbs<const LIMBS: usize, const BITS: usize>(
+    config_params: &AggregationConfigParams,
+) -> Result<(), String> {
+    if config_params.num_limbs != LIMBS || config_params.limb_bits != BITS {
+        return Err(format!(
+            "Config has {} limbs of {} bits, but the circuit expects {LIMBS} limbs of {BITS} bits",
+            config_params.num_limbs, config_params.limb_bits
+        ));
+    }
+    Ok(())
 }
 
-impl CircuitExt<Fr> for AggregationCircuit {
+fn check_protocol_digests(snarks: &[Snark], protocol_digests: &[[u8; 32]]) -> Result<(), String> {
+    if snarks.len() != protocol_digests.len() {
+        return Err(format!(
+            "Got {} protocol digests for {} snarks",
+            protocol_digests.len(),
+            snarks.len()
+        ));
+    }
+    for (idx, (snark, protocol_digest)) in snarks.iter().zip(protocol_digests).enumerate() {
+        if snark.protocol.digest() != *protocol_digest {
+            return Err(format!("Protocol of snark {idx} doesn't match the expected circuit"));
+        }
+    }
+    Ok(())
+}
+
+impl<const LIMBS: usize, const BITS: usize> CircuitExt<Fr> for AggregationCircuit<LIMBS, BITS> {
     fn num_instance(&self) -> Vec<usize> {
         // [..lhs, ..rhs]
         vec![4 * LIMBS]
@@ -128,8 +213,8 @@ impl CircuitExt<Fr> for AggregationCircuit {
     }
 }
 
-impl Circuit<Fr> for AggregationCircuit {
-    type Params = ();
+impl<const LIMBS: usize, const BITS: usize> Circuit<Fr> for AggregationCircuit<LIMBS, BITS> {
+    type Params = AggregationConfigParams;
     type Config = AggregationConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
@@ -139,63 +224,93 @@ impl Circuit<Fr> for AggregationCircuit {
             snarks: self.snarks.iter().map(SnarkWitness::without_witnesses).collect(),
             instances: Vec::new(),
             as_proof: Value::unknown(),
+            config_params: self.config_params.clone(),
         }
     }
 
-    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
-        let path = std::env::var("VERIFY_CONFIG")
-            .unwrap_or_else(|_| "configs/verify_circuit.config".to_owned());
-        let params: AggregationConfigParams = serde_json::from_reader(
-            File::open(path.as_str()).unwrap_or_else(|_| panic!("{path:?} does not exist")),
-        )
-        .unwrap();
+    fn params(&self) -> Self::Params {
+        self.config_params.clone()
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<Fr>,
+        params: Self::Params,
+    ) -> Self::Config {
+        check_limbs::<LIMBS, BITS>(&params).unwrap_or_else(|err| panic!("{err}"));
 
         AggregationConfig::configure(meta, params)
     }
 
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!("AggregationCircuit is configured by configure_with_params")
+    }
+
     fn synthesize(
         &self,
         config: Self::Config,
-        mut layouter: impl Layouter<Fr>,
+        layouter: impl Layouter<Fr>,
     ) -> Result<(), plonk::Error> {
+        self.synthesize_with_stats(config, layouter).map(|_| ())
+    }
+}
+
+impl<const LIMBS: usize, const BITS: usize> AggregationCircuit<LIMBS, BITS> {
+    /// Synthesizes the circuit as [`Circuit::synthesize`], and returns the cells it uses, with
+    /// `rows` left as 0.
+    pub(crate) fn synthesize_with_stats(
+        &self,
+        config: AggregationConfig,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<AggregationCircuitStats, plonk::Error> {
         #[cfg(feature = "display")]
         let witness_time = start_timer!(|| "synthesize | Aggregation Circuit");
         config.range().load_lookup_table(&mut layouter).expect("load range lookup table");
         let mut first_pass = halo2_base::SKIP_FIRST_PASS;
         let mut instances = vec![];
-        layouter
-            .assign_region(
-                || "",
-                |region| {
-                    if first_pass {
-                        first_pass = false;
-                        return Ok(());
-                    }
-                    let ctx = Context::new(
-                        region,
-                        ContextParams {
-                            max_rows: config.gate().max_rows,
-                            num_context_ids: 1,
-                            fixed_columns: config.gate().constants.clone(),
-                        },
-                    );
-
-                    let ecc_chip = config.ecc_chip();
-                    let loader = Halo2Loader::new(ecc_chip, ctx);
-                    let (_, acc) =
-                        aggregate::<KzgBDFG>(&self.svk, &loader, &self.snarks, self.as_proof());
-
-                    instances.extend(
-                        flatten_accumulator(acc).iter().map(|assigned| assigned.cell().clone()),
-                    );
-
-                    config.range().finalize(&mut loader.ctx_mut());
-                    #[cfg(feature = "display")]
-                    loader.ctx_mut().print_stats(&["Range"]);
-                    Ok(())
-                },
-            )
-            .unwrap();
+        let mut stats = AggregationCircuitStats::default();
+        layouter.assign_region(
+            || "",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let ctx = Context::new(
+                    region,
+                    ContextParams {
+                        max_rows: config.gate().max_rows,
+                        num_context_ids: 1,
+                        fixed_columns: config.gate().constants.clone(),
+                    },
+                );
+
+                let ecc_chip = config.ecc_chip();
+                let loader = Halo2Loader::new(ecc_chip, ctx);
+                let (_, acc) =
+                    aggregate::<LIMBS, BITS>(&self.svk, &loader, &self.snarks, self.as_proof())
+                        .map_err(|err| {
+                            log::error!("failed to aggregate snarks: {err:?}");
+                            plonk::Error::Synthesis
+                        })?;
+
+                instances.extend(
+                    flatten_accumulator(acc).iter().map(|assigned| assigned.cell().clone()),
+                );
+
+                // lookup cells are moved to lookup advice columns by `finalize`
+                let lookup_cells = loader.ctx().cells_to_lookup.len();
+                let (_, constants, _) = config.range().finalize(&mut loader.ctx_mut());
+                stats = AggregationCircuitStats {
+                    rows: 0,
+                    advice_cells: loader.ctx().total_advice,
+                    lookup_cells,
+                    constants,
+                };
+                #[cfg(feature = "display")]
+                loader.ctx_mut().print_stats(&["Range"]);
+                Ok(())
+            },
+        )?;
 
         // Expose instances
         for (i, cell) in instances.into_iter().enumerate() {
@@ -203,6 +318,6 @@ impl Circuit<Fr> for AggregationCircuit {
         }
         #[cfg(feature = "display")]
         end_timer!(witness_time);
-        Ok(())
+        Ok(stats)
     }
 }
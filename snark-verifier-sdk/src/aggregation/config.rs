@@ -9,11 +9,15 @@ use snark_verifier::loader::halo2::halo2_ecc::{
     ecc::{BaseFieldEccChip, EccChip},
     fields::fp::{FpConfig, FpStrategy},
 };
+use std::{fs::File, io, path::Path};
 
 use crate::{BITS, LIMBS};
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 /// Parameters for aggregation circuit configs.
+///
+/// `limb_bits` and `num_limbs` configure the non-native field chip, and have to match the limb
+/// layout an aggregation circuit type is instantiated with.
 pub struct AggregationConfigParams {
     pub strategy: FpStrategy,
     pub degree: u32,
@@ -25,6 +29,29 @@ pub struct AggregationConfigParams {
     pub num_limbs: usize,
 }
 
+impl Default for AggregationConfigParams {
+    /// Same as `configs/verify_circuit.config`.
+    fn default() -> Self {
+        Self {
+            strategy: FpStrategy::Simple,
+            degree: 21,
+            num_advice: vec![4],
+            num_lookup_advice: vec![1],
+            num_fixed: 1,
+            lookup_bits: 20,
+            limb_bits: BITS,
+            num_limbs: LIMBS,
+        }
+    }
+}
+
+impl AggregationConfigParams {
+    /// Read parameters from the JSON file at `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+}
+
 #[derive(Clone, Debug)]
 /// Configurations for aggregation circuit
 pub struct AggregationConfig {
@@ -37,11 +64,6 @@ pub struct AggregationConfig {
 impl AggregationConfig {
     /// Build a configuration from parameters.
     pub fn configure(meta: &mut ConstraintSystem<Fr>, params: AggregationConfigParams) -> Self {
-        assert!(
-            params.limb_bits == BITS && params.num_limbs == LIMBS,
-            "For now we fix limb_bits = {}, otherwise change code",
-            BITS
-        );
         let base_field_config = FpConfig::configure(
             meta,
             params.strategy,
@@ -49,8 +71,8 @@ impl AggregationConfig {
             &params.num_lookup_advice,
             params.num_fixed,
             params.lookup_bits,
-            BITS,
-            LIMBS,
+            params.limb_bits,
+            params.num_limbs,
             modulus::<Fq>(),
             0,
             params.degree as usize,
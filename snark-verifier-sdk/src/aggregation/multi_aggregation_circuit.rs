@@ -2,7 +2,7 @@
 use crate::{
     aggregation::{aggregate, flatten_accumulator},
     types::Halo2Loader,
-    CircuitExt, Snark, LIMBS,
+    CircuitExt, Snark,
 };
 #[cfg(feature = "display")]
 use ark_std::end_timer;
@@ -20,33 +20,80 @@ use halo2_base::{
 };
 use itertools::Itertools;
 use rand::Rng;
-use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
+use snark_verifier::Error;
 
-use super::{aggregation_circuit::AggregationCircuit, config::AggregationConfig};
+use super::{
+    aggregation_circuit::AggregationCircuit,
+    config::{AggregationConfig, AggregationConfigParams},
+};
 
 /// This circuit takes multiple SNARKs and passes through all of their instances except the old accumulators.
 ///
 /// * If `has_prev_accumulator = true`, we assume all SNARKs are of aggregation circuits with old accumulators
 /// only in the first instance column.
 /// * Otherwise if `has_prev_accumulator = false`, then all previous instances are passed through.
+///
+/// Accumulators are decomposed into `LIMBS` limbs of `BITS` bits as in [`AggregationCircuit`],
+/// so the circuit type has to be instantiated for each limb layout as well.
 #[derive(Clone)]
-pub struct PublicAggregationCircuit {
-    pub aggregation: AggregationCircuit,
+pub struct PublicAggregationCircuit<
+    const LIMBS: usize = { crate::LIMBS },
+    const BITS: usize = { crate::BITS },
+> {
+    pub aggregation: AggregationCircuit<LIMBS, BITS>,
     pub has_prev_accumulator: bool,
 }
 
 impl PublicAggregationCircuit {
-    pub fn new(
+    /// Builds the circuit configured by `config_params`, rejecting snarks which don't match
+    /// `protocol_digests` if given as [`AggregationCircuit::new_with_config`].
+    pub fn new_with_config(
+        config_params: AggregationConfigParams,
+        params: &ParamsKZG<Bn256>,
+        snarks: Vec<Snark>,
+        protocol_digests: Option<&[[u8; 32]]>,
+        has_prev_accumulator: bool,
+        rng: &mut (impl Rng + Send),
+    ) -> Result<Self, Error> {
+        Self::new_with_limbs(
+            config_params,
+            params,
+            snarks,
+            protocol_digests,
+            has_prev_accumulator,
+            rng,
+        )
+    }
+}
+
+impl<const LIMBS: usize, const BITS: usize> PublicAggregationCircuit<LIMBS, BITS> {
+    /// Builds the circuit configured by `config_params` with accumulators decomposed into
+    /// `LIMBS` limbs of `BITS` bits, rejecting snarks which don't match `protocol_digests` if
+    /// given as [`AggregationCircuit::new_with_limbs`].
+    pub fn new_with_limbs(
+        config_params: AggregationConfigParams,
         params: &ParamsKZG<Bn256>,
         snarks: Vec<Snark>,
+        protocol_digests: Option<&[[u8; 32]]>,
         has_prev_accumulator: bool,
         rng: &mut (impl Rng + Send),
-    ) -> Self {
-        Self { aggregation: AggregationCircuit::new(params, snarks, rng), has_prev_accumulator }
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            aggregation: AggregationCircuit::new_with_limbs(
+                config_params,
+                params,
+                snarks,
+                protocol_digests,
+                rng,
+            )?,
+            has_prev_accumulator,
+        })
     }
 }
 
-impl CircuitExt<Fr> for PublicAggregationCircuit {
+impl<const LIMBS: usize, const BITS: usize> CircuitExt<Fr>
+    for PublicAggregationCircuit<LIMBS, BITS>
+{
     fn num_instance(&self) -> Vec<usize> {
         let prev_num = self
             .aggregation
@@ -81,12 +128,12 @@ impl CircuitExt<Fr> for PublicAggregationCircuit {
     }
 
     fn selectors(config: &Self::Config) -> Vec<Selector> {
-        AggregationCircuit::selectors(config)
+        AggregationCircuit::<LIMBS, BITS>::selectors(config)
     }
 }
 
-impl Circuit<Fr> for PublicAggregationCircuit {
-    type Params = ();
+impl<const LIMBS: usize, const BITS: usize> Circuit<Fr> for PublicAggregationCircuit<LIMBS, BITS> {
+    type Params = AggregationConfigParams;
     type Config = AggregationConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
@@ -97,8 +144,19 @@ impl Circuit<Fr> for PublicAggregationCircuit {
         }
     }
 
-    fn configure(meta: &mut plonk::ConstraintSystem<Fr>) -> Self::Config {
-        AggregationCircuit::configure(meta)
+    fn params(&self) -> Self::Params {
+        self.aggregation.params()
+    }
+
+    fn configure_with_params(
+        meta: &mut plonk::ConstraintSystem<Fr>,
+        params: Self::Params,
+    ) -> Self::Config {
+        AggregationCircuit::<LIMBS, BITS>::configure_with_params(meta, params)
+    }
+
+    fn configure(_: &mut plonk::ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!("PublicAggregationCircuit is configured by configure_with_params")
     }
 
     fn synthesize(
@@ -111,48 +169,49 @@ impl Circuit<Fr> for PublicAggregationCircuit {
         config.range().load_lookup_table(&mut layouter).expect("load range lookup table");
         let mut first_pass = halo2_base::SKIP_FIRST_PASS;
         let mut instances = vec![];
-        layouter
-            .assign_region(
-                || "",
-                |region| {
-                    if first_pass {
-                        first_pass = false;
-                        return Ok(());
-                    }
-                    let ctx = Context::new(
-                        region,
-                        ContextParams {
-                            max_rows: config.gate().max_rows,
-                            num_context_ids: 1,
-                            fixed_columns: config.gate().constants.clone(),
-                        },
-                    );
-
-                    let ecc_chip = config.ecc_chip();
-                    let loader = Halo2Loader::new(ecc_chip, ctx);
-                    let (prev_instances, acc) = aggregate::<Kzg<Bn256, Bdfg21>>(
-                        &self.aggregation.svk,
-                        &loader,
-                        &self.aggregation.snarks,
-                        self.aggregation.as_proof(),
-                    );
-
-                    // accumulator
-                    instances.extend(flatten_accumulator(acc).iter().map(|a| a.cell().clone()));
-                    // prev instances except accumulators
-                    let start_idx = 4 * LIMBS * usize::from(self.has_prev_accumulator);
-                    for prev_instance in prev_instances {
-                        instances
-                            .extend(prev_instance[start_idx..].iter().map(|a| a.cell().clone()));
-                    }
-
-                    config.range().finalize(&mut loader.ctx_mut());
-                    #[cfg(feature = "display")]
-                    loader.ctx_mut().print_stats(&["Range"]);
-                    Ok(())
-                },
-            )
-            .unwrap();
+        layouter.assign_region(
+            || "",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let ctx = Context::new(
+                    region,
+                    ContextParams {
+                        max_rows: config.gate().max_rows,
+                        num_context_ids: 1,
+                        fixed_columns: config.gate().constants.clone(),
+                    },
+                );
+
+                let ecc_chip = config.ecc_chip();
+                let loader = Halo2Loader::new(ecc_chip, ctx);
+                let (prev_instances, acc) = aggregate::<LIMBS, BITS>(
+                    &self.aggregation.svk,
+                    &loader,
+                    &self.aggregation.snarks,
+                    self.aggregation.as_proof(),
+                )
+                .map_err(|err| {
+                    log::error!("failed to aggregate snarks: {err:?}");
+                    plonk::Error::Synthesis
+                })?;
+
+                // accumulator
+                instances.extend(flatten_accumulator(acc).iter().map(|a| a.cell().clone()));
+                // prev instances except accumulators
+                let start_idx = 4 * LIMBS * usize::from(self.has_prev_accumulator);
+                for prev_instance in prev_instances {
+                    instances.extend(prev_instance[start_idx..].iter().map(|a| a.cell().clone()));
+                }
+
+                config.range().finalize(&mut loader.ctx_mut());
+                #[cfg(feature = "display")]
+                loader.ctx_mut().print_stats(&["Range"]);
+                Ok(())
+            },
+        )?;
         // Expose instances
         for (i, cell) in instances.into_iter().enumerate() {
             layouter.constrain_instance(cell, config.instance, i)?;
@@ -0,0 +1,141 @@
+//! Tune [`AggregationConfigParams`] for the snarks to aggregate
+use halo2_base::halo2_proofs::{
+    circuit::{Layouter, Value},
+    dev::CircuitCost,
+    halo2curves::bn256::{Bn256, Fr, G1},
+    plonk::{self, Circuit, ConstraintSystem},
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use num_integer::Integer;
+use snark_verifier::loader::halo2::halo2_ecc::fields::fp::FpStrategy;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    aggregation::{
+        aggregation_circuit::AggregationCircuit,
+        config::{AggregationConfig, AggregationConfigParams},
+    },
+    Snark, SnarkWitness,
+};
+
+/// Degree of the circuit the layout pass runs in, which has a single column of each kind.
+const LAYOUT_DEGREE: u32 = 26;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Cells used by an aggregation circuit.
+pub struct AggregationCircuitStats {
+    /// Usable rows of each column
+    pub rows: usize,
+    /// Number of advice cells
+    pub advice_cells: usize,
+    /// Number of advice cells looked up in the range table
+    pub lookup_cells: usize,
+    /// Number of distinct constants, each of which takes a fixed cell
+    pub constants: usize,
+}
+
+/// Circuit that synthesizes `circuit` and records the cells it uses.
+struct LayoutProxy<const LIMBS: usize, const BITS: usize> {
+    circuit: AggregationCircuit<LIMBS, BITS>,
+    stats: Rc<RefCell<AggregationCircuitStats>>,
+}
+
+impl<const LIMBS: usize, const BITS: usize> Circuit<Fr> for LayoutProxy<LIMBS, BITS> {
+    type Params = AggregationConfigParams;
+    type Config = AggregationConfig;
+    type FloorPlanner = <AggregationCircuit<LIMBS, BITS> as Circuit<Fr>>::FloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { circuit: self.circuit.without_witnesses(), stats: self.stats.clone() }
+    }
+
+    fn params(&self) -> Self::Params {
+        self.circuit.params()
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<Fr>,
+        params: Self::Params,
+    ) -> Self::Config {
+        AggregationCircuit::<LIMBS, BITS>::configure_with_params(meta, params)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!("LayoutProxy is configured by configure_with_params")
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<Fr>,
+    ) -> Result<(), plonk::Error> {
+        *self.stats.borrow_mut() = self.circuit.synthesize_with_stats(config, layouter)?;
+        Ok(())
+    }
+}
+
+/// Returns the smallest [`AggregationConfigParams`] of degree `degree` for an
+/// [`AggregationCircuit`] aggregating `snarks`, with the cells it uses.
+///
+/// Only the protocols and the number of instances of `snarks` are used, since the circuit is
+/// laid out without witnesses, so dummy snarks from [`gen_dummy_snark`](crate::gen_dummy_snark)
+/// give the same result as the real ones. The lookup table takes `2^(degree - 1)` rows.
+///
+/// Panics if the circuit has more than `2^26` advice cells.
+pub fn tune_aggregation_config<const LIMBS: usize, const BITS: usize>(
+    params: &ParamsKZG<Bn256>,
+    snarks: impl IntoIterator<Item = Snark>,
+    degree: u32,
+) -> (AggregationConfigParams, AggregationCircuitStats) {
+    assert!(degree < LAYOUT_DEGREE, "Degree should be less than {LAYOUT_DEGREE}");
+
+    let config_params = |degree, num_advice, num_lookup_advice, num_fixed| {
+        AggregationConfigParams {
+            strategy: FpStrategy::Simple,
+            degree,
+            num_advice: vec![num_advice],
+            num_lookup_advice: vec![num_lookup_advice],
+            num_fixed,
+            // cells to range check depend on `lookup_bits`, so it's fixed during layout
+            lookup_bits: degree as usize - 1,
+            limb_bits: BITS,
+            num_limbs: LIMBS,
+        }
+    };
+
+    let proxy = LayoutProxy {
+        circuit: AggregationCircuit::<LIMBS, BITS> {
+            svk: params.get_g()[0].into(),
+            snarks: snarks
+                .into_iter()
+                .map(|snark| SnarkWitness::from(snark).without_witnesses())
+                .collect(),
+            instances: Vec::new(),
+            as_proof: Value::unknown(),
+            config_params: AggregationConfigParams {
+                degree: LAYOUT_DEGREE,
+                ..config_params(degree, 1, 1, 1)
+            },
+        },
+        stats: Default::default(),
+    };
+    CircuitCost::<G1, _>::measure(LAYOUT_DEGREE as _, &proxy);
+    let mut stats = *proxy.stats.borrow();
+
+    stats.rows = {
+        let mut meta = ConstraintSystem::default();
+        AggregationConfig::configure(&mut meta, config_params(degree, 1, 1, 1)).gate().max_rows
+    };
+    // at least one column of each kind is configured even if it's unused
+    let num_columns = |cells: usize| Integer::div_ceil(&cells, &stats.rows).max(1);
+
+    (
+        config_params(
+            degree,
+            num_columns(stats.advice_cells),
+            num_columns(stats.lookup_cells),
+            num_columns(stats.constants),
+        ),
+        stats,
+    )
+}
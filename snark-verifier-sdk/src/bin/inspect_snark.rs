@@ -0,0 +1,35 @@
+//! Prints the protocol of a `bincode` serialized SNARK, or the structural diff
+//! between protocols of two SNARKs.
+//!
+//! Usage: `inspect_snark <SNARK> [<OTHER_SNARK>]`
+
+use snark_verifier_sdk::read_snark;
+use std::{env, process};
+
+fn main() {
+    let paths = env::args().skip(1).collect::<Vec<_>>();
+    if paths.is_empty() || paths.len() > 2 {
+        eprintln!("Usage: inspect_snark <SNARK> [<OTHER_SNARK>]");
+        process::exit(1);
+    }
+
+    let snarks = paths
+        .iter()
+        .map(|path| {
+            read_snark(path).unwrap_or_else(|err| {
+                eprintln!("Failed to read SNARK from {path}: {err}");
+                process::exit(1);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    match snarks.as_slice() {
+        [snark] => {
+            println!("{}", snark.protocol);
+            println!("digest: 0x{}", hex::encode(snark.protocol.digest()));
+            println!("multi-open scheme: {:?}", snark.multi_open);
+        }
+        [lhs, rhs] => println!("{}", lhs.protocol.diff(&rhs.protocol)),
+        _ => unreachable!(),
+    }
+}
@@ -4,7 +4,7 @@ use crate::{circuit_ext::CircuitExt, types::Plonk};
 use ark_std::end_timer;
 #[cfg(feature = "display")]
 use ark_std::start_timer;
-use ethereum_types::Address;
+use ethereum_types::{Address, H256};
 use halo2_base::halo2_proofs::{
     halo2curves::bn256::{Bn256, Fq, Fr, G1Affine},
     plonk::{create_proof, verify_proof, Circuit, ProvingKey, VerifyingKey},
@@ -22,17 +22,28 @@ use halo2_base::halo2_proofs::{
 };
 use itertools::Itertools;
 use rand::Rng;
-pub use snark_verifier::loader::evm::encode_calldata;
+pub use snark_verifier::loader::evm::{
+    decode_accumulator, encode_batch_calldata, encode_calldata, encode_calldata_with_vk_address,
+    vk_contract_codehash, CalldataLayout, DebugArena, GasReport,
+};
 use snark_verifier::{
-    loader::evm::{compile_yul, EvmLoader, ExecutorBuilder},
+    cost::CostEstimation,
+    loader::{
+        evm::{
+            assemble_yul, calldata_layout, decode_revert_reason, encode_vk_data,
+            get_contract_address, vk_contract_deployment_code, EvmLoader, ExecutorBuilder,
+        },
+        native::NativeLoader,
+    },
     pcs::{
         kzg::{Bdfg21, Gwc19, Kzg, KzgAccumulator, KzgDecidingKey, KzgSuccinctVerifyingKey},
-        Decider, MultiOpenScheme, PolynomialCommitmentScheme,
+        Decider, MultiOpenScheme, PolynomialCommitmentScheme, Query,
     },
     system::halo2::{compile, transcript::evm::EvmTranscript, Config},
     verifier::PlonkVerifier,
+    Protocol,
 };
-use std::{fs, path::Path, rc::Rc};
+use std::{fs, iter, path::Path, rc::Rc};
 
 /// Generates a proof for evm verification using either SHPLONK or GWC proving method. Uses Keccak for Fiat-Shamir.
 pub fn gen_evm_proof<'params, C, P, V>(
@@ -118,12 +129,30 @@ pub fn gen_evm_proof_shplonk<'params, C: Circuit<Fr>>(
     gen_evm_proof::<C, ProverSHPLONK<_>, VerifierSHPLONK<_>>(params, pk, circuit, instances, rng)
 }
 
-pub fn gen_evm_verifier<C, PCS>(
+/// Generates the protocol of circuit `C` verified by the bytecode, to be
+/// passed to [`gen_evm_multi_verifier`].
+pub fn gen_evm_protocol<C: CircuitExt<Fr>>(
     params: &ParamsKZG<Bn256>,
     vk: &VerifyingKey<G1Affine>,
     num_instance: Vec<usize>,
-    path: Option<&Path>,
-) -> Vec<u8>
+) -> Protocol<G1Affine> {
+    compile(
+        params,
+        vk,
+        Config::kzg()
+            .with_num_instance(num_instance)
+            .with_accumulator_indices(C::accumulator_indices()),
+    )
+    .unwrap()
+}
+
+fn gen_evm_verifier_loader<C, PCS>(
+    loader: Rc<EvmLoader>,
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+    vk_contract: bool,
+) -> Rc<EvmLoader>
 where
     C: CircuitExt<Fr>,
     PCS: PolynomialCommitmentScheme<
@@ -138,37 +167,356 @@ where
 {
     let svk = params.get_g()[0].into();
     let dk = (params.g2(), params.s_g2()).into();
-    let protocol = compile(
+    let protocol = gen_evm_protocol::<C>(params, vk, num_instance.clone());
+
+    let (mut transcript, protocol) = if vk_contract {
+        EvmTranscript::<_, Rc<EvmLoader>, _, _>::new_with_vk_contract(&loader, &protocol)
+    } else {
+        let protocol = protocol.loaded(&loader);
+        (EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader), protocol)
+    };
+
+    let instances = transcript.load_instances(num_instance);
+    let proof = Plonk::<PCS>::read_proof(&svk, &protocol, &instances, &mut transcript).unwrap();
+    Plonk::<PCS>::verify(&svk, &dk, &protocol, &instances, &proof).unwrap();
+
+    loader
+}
+
+/// Returns layout of calldata expected by verifier from [`gen_evm_verifier`],
+/// which rejects calldata of any other length.
+pub fn gen_evm_calldata_layout<C, PCS>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+) -> CalldataLayout
+where
+    C: CircuitExt<Fr>,
+    PCS: MultiOpenScheme<G1Affine, NativeLoader> + CostEstimation<G1Affine, Input = Vec<Query<Fr>>>,
+{
+    let protocol = gen_evm_protocol::<C>(params, vk, num_instance);
+    calldata_layout::<G1Affine, PCS>(&protocol)
+}
+
+/// Generates the bytecode of a verifier of proofs of `C`. If `C` has
+/// [`CircuitExt::accumulator_indices`], its accumulator is decoded from
+/// instances with [`LimbsEncoding`](snark_verifier::pcs::kzg::LimbsEncoding) of
+/// [`LIMBS`](crate::LIMBS) limbs of [`BITS`](crate::BITS) bits, which is how
+/// SDK aggregation circuits expose it. Its Yul code is written to `path` if
+/// given.
+pub fn gen_evm_verifier<C, PCS>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+    path: Option<&Path>,
+) -> Result<Vec<u8>, String>
+where
+    C: CircuitExt<Fr>,
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    let loader = gen_evm_verifier_loader::<C, PCS>(
+        EvmLoader::new::<Fq, Fr>(),
         params,
         vk,
-        Config::kzg()
-            .with_num_instance(num_instance.clone())
-            .with_accumulator_indices(C::accumulator_indices()),
+        num_instance,
+        false,
     );
+    compile_evm_verifier(&loader, path)
+}
+
+/// Generates the bytecode of a verifier of `num_proof` proofs of the same
+/// circuit in one call, with calldata from [`encode_batch_calldata`]. Each
+/// proof is succinctly verified, then all the accumulators are combined with
+/// a random challenge into one for a single pairing check.
+pub fn gen_evm_batch_verifier<C, PCS>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+    num_proof: usize,
+    path: Option<&Path>,
+) -> Result<Vec<u8>, String>
+where
+    C: CircuitExt<Fr>,
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    assert!(num_proof > 0);
+
+    let protocol = gen_evm_protocol::<C>(params, vk, num_instance.clone());
+    gen_evm_multi_verifier::<PCS>(params, &vec![(protocol, num_instance); num_proof], path)
+}
+
+/// Generates the bytecode of a verifier of proofs of different circuits in
+/// one call, one proof for each of `protocols` with its number of instances,
+/// with calldata from [`encode_batch_calldata`] in the same order. Each proof
+/// is succinctly verified with its own preprocessed commitments, then all the
+/// accumulators are combined into one for a single pairing check, so all
+/// protocols should be from the same `params`.
+pub fn gen_evm_multi_verifier<PCS>(
+    params: &ParamsKZG<Bn256>,
+    protocols: &[(Protocol<G1Affine>, Vec<usize>)],
+    path: Option<&Path>,
+) -> Result<Vec<u8>, String>
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    assert!(!protocols.is_empty());
+
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
 
     let loader = EvmLoader::new::<Fq, Fr>();
-    let protocol = protocol.loaded(&loader);
-    let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader);
+    let accumulators = protocols
+        .iter()
+        .flat_map(|(protocol, num_instance)| {
+            // Each proof starts right after the calldata read so far
+            let (mut transcript, protocol) = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new_at(
+                &loader,
+                protocol,
+                loader.calldata_len(),
+            );
+            let instances = transcript.load_instances(num_instance.clone());
+            let proof =
+                Plonk::<PCS>::read_proof(&svk, &protocol, &instances, &mut transcript).unwrap();
+            Plonk::<PCS>::succinct_verify(&svk, &protocol, &instances, &proof).unwrap()
+        })
+        .collect();
+    PCS::decide_all(&dk, accumulators);
 
-    let instances = transcript.load_instances(num_instance);
-    let proof = Plonk::<PCS>::read_proof(&svk, &protocol, &instances, &mut transcript);
-    Plonk::<PCS>::verify(&svk, &dk, &protocol, &instances, &proof);
+    compile_evm_verifier(&loader, path)
+}
 
+/// Generates the bytecode of a verifier which emits a log with gas consumed
+/// by each section of verification, to be reported by
+/// [`verify_evm_calldata`]. The extra logs cost a few thousand gas, so it's
+/// meant for profiling rather than deployment.
+pub fn gen_evm_verifier_with_gas_metering<C, PCS>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+    path: Option<&Path>,
+) -> Result<Vec<u8>, String>
+where
+    C: CircuitExt<Fr>,
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    let loader = gen_evm_verifier_loader::<C, PCS>(
+        EvmLoader::new_with_gas_metering::<Fq, Fr>(),
+        params,
+        vk,
+        num_instance,
+        false,
+    );
+    compile_evm_verifier(&loader, path)
+}
+
+/// Generates the bytecode of a verifier which doesn't perform the final
+/// pairing check, but returns the KZG accumulator to be decoded by
+/// [`decode_accumulator`] from [`EvmVerification::output`]. The proof is only
+/// valid when the accumulator passes the pairing check, so a caller can fold
+/// accumulators from many verifiers into one pairing check.
+pub fn gen_evm_verifier_with_deferred_pairing<C, PCS>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+    path: Option<&Path>,
+) -> Result<Vec<u8>, String>
+where
+    C: CircuitExt<Fr>,
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    let loader = gen_evm_verifier_loader::<C, PCS>(
+        EvmLoader::new_with_deferred_pairing::<Fq, Fr>(),
+        params,
+        vk,
+        num_instance,
+        false,
+    );
+    compile_evm_verifier(&loader, path)
+}
+
+/// Assembles the verifier with [`assemble_yul`], so the bytecode doesn't
+/// depend on whether `solc` is installed. Its Yul code is written to `path` if
+/// given, to be compiled by `solc` instead.
+fn compile_evm_verifier(loader: &Rc<EvmLoader>, path: Option<&Path>) -> Result<Vec<u8>, String> {
     let yul_code = loader.yul_code();
-    let byte_code = compile_yul(&yul_code);
+    let byte_code = assemble_yul(&yul_code)?;
     if let Some(path) = path {
         path.parent().and_then(|dir| fs::create_dir_all(dir).ok()).unwrap();
         fs::write(path, yul_code).unwrap();
     }
-    byte_code
+    Ok(byte_code)
 }
 
-pub fn gen_evm_verifier_gwc<C: CircuitExt<Fr>>(
+/// Generates the bytecode of a verifier which reads preprocessed commitments
+/// and transcript initial state from a VK contract, so it's shared by circuits
+/// with the same constraint system. The VK contract comes from
+/// [`gen_evm_vk_contract`], and its address is appended to calldata by
+/// [`encode_calldata_with_vk_address`].
+///
+/// The verifier accepts proofs against whichever VK contract is in calldata,
+/// and returns its code hash as a word. Callers must compare it with
+/// [`vk_contract_codehash`] of [`gen_evm_vk_data`], otherwise a proof of any
+/// circuit with the same constraint system is accepted.
+pub fn gen_evm_verifier_with_vk_contract<C, PCS>(
     params: &ParamsKZG<Bn256>,
     vk: &VerifyingKey<G1Affine>,
     num_instance: Vec<usize>,
     path: Option<&Path>,
+) -> Result<Vec<u8>, String>
+where
+    C: CircuitExt<Fr>,
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    let loader = gen_evm_verifier_loader::<C, PCS>(
+        EvmLoader::new::<Fq, Fr>(),
+        params,
+        vk,
+        num_instance,
+        true,
+    );
+    compile_evm_verifier(&loader, path)
+}
+
+/// Generates the data of VK contract, for verifier from
+/// [`gen_evm_verifier_with_vk_contract`].
+pub fn gen_evm_vk_data<C: CircuitExt<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
 ) -> Vec<u8> {
+    encode_vk_data(&gen_evm_protocol::<C>(params, vk, num_instance))
+}
+
+/// Generates the deployment bytecode of VK contract, for verifier from
+/// [`gen_evm_verifier_with_vk_contract`].
+pub fn gen_evm_vk_contract<C: CircuitExt<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+) -> Vec<u8> {
+    vk_contract_deployment_code(&gen_evm_vk_data::<C>(params, vk, num_instance))
+}
+
+/// EVM verifier split into a chain of contracts by [`gen_evm_verifier_split`].
+#[derive(Clone, Debug)]
+pub struct SplitEvmVerifier {
+    /// Deployment bytecodes to be deployed in order by `deployer`, where the
+    /// first one is the driver.
+    pub deployment_codes: Vec<Vec<u8>>,
+    /// Account which deploys the contracts.
+    pub deployer: Address,
+    /// Nonce of `deployer` when deploying the first contract.
+    pub nonce: u64,
+    /// Address of the driver, which takes the same calldata as the verifier
+    /// from [`gen_evm_verifier`].
+    pub driver_address: Address,
+}
+
+/// Generates the verifier as a chain of contracts whose runtime bytecodes are
+/// at most `max_runtime_size` bytes each (e.g.
+/// [`MAX_CODE_SIZE`](snark_verifier::loader::evm::MAX_CODE_SIZE)), plus a
+/// driver calling them in order. Contract addresses are derived from
+/// `deployer` and its `nonce`, so the bytecodes must be deployed in order by
+/// it starting from that nonce. The driver is deployed first, since the other
+/// contracts only accept calls from it.
+pub fn gen_evm_verifier_split<C, PCS>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+    deployer: Address,
+    nonce: u64,
+    max_runtime_size: usize,
+) -> Result<SplitEvmVerifier, String>
+where
+    C: CircuitExt<Fr>,
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    let loader = gen_evm_verifier_loader::<C, PCS>(
+        EvmLoader::new::<Fq, Fr>(),
+        params,
+        vk,
+        num_instance,
+        false,
+    );
+
+    let driver_address = get_contract_address(deployer, nonce);
+    let parts = loader
+        .split_yul_code(max_runtime_size, driver_address)?
+        .iter()
+        .map(|part| assemble_yul(part))
+        .collect::<Result<Vec<_>, _>>()?;
+    let addresses = (nonce + 1..)
+        .take(parts.len())
+        .map(|nonce| get_contract_address(deployer, nonce))
+        .collect_vec();
+    let deployment_codes =
+        iter::once(assemble_yul(&loader.driver_yul_code(&addresses))?).chain(parts).collect();
+
+    Ok(SplitEvmVerifier { deployment_codes, deployer, nonce, driver_address })
+}
+
+pub fn gen_evm_verifier_gwc<C: CircuitExt<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+    path: Option<&Path>,
+) -> Result<Vec<u8>, String> {
     gen_evm_verifier::<C, Kzg<Bn256, Gwc19>>(params, vk, num_instance, path)
 }
 
@@ -177,22 +525,67 @@ pub fn gen_evm_verifier_shplonk<C: CircuitExt<Fr>>(
     vk: &VerifyingKey<G1Affine>,
     num_instance: Vec<usize>,
     path: Option<&Path>,
-) -> Vec<u8> {
+) -> Result<Vec<u8>, String> {
     gen_evm_verifier::<C, Kzg<Bn256, Bdfg21>>(params, vk, num_instance, path)
 }
 
 pub fn evm_verify(deployment_code: Vec<u8>, instances: Vec<Vec<Fr>>, proof: Vec<u8>) {
-    let success = verify_evm_proof(deployment_code, instances, proof);
+    let verification = verify_evm_calldata(deployment_code, encode_calldata(&instances, &proof));
 
-    assert!(success);
+    assert!(verification.address.is_some(), "Failed to deploy verifier");
+    assert!(
+        verification.accepted,
+        "Verification failed: {}",
+        verification.revert_reason().unwrap_or_else(|| "no revert reason".to_string())
+    );
 }
 
 pub fn verify_evm_proof(deployment_code: Vec<u8>, instances: Vec<Vec<Fr>>, proof: Vec<u8>) -> bool {
     let calldata = encode_calldata(&instances, &proof);
-    verify_evm_calldata(deployment_code, calldata)
+    verify_evm_calldata(deployment_code, calldata).accepted
+}
+
+/// Outcome of deploying a verifier and calling it in the in-process EVM by
+/// [`verify_evm_calldata`].
+#[derive(Debug)]
+pub struct EvmVerification {
+    /// Address of the verifier, or `None` if deployment fails.
+    pub address: Option<Address>,
+    /// Gas used by deployment.
+    pub deploy_gas_used: u64,
+    /// Whether the call returns without reverting, which is `false` if
+    /// deployment fails.
+    pub accepted: bool,
+    /// Gas used by the call.
+    pub gas_used: u64,
+    /// Return data of the call, empty when reverted.
+    pub output: Vec<u8>,
+    /// Revert data of the call, empty when accepted.
+    pub revert_data: Vec<u8>,
+    /// Gas report of the call, whose sections are only present when the
+    /// verifier is generated by [`gen_evm_verifier_with_gas_metering`].
+    pub report: GasReport,
+    /// Every step of the call, only recorded when it reverts.
+    pub trace: Option<DebugArena>,
 }
 
-pub fn verify_evm_calldata(deployment_code: Vec<u8>, calldata: Vec<u8>) -> bool {
+impl EvmVerification {
+    /// Returns revert reason of the call, which is one of [`INVALID_POINT`],
+    /// [`PAIRING_FAILED`], [`BAD_CALLDATA_LENGTH`] and
+    /// [`VERIFICATION_FAILED`] for generated verifiers.
+    ///
+    /// [`INVALID_POINT`]: snark_verifier::loader::evm::INVALID_POINT
+    /// [`PAIRING_FAILED`]: snark_verifier::loader::evm::PAIRING_FAILED
+    /// [`BAD_CALLDATA_LENGTH`]: snark_verifier::loader::evm::BAD_CALLDATA_LENGTH
+    /// [`VERIFICATION_FAILED`]: snark_verifier::loader::evm::VERIFICATION_FAILED
+    pub fn revert_reason(&self) -> Option<String> {
+        decode_revert_reason(&self.revert_data)
+    }
+}
+
+/// Deploys the verifier by `deployment_code` and calls it with `calldata`.
+/// When the call reverts, it's run again with debugger to record the trace.
+pub fn verify_evm_calldata(deployment_code: Vec<u8>, calldata: Vec<u8>) -> EvmVerification {
     let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
 
     let caller = Address::from_low_u64_be(0xfe);
@@ -200,13 +593,88 @@ pub fn verify_evm_calldata(deployment_code: Vec<u8>, calldata: Vec<u8>) -> bool
 
     let verifier_address = match deploy_result.address {
         None => {
-            panic!("deploy failed {deploy_result:?}");
+            log::error!("deploy failed {:?}", deploy_result.exit_reason);
+            return EvmVerification {
+                address: None,
+                deploy_gas_used: deploy_result.gas_used,
+                accepted: false,
+                gas_used: 0,
+                output: Vec::new(),
+                revert_data: Vec::new(),
+                report: GasReport::default(),
+                trace: None,
+            };
         }
         Some(verifier_address) => verifier_address,
     };
+    let calldata_len = calldata.len();
+    let result = evm.call_raw(caller, verifier_address, calldata.clone().into(), 0.into());
+
+    log::info!("gas used: {}", result.gas_used);
+
+    let (output, revert_data, trace) = if result.reverted {
+        evm.set_debugger(true);
+        let debug = evm.call_raw(caller, verifier_address, calldata.into(), 0.into()).debug;
+        (Vec::new(), result.result.to_vec(), debug)
+    } else {
+        (result.result.to_vec(), Vec::new(), None)
+    };
+
+    EvmVerification {
+        address: Some(verifier_address),
+        deploy_gas_used: deploy_result.gas_used,
+        accepted: !result.reverted,
+        gas_used: result.gas_used,
+        output,
+        revert_data,
+        report: GasReport::new(calldata_len, &result),
+        trace,
+    }
+}
+
+/// Deploys the verifier from [`gen_evm_verifier_with_vk_contract`] and the VK
+/// contract, and verifies the proof against it. The proof is only accepted if
+/// the code hash returned by the verifier is `vk_codehash`, which should be
+/// [`vk_contract_codehash`] of the expected [`gen_evm_vk_data`].
+pub fn verify_evm_proof_with_vk_contract(
+    deployment_code: Vec<u8>,
+    vk_deployment_code: Vec<u8>,
+    vk_codehash: H256,
+    instances: Vec<Vec<Fr>>,
+    proof: Vec<u8>,
+) -> bool {
+    let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+
+    let caller = Address::from_low_u64_be(0xfe);
+    let [verifier_address, vk_address] = [deployment_code, vk_deployment_code].map(|code| {
+        let deploy_result = evm.deploy(caller, code.into(), 0.into());
+        match deploy_result.address {
+            None => panic!("deploy failed {deploy_result:?}"),
+            Some(address) => address,
+        }
+    });
+    let calldata = encode_calldata_with_vk_address(&instances, &proof, vk_address);
     let result = evm.call_raw(caller, verifier_address, calldata.into(), 0.into());
 
     log::info!("gas used: {}", result.gas_used);
 
+    !result.reverted && result.result.as_ref() == vk_codehash.as_bytes()
+}
+
+pub fn verify_split_evm_calldata(verifier: &SplitEvmVerifier, calldata: Vec<u8>) -> bool {
+    let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+    evm.set_nonce(verifier.deployer, verifier.nonce);
+
+    for deployment_code in verifier.deployment_codes.iter() {
+        let deploy_result = evm.deploy(verifier.deployer, deployment_code.clone().into(), 0.into());
+        if deploy_result.address.is_none() {
+            panic!("deploy failed {deploy_result:?}");
+        }
+    }
+    let result =
+        evm.call_raw(verifier.deployer, verifier.driver_address, calldata.into(), 0.into());
+
+    log::info!("gas used: {}", result.gas_used);
+
     !result.reverted
 }
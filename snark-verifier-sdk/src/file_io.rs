@@ -1,5 +1,5 @@
 use std::{
-    fs::{write, File},
+    fs::{read, write, File},
     io::{BufReader, BufWriter},
     path::Path,
 };
@@ -10,9 +10,18 @@ use halo2_base::halo2_proofs::{
     SerdeFormat,
 };
 use itertools::Itertools;
-use snark_verifier::loader::evm::encode_calldata;
+use serde::Deserialize;
+use snark_verifier::{loader::evm::encode_calldata, Protocol};
 
-use crate::Snark;
+use crate::{MultiOpen, Snark};
+
+/// Layout of [`Snark`] before [`Snark::multi_open`] was added.
+#[derive(Deserialize)]
+struct LegacySnark {
+    protocol: Protocol<G1Affine>,
+    instances: Vec<Vec<Fr>>,
+    proof: Vec<u8>,
+}
 
 /// Read instances from the disk
 pub fn read_instances(path: impl AsRef<Path>) -> Result<Vec<Vec<Fr>>, bincode::Error> {
@@ -70,10 +79,49 @@ pub fn read_pk<C: Circuit<Fr>>(
 
 /// Tries to deserialize a SNARK from the specified `path` using `bincode`.
 ///
-/// WARNING: The user must keep track of whether the SNARK was generated using the GWC or SHPLONK multi-open scheme.
+/// SNARKs written before [`Snark::multi_open`] was added are rejected with an error saying so,
+/// since the scheme they are proven with isn't recorded. They can be migrated by
+/// [`read_legacy_snark`].
 pub fn read_snark(path: impl AsRef<Path>) -> Result<Snark, bincode::Error> {
-    let f = File::open(path).map_err(Box::<bincode::ErrorKind>::from)?;
-    bincode::deserialize_from(f)
+    let bytes = read(path)?;
+    bincode::deserialize(&bytes).map_err(|err| {
+        if bincode::deserialize::<LegacySnark>(&bytes).is_ok() {
+            Box::new(bincode::ErrorKind::Custom(
+                "SNARK is in the legacy format without multi-open scheme, migrate it by read_legacy_snark"
+                    .to_owned(),
+            ))
+        } else {
+            err
+        }
+    })
+}
+
+/// Deserializes a SNARK written before [`Snark::multi_open`] was added from the specified `path`,
+/// and attaches `multi_open`, which has to be the scheme it's proven with. Writing the returned
+/// [`Snark`] back with `bincode` migrates it to the current format.
+pub fn read_legacy_snark(
+    path: impl AsRef<Path>,
+    multi_open: MultiOpen,
+) -> Result<Snark, bincode::Error> {
+    let f = File::open(path)?;
+    let LegacySnark { protocol, instances, proof } = bincode::deserialize_from(BufReader::new(f))?;
+    Ok(Snark::new(protocol, instances, proof, multi_open))
+}
+
+/// Tries to deserialize a SNARK from the specified `path` using `bincode`, and rejects it if the
+/// digest of its protocol doesn't match `protocol_digest`, which can be computed from a verifying
+/// key by [`snark_verifier::system::halo2::protocol_digest`].
+pub fn read_snark_checked(
+    path: impl AsRef<Path>,
+    protocol_digest: [u8; 32],
+) -> Result<Snark, bincode::Error> {
+    let snark = read_snark(path)?;
+    if snark.protocol.digest() != protocol_digest {
+        return Err(Box::new(bincode::ErrorKind::Custom(
+            "Protocol of SNARK doesn't match the expected circuit".to_owned(),
+        )));
+    }
+    Ok(snark)
 }
 
 /// Write the calldata to disk
@@ -6,8 +6,9 @@ use std::{
 
 use crate::{
     circuit_ext::CircuitExt,
-    file_io::{read_pk, read_snark},
+    file_io::{read_pk, read_snark_checked},
     read_instances,
+    snark::HasMultiOpen,
     types::{PoseidonTranscript, POSEIDON_SPEC},
     write_instances, Snark,
 };
@@ -185,7 +186,10 @@ pub fn gen_proof_shplonk<C: Circuit<Fr>>(
 /// Generates a SNARK using either SHPLONK or GWC multi-open scheme. Uses Poseidon for Fiat-Shamir.
 ///
 /// Tries to first deserialize from / later serialize the entire SNARK into `path` if specified.
-/// Serialization is done using `bincode`.
+/// Serialization is done using `bincode`. A deserialized SNARK is only reused if its protocol
+/// matches the one compiled from `pk`, and it's proven with the multi-open scheme of `V`.
+/// Otherwise, including when it's in the legacy format read by
+/// [`read_legacy_snark`](crate::read_legacy_snark), a warning is logged and it's overwritten.
 pub fn gen_snark<'params, ConcreteCircuit, P, V>(
     params: &'params ParamsKZG<Bn256>,
     pk: &ProvingKey<G1Affine>,
@@ -197,30 +201,39 @@ where
     ConcreteCircuit: CircuitExt<Fr>,
     P: Prover<'params, KZGCommitmentScheme<Bn256>>,
     V: Verifier<
-        'params,
-        KZGCommitmentScheme<Bn256>,
-        Guard = GuardKZG<'params, Bn256>,
-        MSMAccumulator = DualMSM<'params, Bn256>,
-    >,
+            'params,
+            KZGCommitmentScheme<Bn256>,
+            Guard = GuardKZG<'params, Bn256>,
+            MSMAccumulator = DualMSM<'params, Bn256>,
+        > + HasMultiOpen,
 {
-    if let Some(path) = &path {
-        if let Ok(snark) = read_snark(path) {
-            return Ok(snark);
-        }
-    }
     let protocol = compile(
         params,
         pk.get_vk(),
         Config::kzg()
             .with_num_instance(circuit.num_instance())
             .with_accumulator_indices(ConcreteCircuit::accumulator_indices()),
-    );
+    )
+    .unwrap();
+    // Only reuse the cached SNARK if it's generated for the same circuit with the same scheme
+    if let Some(path) = path.as_ref().filter(|path| path.as_ref().exists()) {
+        match read_snark_checked(path, protocol.digest()) {
+            Ok(snark) if snark.multi_open == V::MULTI_OPEN => return Ok(snark),
+            Ok(snark) => log::warn!(
+                "regenerating SNARK at {:?} proven with {:?} instead of {:?}",
+                path.as_ref(),
+                snark.multi_open,
+                V::MULTI_OPEN
+            ),
+            Err(err) => log::warn!("regenerating SNARK at {:?}: {err}", path.as_ref()),
+        }
+    }
 
     let instances = circuit.instances();
     let proof =
         gen_proof::<ConcreteCircuit, P, V>(params, pk, circuit, instances.clone(), rng, None)?;
 
-    let snark = Snark::new(protocol, instances, proof);
+    let snark = Snark::new(protocol, instances, proof, V::MULTI_OPEN);
     if let Some(path) = &path {
         let f = File::create(path).unwrap();
         #[cfg(feature = "display")]
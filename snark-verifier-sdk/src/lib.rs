@@ -16,21 +16,27 @@ mod snark;
 pub mod types;
 
 pub use aggregation::aggregation_circuit::AggregationCircuit;
-pub use aggregation::load_verify_circuit_degree;
+pub use aggregation::config::AggregationConfigParams;
 pub use aggregation::multi_aggregation_circuit::PublicAggregationCircuit;
+pub use aggregation::tuning::{tune_aggregation_config, AggregationCircuitStats};
 pub use aggregation::{aggregate, flatten_accumulator};
+pub use aggregation::{load_verify_circuit_degree, load_verify_circuit_params};
 pub use circuit_ext::CircuitExt;
 pub use param::{BITS, LIMBS};
 pub use snark::gen_dummy_snark;
-pub use snark::{Snark, SnarkWitness};
+pub use snark::{HasMultiOpen, MultiOpen, Snark, SnarkWitness};
 
 pub use file_io::{
     // read instances from disk
     read_instances,
+    // read snark without multi-open scheme from disk
+    read_legacy_snark,
     // read pk from disk
     read_pk,
     // read snark from disk
     read_snark,
+    // read snark from disk and check its protocol matches the expected one
+    read_snark_checked,
     // write call date to disk
     write_calldata,
     // write instances to disk
@@ -39,26 +45,66 @@ pub use file_io::{
 
 #[cfg(feature = "loader_evm")]
 pub use evm_api::{
+    // decode the accumulator returned by the bytecode with deferred pairing
+    decode_accumulator,
+    // encode instances and proofs of several proofs as calldata
+    encode_batch_calldata,
     // encode instances and proofs as calldata
     encode_calldata,
+    // encode instances, proofs and address of VK contract as calldata
+    encode_calldata_with_vk_address,
     // verify instances and proofs with the bytecode
     evm_verify,
+    // generate the bytecode that verifies several proofs with one pairing
+    gen_evm_batch_verifier,
+    // generate the layout of calldata expected by the bytecode
+    gen_evm_calldata_layout,
+    // generate the bytecode that verifies proofs of different circuits with one pairing
+    gen_evm_multi_verifier,
     // generate evm proof with keccak that can be verified by bytecode
     gen_evm_proof,
     // generate snark proof with keccak and KZG-GWC that can be verified by bytecode
     gen_evm_proof_gwc,
     // generate evm proof with keccak and KZG-BDFG that can be verified by bytecode
     gen_evm_proof_shplonk,
+    // generate the protocol verified by bytecode
+    gen_evm_protocol,
     // generate the bytecode that verifies proofs
     gen_evm_verifier,
     // generate the bytecode that verifies proofs with keccak and KZG-GWC
     gen_evm_verifier_gwc,
     // generate the bytecode that verifies proofs with keccak and KZG-BDFG
     gen_evm_verifier_shplonk,
-    // verify calldata with the bytecode (returns bool)
+    // generate the bytecodes of the verifier split into a chain of contracts
+    gen_evm_verifier_split,
+    // generate the bytecode that returns the accumulator instead of the pairing check
+    gen_evm_verifier_with_deferred_pairing,
+    // generate the bytecode that verifies proofs and logs gas of each section
+    gen_evm_verifier_with_gas_metering,
+    // generate the bytecode that verifies proofs with VK read from VK contract
+    gen_evm_verifier_with_vk_contract,
+    // generate the deployment bytecode of VK contract
+    gen_evm_vk_contract,
+    // generate the data stored in VK contract
+    gen_evm_vk_data,
+    // verify calldata with the bytecode (returns the outcome)
     verify_evm_calldata,
     // verify instances and proofs with the bytecode (returns bool)
     verify_evm_proof,
+    // verify instances and proofs with the bytecode and VK contract (returns bool)
+    verify_evm_proof_with_vk_contract,
+    // verify calldata with the verifier split into a chain of contracts (returns bool)
+    verify_split_evm_calldata,
+    // code hash of VK contract returned by the bytecode with VK contract
+    vk_contract_codehash,
+    // layout of calldata expected by the bytecode
+    CalldataLayout,
+    // outcome of verifying calldata with the bytecode
+    EvmVerification,
+    // gas profiling report of verifying calldata
+    GasReport,
+    // the verifier split into a chain of contracts
+    SplitEvmVerifier,
 };
 #[cfg(feature = "loader_halo2")]
 pub use halo2_api::{
@@ -1,27 +1,69 @@
 use halo2_base::halo2_proofs;
 use halo2_proofs::{
     circuit::Value,
-    halo2curves::bn256::{Fr, G1Affine},
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    poly::kzg::multiopen::{VerifierGWC, VerifierSHPLONK},
 };
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use snark_verifier::Protocol;
+use snark_verifier::{
+    pcs::kzg::{Bdfg21, Gwc19, Kzg},
+    Protocol,
+};
 
 mod mock;
 
 pub use mock::gen_dummy_snark;
 
+/// KZG multi-open scheme a snark is proven with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MultiOpen {
+    /// GWC19, verified by `Kzg<Bn256, Gwc19>`
+    Gwc19,
+    /// SHPLONK, verified by `Kzg<Bn256, Bdfg21>`
+    Bdfg21,
+}
+
+/// Provers and verifiers of a particular [`MultiOpen`].
+pub trait HasMultiOpen {
+    const MULTI_OPEN: MultiOpen;
+}
+
+impl HasMultiOpen for Kzg<Bn256, Gwc19> {
+    const MULTI_OPEN: MultiOpen = MultiOpen::Gwc19;
+}
+
+impl HasMultiOpen for Kzg<Bn256, Bdfg21> {
+    const MULTI_OPEN: MultiOpen = MultiOpen::Bdfg21;
+}
+
+impl HasMultiOpen for VerifierGWC<'_, Bn256> {
+    const MULTI_OPEN: MultiOpen = MultiOpen::Gwc19;
+}
+
+impl HasMultiOpen for VerifierSHPLONK<'_, Bn256> {
+    const MULTI_OPEN: MultiOpen = MultiOpen::Bdfg21;
+}
+
 /// A Snark struct is all one may need to generate witnesses for an aggregation circuit.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Snark {
     pub protocol: Protocol<G1Affine>,
     pub instances: Vec<Vec<Fr>>,
     pub proof: Vec<u8>,
+    /// Scheme `proof` is opened with. Serialized SNARKs written before this field was added have
+    /// to be migrated by [`read_legacy_snark`](crate::read_legacy_snark).
+    pub multi_open: MultiOpen,
 }
 
 impl Snark {
-    pub fn new(protocol: Protocol<G1Affine>, instances: Vec<Vec<Fr>>, proof: Vec<u8>) -> Self {
-        Self { protocol, instances, proof }
+    pub fn new(
+        protocol: Protocol<G1Affine>,
+        instances: Vec<Vec<Fr>>,
+        proof: Vec<u8>,
+        multi_open: MultiOpen,
+    ) -> Self {
+        Self { protocol, instances, proof, multi_open }
     }
 }
 
@@ -35,6 +77,7 @@ impl From<Snark> for SnarkWitness {
                 .map(|instances| instances.into_iter().map(Value::known).collect_vec())
                 .collect(),
             proof: Value::known(snark.proof),
+            multi_open: snark.multi_open,
         }
     }
 }
@@ -45,6 +88,7 @@ pub struct SnarkWitness {
     pub protocol: Protocol<G1Affine>,
     pub instances: Vec<Vec<Value<Fr>>>,
     pub proof: Value<Vec<u8>>,
+    pub multi_open: MultiOpen,
 }
 
 impl SnarkWitness {
@@ -58,6 +102,7 @@ impl SnarkWitness {
                 .map(|instances| vec![Value::unknown(); instances.len()])
                 .collect(),
             proof: Value::unknown(),
+            multi_open: self.multi_open,
         }
     }
 
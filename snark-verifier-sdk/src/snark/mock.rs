@@ -1,7 +1,7 @@
 //! Mock Snark
 use crate::{circuit_ext::CircuitExt, types::PoseidonTranscript};
 
-use super::Snark;
+use super::{HasMultiOpen, Snark};
 #[cfg(feature = "display")]
 use ark_std::end_timer;
 #[cfg(feature = "display")]
@@ -28,15 +28,30 @@ use snark_verifier::{
 };
 use std::marker::PhantomData;
 
-struct CsProxy<F, C>(PhantomData<(F, C)>);
+/// Circuit with the constraint system of `C` configured by `params`, but without witnesses.
+struct CsProxy<F: Field, C: Circuit<F>> {
+    params: C::Params,
+    _marker: PhantomData<(F, C)>,
+}
 
-impl<F: Field, C: CircuitExt<F>> Circuit<F> for CsProxy<F, C> {
-    type Params = ();
+impl<F: Field, C: CircuitExt<F>> Circuit<F> for CsProxy<F, C>
+where
+    C::Params: Clone,
+{
+    type Params = C::Params;
     type Config = C::Config;
     type FloorPlanner = C::FloorPlanner;
 
     fn without_witnesses(&self) -> Self {
-        CsProxy(PhantomData)
+        CsProxy { params: self.params.clone(), _marker: PhantomData }
+    }
+
+    fn params(&self) -> Self::Params {
+        self.params.clone()
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
+        C::configure_with_params(meta, params)
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
@@ -64,26 +79,37 @@ impl<F: Field, C: CircuitExt<F>> Circuit<F> for CsProxy<F, C> {
 }
 
 /// Generate a Snark for a ConcreteCircuit
+///
+/// Without `vk`, a verifying key is generated from the constraint system of `ConcreteCircuit`
+/// configured by `circuit_params`, e.g. the [`AggregationConfigParams`] of an aggregation circuit.
+///
+/// [`AggregationConfigParams`]: crate::aggregation::config::AggregationConfigParams
 pub fn gen_dummy_snark<ConcreteCircuit, MOS>(
     params: &ParamsKZG<Bn256>,
     vk: Option<&VerifyingKey<G1Affine>>,
     num_instance: Vec<usize>,
+    circuit_params: ConcreteCircuit::Params,
 ) -> Snark
 where
     ConcreteCircuit: CircuitExt<Fr>,
+    ConcreteCircuit::Params: Clone,
     MOS: MultiOpenScheme<G1Affine, NativeLoader>
-        + CostEstimation<G1Affine, Input = Vec<pcs::Query<Fr>>>,
+        + CostEstimation<G1Affine, Input = Vec<pcs::Query<Fr>>>
+        + HasMultiOpen,
 {
-    let dummy_vk = vk
-        .is_none()
-        .then(|| keygen_vk(params, &CsProxy::<Fr, ConcreteCircuit>(PhantomData)).unwrap());
+    let dummy_vk = vk.is_none().then(|| {
+        let circuit =
+            CsProxy::<Fr, ConcreteCircuit> { params: circuit_params, _marker: PhantomData };
+        keygen_vk(params, &circuit).unwrap()
+    });
     let protocol = compile(
         params,
         vk.or(dummy_vk.as_ref()).unwrap(),
         Config::kzg()
             .with_num_instance(num_instance.clone())
             .with_accumulator_indices(ConcreteCircuit::accumulator_indices()),
-    );
+    )
+    .unwrap();
     let instances = num_instance.into_iter().map(|n| vec![Fr::default(); n]).collect();
     let proof = {
         let mut transcript = PoseidonTranscript::<NativeLoader, _>::new(Vec::new());
@@ -105,5 +131,5 @@ where
         transcript.finalize()
     };
 
-    Snark::new(protocol, instances, proof)
+    Snark::new(protocol, instances, proof, MOS::MULTI_OPEN)
 }
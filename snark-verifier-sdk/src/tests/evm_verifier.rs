@@ -1,34 +1,336 @@
-use super::TestCircuit1;
+use super::{TestCircuit1, TestCircuit2};
 use crate::{
-    evm_api::{evm_verify, gen_evm_proof_shplonk, gen_evm_verifier},
+    evm_api::{
+        decode_accumulator, encode_batch_calldata, encode_calldata, evm_verify,
+        gen_evm_batch_verifier, gen_evm_calldata_layout, gen_evm_multi_verifier,
+        gen_evm_proof_shplonk, gen_evm_protocol, gen_evm_verifier, gen_evm_verifier_split,
+        gen_evm_verifier_with_deferred_pairing, gen_evm_verifier_with_gas_metering,
+        gen_evm_verifier_with_vk_contract, gen_evm_vk_contract, gen_evm_vk_data,
+        verify_evm_calldata, verify_evm_proof_with_vk_contract, verify_split_evm_calldata,
+        vk_contract_codehash, GasReport,
+    },
     halo2_api::gen_pk,
     CircuitExt,
 };
 use ark_std::test_rng;
+use ethereum_types::{Address, U256};
 use halo2_base::halo2_proofs;
-use halo2_proofs::halo2curves::bn256::Bn256;
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::ProvingKey,
+    poly::kzg::commitment::ParamsKZG,
+};
+use rand::Rng;
 use snark_verifier::{
-    loader::halo2::halo2_ecc::halo2_base::utils::fs::gen_srs,
-    pcs::kzg::{Bdfg21, Kzg},
+    loader::{
+        evm::{modulus, BAD_CALLDATA_LENGTH, INVALID_INSTANCE, INVALID_POINT, PAIRING_FAILED},
+        halo2::halo2_ecc::halo2_base::utils::fs::gen_srs,
+    },
+    pcs::{
+        kzg::{Bdfg21, Kzg, KzgDecidingKey},
+        Decider,
+    },
 };
+use std::iter;
+
+/// Returns params, proving key and a random [`TestCircuit1`] with its SHPLONK
+/// proof for EVM, for tests of EVM verifiers.
+fn test_circuit_1_evm_proof(
+    rng: &mut (impl Rng + Send),
+) -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>, TestCircuit1, Vec<u8>) {
+    let params = gen_srs(8);
+
+    let circuit = TestCircuit1::rand(&mut *rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let proof = gen_evm_proof_shplonk(&params, &pk, circuit.clone(), circuit.instances(), rng);
+    (params, pk, circuit, proof)
+}
 
 #[test]
 fn test_evm_verification() {
-    std::env::set_var("VERIFY_CONFIG", "./configs/verify_circuit.config");
+    let (params, pk, circuit, proof) = test_circuit_1_evm_proof(&mut test_rng());
+    let deployment_code = gen_evm_verifier::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+        None,
+    )
+    .unwrap();
 
+    evm_verify(deployment_code, circuit.instances(), proof)
+}
+
+#[test]
+fn test_evm_batch_verification() {
+    // Lookup table of `TestCircuit1` contains its instance, so proofs verified
+    // by the same verifying key are of the same circuit
     let mut rng = test_rng();
-    let params = gen_srs(8);
+    let (params, pk, circuit, proof) = test_circuit_1_evm_proof(&mut rng);
+    let deployment_code = gen_evm_batch_verifier::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+        3,
+        None,
+    )
+    .unwrap();
 
-    let circuit = TestCircuit1::rand(&mut rng);
-    let pk = gen_pk(&params, &circuit, None);
-    let deployment_code = gen_evm_verifier::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+    let instances_and_proofs = iter::once(proof)
+        .chain((0..2).map(|_| {
+            gen_evm_proof_shplonk(&params, &pk, circuit.clone(), circuit.instances(), &mut rng)
+        }))
+        .map(|proof| (circuit.instances(), proof))
+        .collect::<Vec<_>>();
+    let verification =
+        verify_evm_calldata(deployment_code.clone(), encode_batch_calldata(&instances_and_proofs));
+    assert!(verification.accepted);
+
+    // Second proof with wrong instance
+    let wrong_instance = {
+        let mut instances_and_proofs = instances_and_proofs.clone();
+        instances_and_proofs[1].0[0][0] += Fr::one();
+        instances_and_proofs
+    };
+    let verification =
+        verify_evm_calldata(deployment_code.clone(), encode_batch_calldata(&wrong_instance));
+    assert_eq!(verification.revert_reason().as_deref(), Some(PAIRING_FAILED));
+
+    let verification =
+        verify_evm_calldata(deployment_code, encode_batch_calldata(&instances_and_proofs[..2]));
+    assert_eq!(verification.revert_reason().as_deref(), Some(BAD_CALLDATA_LENGTH));
+}
+
+#[test]
+fn test_evm_multi_verification() {
+    let mut rng = test_rng();
+    let (params, pk_1, circuit_1, proof_1) = test_circuit_1_evm_proof(&mut rng);
+
+    let circuit_2 = TestCircuit2::rand(&mut rng);
+    let pk_2 = gen_pk(&params, &circuit_2, None);
+    let proof_2 =
+        gen_evm_proof_shplonk(&params, &pk_2, circuit_2.clone(), circuit_2.instances(), &mut rng);
+    let protocol_1 = gen_evm_protocol::<TestCircuit1>(&params, pk_1.get_vk(), vec![1]);
+    let protocol_2 = gen_evm_protocol::<TestCircuit2>(&params, pk_2.get_vk(), vec![1]);
+    assert_ne!(protocol_1.preprocessed, protocol_2.preprocessed);
+
+    let deployment_code = gen_evm_multi_verifier::<Kzg<Bn256, Bdfg21>>(
+        &params,
+        &[(protocol_1, circuit_1.num_instance()), (protocol_2, circuit_2.num_instance())],
+        None,
+    )
+    .unwrap();
+
+    let instances_and_proofs =
+        vec![(circuit_1.instances(), proof_1), (circuit_2.instances(), proof_2)];
+    let verification =
+        verify_evm_calldata(deployment_code.clone(), encode_batch_calldata(&instances_and_proofs));
+    assert!(verification.accepted);
+
+    // Proofs in the wrong order
+    let reversed = instances_and_proofs.into_iter().rev().collect::<Vec<_>>();
+    let verification = verify_evm_calldata(deployment_code, encode_batch_calldata(&reversed));
+    assert!(!verification.accepted);
+}
+
+#[test]
+fn test_split_evm_verification() {
+    let (params, pk, circuit, proof) = test_circuit_1_evm_proof(&mut test_rng());
+    let verifier = gen_evm_verifier_split::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+        Address::from_low_u64_be(0xfe),
+        0,
+        0x1000,
+    )
+    .unwrap();
+    assert!(verifier.deployment_codes.len() > 2);
+
+    let instances = circuit.instances();
+    assert!(verify_split_evm_calldata(&verifier, encode_calldata(&instances, &proof)));
+}
+
+#[test]
+fn test_evm_verification_with_vk_contract() {
+    // Lookup table of `TestCircuit1` contains its instance, so these have the
+    // same shape but different verifying keys
+    let mut rng = test_rng();
+    let (params, pk, circuit, proof) = test_circuit_1_evm_proof(&mut rng);
+    let (_, other_pk, other_circuit, other_proof) = test_circuit_1_evm_proof(&mut rng);
+    let deployment_code = gen_evm_verifier_with_vk_contract::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
         &params,
         pk.get_vk(),
         circuit.num_instance(),
         None,
+    )
+    .unwrap();
+    let [vk_deployment_code, other_vk_deployment_code] = [&pk, &other_pk].map(|pk| {
+        gen_evm_vk_contract::<TestCircuit1>(&params, pk.get_vk(), circuit.num_instance())
+    });
+    let [vk_codehash, other_vk_codehash] = [&pk, &other_pk].map(|pk| {
+        vk_contract_codehash(&gen_evm_vk_data::<TestCircuit1>(
+            &params,
+            pk.get_vk(),
+            circuit.num_instance(),
+        ))
+    });
+    assert_ne!(vk_codehash, other_vk_codehash);
+
+    assert!(verify_evm_proof_with_vk_contract(
+        deployment_code.clone(),
+        vk_deployment_code,
+        vk_codehash,
+        circuit.instances(),
+        proof
+    ));
+
+    // Valid proof against the other VK contract is rejected by its code hash
+    let other_instances = other_circuit.instances();
+    assert!(verify_evm_proof_with_vk_contract(
+        deployment_code.clone(),
+        other_vk_deployment_code.clone(),
+        other_vk_codehash,
+        other_instances.clone(),
+        other_proof.clone()
+    ));
+    assert!(!verify_evm_proof_with_vk_contract(
+        deployment_code,
+        other_vk_deployment_code,
+        vk_codehash,
+        other_instances,
+        other_proof
+    ));
+}
+
+#[test]
+fn test_evm_verification_gas_report() {
+    let (params, pk, circuit, proof) = test_circuit_1_evm_proof(&mut test_rng());
+    let deployment_code = gen_evm_verifier_with_gas_metering::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+        None,
+    )
+    .unwrap();
+
+    let instances = circuit.instances();
+    let calldata = encode_calldata(&instances, &proof);
+    let calldata_len = calldata.len();
+    let verification = verify_evm_calldata(deployment_code, calldata);
+    assert!(verification.accepted);
+
+    let report = verification.report;
+
+    assert_eq!(report.calldata_len, calldata_len);
+    assert!(report.memory_size > 0);
+    assert_eq!(
+        report.sections.iter().map(|section| section.name.as_str()).collect::<Vec<_>>(),
+        ["read_proof", "common_polynomial_evaluation", "msm", "pairing"]
     );
+    assert!(report.sections.iter().map(|section| section.gas).sum::<u64>() < report.gas_used);
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(serde_json::from_str::<GasReport>(&json).unwrap(), report);
+}
+
+#[test]
+fn test_evm_verification_with_deferred_pairing() {
+    let (params, pk, circuit, proof) = test_circuit_1_evm_proof(&mut test_rng());
+    let deployment_code =
+        gen_evm_verifier_with_deferred_pairing::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+            &params,
+            pk.get_vk(),
+            circuit.num_instance(),
+            None,
+        )
+        .unwrap();
 
     let instances = circuit.instances();
-    let proof = gen_evm_proof_shplonk(&params, &pk, circuit.clone(), instances.clone(), &mut rng);
-    evm_verify(deployment_code.clone(), circuit.instances(), proof)
+    let calldata = encode_calldata(&instances, &proof);
+    let dk = KzgDecidingKey::<Bn256>::new(params.g2(), params.s_g2());
+
+    let verification = verify_evm_calldata(deployment_code.clone(), calldata.clone());
+    assert!(verification.accepted);
+    let accumulator = decode_accumulator::<G1Affine>(&verification.output).unwrap();
+    assert!(Kzg::<Bn256, Bdfg21>::decide(&dk, accumulator));
+
+    // Wrong instance passes the verifier, but not the deferred pairing check
+    let mut wrong_instance = calldata;
+    wrong_instance[0x1f] ^= 1;
+    let verification = verify_evm_calldata(deployment_code, wrong_instance);
+    assert!(verification.accepted);
+    let accumulator = decode_accumulator::<G1Affine>(&verification.output).unwrap();
+    assert!(!Kzg::<Bn256, Bdfg21>::decide(&dk, accumulator));
+
+    assert!(decode_accumulator::<G1Affine>(&[0; 0x60]).is_none());
+}
+
+#[test]
+fn test_evm_verification_revert_reasons() {
+    let (params, pk, circuit, proof) = test_circuit_1_evm_proof(&mut test_rng());
+    let deployment_code = gen_evm_verifier::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+        None,
+    )
+    .unwrap();
+
+    let instances = circuit.instances();
+    let calldata = encode_calldata(&instances, &proof);
+
+    let verification = verify_evm_calldata(deployment_code.clone(), calldata.clone());
+    assert!(verification.accepted);
+    assert!(verification.revert_data.is_empty() && verification.trace.is_none());
+
+    let layout = gen_evm_calldata_layout::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+    );
+    assert_eq!(layout.len, calldata.len());
+    assert_eq!(layout.instances, vec![vec![0]]);
+    assert_eq!(layout.commitments.last().unwrap() + 0x40, layout.len);
+
+    let tampered = |offset: usize, word: fn([u8; 32]) -> [u8; 32]| {
+        let mut calldata = calldata.clone();
+        let tampered = word(calldata[offset..offset + 0x20].try_into().unwrap());
+        calldata[offset..offset + 0x20].copy_from_slice(&tampered);
+        calldata
+    };
+    // Flip the last bit of y coordinate of the last commitment
+    let invalid_point = tampered(layout.commitments.last().unwrap() + 0x20, |mut word| {
+        word[31] ^= 1;
+        word
+    });
+    // Flip the last bit of the first instance
+    let wrong_instance = tampered(layout.instances[0][0], |mut word| {
+        word[31] ^= 1;
+        word
+    });
+    // Non-canonical encoding of the first instance, which is the same field
+    // element
+    let invalid_instance = tampered(layout.instances[0][0], |word| {
+        let mut tampered = [0; 32];
+        (U256::from_big_endian(&word) + modulus::<Fr>()).to_big_endian(&mut tampered);
+        tampered
+    });
+    let short_calldata = calldata[..calldata.len() - 0x20].to_vec();
+    let long_calldata = [calldata.as_slice(), &[0]].concat();
+    for (calldata, reason) in [
+        (invalid_point, INVALID_POINT),
+        (wrong_instance, PAIRING_FAILED),
+        (invalid_instance, INVALID_INSTANCE),
+        (short_calldata, BAD_CALLDATA_LENGTH),
+        (long_calldata, BAD_CALLDATA_LENGTH),
+    ] {
+        let verification = verify_evm_calldata(deployment_code.clone(), calldata);
+        assert!(!verification.accepted);
+        assert_eq!(verification.revert_reason().as_deref(), Some(reason));
+        assert!(verification.trace.is_some());
+    }
+
+    // Deployment code which reverts
+    let verification = verify_evm_calldata(vec![0x60, 0x00, 0x80, 0xfd], calldata);
+    assert!(verification.address.is_none() && !verification.accepted);
 }
@@ -1,6 +1,6 @@
 use super::{TestCircuit1, TestCircuit2};
 use crate::{
-    aggregation::aggregation_circuit::AggregationCircuit,
+    aggregation::{aggregation_circuit::AggregationCircuit, config::AggregationConfigParams},
     evm_api::{evm_verify, gen_evm_proof_shplonk, gen_evm_verifier},
     halo2_api::{gen_pk, gen_snark_shplonk},
     CircuitExt,
@@ -16,7 +16,10 @@ use std::path::Path;
 
 #[test]
 fn test_shplonk_then_sphplonk_with_evm_verification() {
-    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
+    let config_params = AggregationConfigParams::from_path(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("configs/example_evm_accumulator.config"),
+    )
+    .unwrap();
     let k = 8;
     let k_agg = 24;
 
@@ -69,7 +72,9 @@ fn test_shplonk_then_sphplonk_with_evm_verification() {
 
     // aggregation circuit
     let snarks = vec![snarks_1, snarks_2, snarks_3];
-    let agg_circuit = AggregationCircuit::new(&params_outer, snarks, &mut rng);
+    let agg_circuit =
+        AggregationCircuit::new_with_config(config_params, &params_outer, snarks, None, &mut rng)
+            .unwrap();
     let pk_outer = gen_pk(&params_outer, &agg_circuit, Some(Path::new("data/outer.pkey")));
     println!("finished outer pk generation");
     let instances = agg_circuit.instances();
@@ -87,8 +92,295 @@ fn test_shplonk_then_sphplonk_with_evm_verification() {
         pk_outer.get_vk(),
         agg_circuit.num_instance(),
         Some(Path::new("data/single_layer_recur.sol")),
-    );
+    )
+    .unwrap();
 
     println!("finished bytecode generation");
     evm_verify(deployment_code, instances, proof)
 }
+
+#[test]
+fn test_aggregation_circuit_limbs() {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    let num_accumulator_limbs =
+        |indices: Option<Vec<(usize, usize)>>| indices.map(|indices| indices.len());
+    assert_eq!(
+        num_accumulator_limbs(<AggregationCircuit as CircuitExt<Fr>>::accumulator_indices()),
+        Some(4 * crate::LIMBS)
+    );
+    assert_eq!(num_accumulator_limbs(AggregationCircuit::<4, 64>::accumulator_indices()), Some(16));
+}
+
+#[test]
+#[should_panic(expected = "but the circuit expects 4 limbs of 64 bits")]
+fn test_aggregation_circuit_limbs_mismatch() {
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem};
+
+    // Default config has 3 limbs of 88 bits
+    AggregationCircuit::<4, 64>::configure_with_params(
+        &mut ConstraintSystem::default(),
+        AggregationConfigParams::default(),
+    );
+}
+
+#[test]
+fn test_aggregation_circuits_with_different_configs() {
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem};
+
+    let num_advice_columns = |config_params: AggregationConfigParams| {
+        let mut meta = ConstraintSystem::default();
+        AggregationCircuit::<{ crate::LIMBS }, { crate::BITS }>::configure_with_params(
+            &mut meta,
+            config_params,
+        );
+        meta.num_advice_columns()
+    };
+    let config_params = AggregationConfigParams::default();
+    let wider_config_params = AggregationConfigParams {
+        num_advice: vec![config_params.num_advice[0] + 1],
+        ..config_params.clone()
+    };
+    assert_eq!(num_advice_columns(wider_config_params), num_advice_columns(config_params) + 1);
+}
+
+#[test]
+fn test_tune_aggregation_config() {
+    use crate::{gen_dummy_snark, tune_aggregation_config};
+    use halo2_proofs::dev::MockProver;
+
+    let k = 8;
+    let k_agg = 22;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk_inner = gen_pk(&params_inner, &circuit, None);
+    let snark =
+        gen_snark_shplonk(&params_inner, &pk_inner, circuit.clone(), &mut rng, None::<&str>)
+            .unwrap();
+    let dummy_snark = gen_dummy_snark::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params_inner,
+        Some(pk_inner.get_vk()),
+        circuit.num_instance(),
+        (),
+    );
+
+    let (config_params, stats) = tune_aggregation_config::<{ crate::LIMBS }, { crate::BITS }>(
+        &params_outer,
+        [snark.clone()],
+        k_agg,
+    );
+    let (dummy_config_params, dummy_stats) = tune_aggregation_config::<
+        { crate::LIMBS },
+        { crate::BITS },
+    >(&params_outer, [dummy_snark], k_agg);
+    assert_eq!(stats, dummy_stats);
+    assert_eq!(config_params.num_advice, dummy_config_params.num_advice);
+    assert_eq!(config_params.lookup_bits, k_agg as usize - 1);
+    assert!(config_params.num_advice[0] * stats.rows >= stats.advice_cells);
+    // one column fewer of any kind can't hold the cells
+    assert!((config_params.num_advice[0] - 1) * stats.rows < stats.advice_cells);
+    assert!((config_params.num_lookup_advice[0] - 1) * stats.rows < stats.lookup_cells);
+    assert!((config_params.num_fixed - 1) * stats.rows < stats.constants);
+
+    // the tuned config fits the circuit
+    let agg_circuit =
+        AggregationCircuit::new_with_config(config_params, &params_outer, [snark], None, &mut rng)
+            .unwrap();
+    MockProver::run(k_agg, &agg_circuit, agg_circuit.instances()).unwrap().assert_satisfied();
+}
+
+#[test]
+fn test_aggregation_circuit_with_non_default_limbs() {
+    use crate::tune_aggregation_config;
+    use halo2_proofs::dev::MockProver;
+
+    let k = 8;
+    let k_agg = 22;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk_inner = gen_pk(&params_inner, &circuit, None);
+    let snark =
+        gen_snark_shplonk(&params_inner, &pk_inner, circuit, &mut rng, None::<&str>).unwrap();
+
+    let (config_params, _) =
+        tune_aggregation_config::<4, 64>(&params_outer, [snark.clone()], k_agg);
+    assert_eq!((config_params.num_limbs, config_params.limb_bits), (4, 64));
+
+    let agg_circuit = AggregationCircuit::<4, 64>::new_with_limbs(
+        config_params,
+        &params_outer,
+        [snark],
+        None,
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(agg_circuit.num_instance(), vec![16]);
+    MockProver::run(k_agg, &agg_circuit, agg_circuit.instances()).unwrap().assert_satisfied();
+}
+
+#[test]
+fn test_dummy_aggregation_snark() {
+    use crate::gen_dummy_snark;
+    use snark_verifier::loader::halo2::halo2_ecc::fields::fp::FpStrategy;
+
+    let k_agg = 14;
+    let params = gen_srs(k_agg);
+    let config_params = AggregationConfigParams {
+        strategy: FpStrategy::Simple,
+        degree: k_agg,
+        num_advice: vec![4],
+        num_lookup_advice: vec![1],
+        num_fixed: 1,
+        lookup_bits: k_agg as usize - 1,
+        limb_bits: crate::BITS,
+        num_limbs: crate::LIMBS,
+    };
+
+    // aggregation circuit is only configured by its params
+    let snark = gen_dummy_snark::<AggregationCircuit, Kzg<Bn256, Bdfg21>>(
+        &params,
+        None,
+        vec![4 * crate::LIMBS],
+        config_params,
+    );
+    assert_eq!(snark.protocol.domain.k, k_agg as usize);
+    assert_eq!(snark.protocol.num_instance, vec![4 * crate::LIMBS]);
+    assert_eq!(snark.instances, vec![vec![Default::default(); 4 * crate::LIMBS]]);
+}
+
+#[test]
+fn test_aggregation_of_gwc_and_shplonk_snarks() {
+    use crate::{halo2_api::gen_snark_gwc, tune_aggregation_config, MultiOpen};
+    use halo2_proofs::dev::MockProver;
+
+    let k = 8;
+    let k_agg = 22;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+
+    let circuit_1 = TestCircuit1::rand(&mut rng);
+    let pk_inner_1 = gen_pk(&params_inner, &circuit_1, None);
+    let snark_gwc =
+        gen_snark_gwc(&params_inner, &pk_inner_1, circuit_1, &mut rng, None::<&str>).unwrap();
+    assert_eq!(snark_gwc.multi_open, MultiOpen::Gwc19);
+
+    let circuit_2 = TestCircuit2::rand(&mut rng);
+    let pk_inner_2 = gen_pk(&params_inner, &circuit_2, None);
+    let snark_shplonk =
+        gen_snark_shplonk(&params_inner, &pk_inner_2, circuit_2, &mut rng, None::<&str>).unwrap();
+    assert_eq!(snark_shplonk.multi_open, MultiOpen::Bdfg21);
+
+    let snarks = vec![snark_gwc, snark_shplonk];
+    let (config_params, _) = tune_aggregation_config::<{ crate::LIMBS }, { crate::BITS }>(
+        &params_outer,
+        snarks.clone(),
+        k_agg,
+    );
+    let agg_circuit =
+        AggregationCircuit::new_with_config(config_params, &params_outer, snarks, None, &mut rng)
+            .unwrap();
+    MockProver::run(k_agg, &agg_circuit, agg_circuit.instances()).unwrap().assert_satisfied();
+}
+
+#[test]
+fn test_read_legacy_snark() {
+    use crate::{read_legacy_snark, read_snark, MultiOpen};
+    use std::fs::{create_dir_all, File};
+
+    let k = 8;
+    create_dir_all("data").unwrap();
+    let path = Path::new("data/legacy.snark");
+
+    let mut rng = test_rng();
+    let params = gen_srs(k);
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let snark = gen_snark_shplonk(&params, &pk, circuit, &mut rng, None::<&str>).unwrap();
+
+    // Layout of a SNARK serialized before `multi_open` was added
+    bincode::serialize_into(
+        File::create(path).unwrap(),
+        &(&snark.protocol, &snark.instances, &snark.proof),
+    )
+    .unwrap();
+    let err = read_snark(path).unwrap_err();
+    assert!(err.to_string().contains("legacy format"));
+
+    let migrated = read_legacy_snark(path, MultiOpen::Bdfg21).unwrap();
+    assert_eq!(migrated.protocol.digest(), snark.protocol.digest());
+    assert_eq!(migrated.instances, snark.instances);
+    assert_eq!(migrated.proof, snark.proof);
+    assert_eq!(migrated.multi_open, MultiOpen::Bdfg21);
+
+    // Serializing the migrated SNARK upgrades it to the current format
+    bincode::serialize_into(File::create(path).unwrap(), &migrated).unwrap();
+    assert_eq!(read_snark(path).unwrap().multi_open, MultiOpen::Bdfg21);
+}
+
+#[test]
+fn test_aggregation_circuit_protocol_digests() {
+    use snark_verifier::{
+        system::halo2::{protocol_digest, Config},
+        Error,
+    };
+
+    let k = 8;
+    let mut rng = test_rng();
+    let params = gen_srs(k);
+
+    let circuit_1 = TestCircuit1::rand(&mut rng);
+    let circuit_2 = TestCircuit2::rand(&mut rng);
+    let pk_1 = gen_pk(&params, &circuit_1, None);
+    let pk_2 = gen_pk(&params, &circuit_2, None);
+    let config = |num_instance| {
+        Config::kzg()
+            .with_num_instance(num_instance)
+            .with_accumulator_indices(TestCircuit1::accumulator_indices())
+    };
+    let protocol_digests = [
+        protocol_digest(&params, pk_1.get_vk(), config(circuit_1.num_instance())).unwrap(),
+        protocol_digest(&params, pk_2.get_vk(), config(circuit_2.num_instance())).unwrap(),
+    ];
+    let snarks = [
+        gen_snark_shplonk(&params, &pk_1, circuit_1, &mut rng, None::<&str>).unwrap(),
+        gen_snark_shplonk(&params, &pk_2, circuit_2, &mut rng, None::<&str>).unwrap(),
+    ];
+
+    let mut new = |protocol_digests: Option<&[[u8; 32]]>| {
+        AggregationCircuit::new_with_config(
+            AggregationConfigParams::default(),
+            &params,
+            snarks.clone(),
+            protocol_digests,
+            &mut rng,
+        )
+    };
+    assert!(new(None).is_ok());
+    assert!(new(Some(&protocol_digests)).is_ok());
+    // Snarks in the wrong order, or digests not one per snark
+    let swapped = [protocol_digests[1], protocol_digests[0]];
+    assert!(matches!(new(Some(&swapped)), Err(Error::AssertionFailure(_))));
+    assert!(matches!(new(Some(&protocol_digests[..1])), Err(Error::AssertionFailure(_))));
+}
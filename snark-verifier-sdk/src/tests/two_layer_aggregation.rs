@@ -1,6 +1,6 @@
 use super::TestCircuit1;
 use crate::{
-    aggregation::aggregation_circuit::AggregationCircuit,
+    aggregation::{aggregation_circuit::AggregationCircuit, config::AggregationConfigParams},
     evm_api::{evm_verify, gen_evm_proof_shplonk, gen_evm_verifier},
     halo2_api::{gen_pk, gen_snark_shplonk},
     CircuitExt,
@@ -16,7 +16,10 @@ use std::path::Path;
 
 #[test]
 fn test_two_layer_aggregation_evm_verification() {
-    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
+    let config_params = AggregationConfigParams::from_path(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("configs/example_evm_accumulator.config"),
+    )
+    .unwrap();
     let k = 8;
     let k_agg = 21;
 
@@ -46,7 +49,14 @@ fn test_two_layer_aggregation_evm_verification() {
     println!("finished snark generation");
 
     // layer 2, first aggregation
-    let first_agg_circuit = AggregationCircuit::new(&params_outer, snarks, &mut rng);
+    let first_agg_circuit = AggregationCircuit::new_with_config(
+        config_params.clone(),
+        &params_outer,
+        snarks,
+        None,
+        &mut rng,
+    )
+    .unwrap();
     let pk_outer = gen_pk(&params_outer, &first_agg_circuit, None);
     println!("finished outer pk generation");
     let first_agg_proof = gen_snark_shplonk(
@@ -60,7 +70,14 @@ fn test_two_layer_aggregation_evm_verification() {
     println!("finished outer proof generation");
 
     // layer 3, second aggregation
-    let second_agg_circuit = AggregationCircuit::new(&params_outer, [first_agg_proof], &mut rng);
+    let second_agg_circuit = AggregationCircuit::new_with_config(
+        config_params,
+        &params_outer,
+        [first_agg_proof],
+        None,
+        &mut rng,
+    )
+    .unwrap();
     let pk_agg = gen_pk(&params_outer, &second_agg_circuit, None);
 
     let deployment_code = gen_evm_verifier::<AggregationCircuit, Kzg<Bn256, Bdfg21>>(
@@ -68,7 +85,8 @@ fn test_two_layer_aggregation_evm_verification() {
         pk_agg.get_vk(),
         second_agg_circuit.num_instance(),
         Some(Path::new("data/two_layer_recur.sol")),
-    );
+    )
+    .unwrap();
     let proof = gen_evm_proof_shplonk(
         &params_outer,
         &pk_agg,
@@ -1,11 +1,10 @@
 //! This module concretize generic types with Bn256 curve and BDFG KZG scheme.
 
-use super::{BITS, LIMBS};
 use halo2_base::halo2_proofs::halo2curves::bn256::{Bn256, Fr, G1Affine};
 use snark_verifier::{
     loader::halo2::{halo2_ecc::ecc::BaseFieldEccChip as EccChip, Halo2Loader as Loader},
     pcs::kzg::{
-        Bdfg21, Kzg, KzgAs as KzgAccumulationScheme, KzgSuccinctVerifyingKey, LimbsEncoding,
+        Bdfg21, Gwc19, Kzg, KzgAs as KzgAccumulationScheme, KzgSuccinctVerifyingKey, LimbsEncoding,
     },
     verifier, PoseidonSpec,
 };
@@ -28,9 +27,14 @@ pub type PoseidonTranscript<L, S> =
         R_P,
     >;
 
-/// Plonk configured with PCS.
+/// Plonk configured with PCS and accumulator decomposed into `LIMBS` limbs of
+/// `BITS` bits.
 /// PCS is either `Kzg<Bn256, Gwc19>` or `Kzg<Bn256, Bdfg21>`
-pub type Plonk<PCS> = verifier::Plonk<PCS, LimbsEncoding<LIMBS, BITS>>;
+pub type Plonk<PCS, const LIMBS: usize = { crate::LIMBS }, const BITS: usize = { crate::BITS }> =
+    verifier::Plonk<PCS, LimbsEncoding<LIMBS, BITS>>;
+
+/// KZG instantiated with GWC19
+pub type KzgGWC = Kzg<Bn256, Gwc19>;
 
 /// KZG instantiated with BDFG21
 pub type KzgBDFG = Kzg<Bn256, Bdfg21>;
@@ -324,8 +324,8 @@ mod aggregation {
                 let instances = assign_instances(&snark.instances);
                 let mut transcript =
                     PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, snark.proof());
-                let proof = Plonk::read_proof(svk, &protocol, &instances, &mut transcript);
-                Plonk::succinct_verify(svk, &protocol, &instances, &proof)
+                let proof = Plonk::read_proof(svk, &protocol, &instances, &mut transcript).unwrap();
+                Plonk::succinct_verify(svk, &protocol, &instances, &proof).unwrap()
             })
             .collect_vec();
 
@@ -412,8 +412,9 @@ mod aggregation {
                     let mut transcript =
                         PoseidonTranscript::<NativeLoader, _>::new(snark.proof.as_slice());
                     let proof =
-                        Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript);
-                    Plonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof)
+                        Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript)
+                            .unwrap();
+                    Plonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof).unwrap()
                 })
                 .collect_vec();
 
@@ -606,7 +607,8 @@ fn gen_application_snark(params: &ParamsKZG<Bn256>) -> aggregation::Snark {
         params,
         pk.get_vk(),
         Config::kzg().with_num_instance(application::StandardPlonk::num_instance()),
-    );
+    )
+    .unwrap();
 
     let proof = gen_proof::<
         _,
@@ -631,17 +633,18 @@ fn gen_aggregation_evm_verifier(
         Config::kzg()
             .with_num_instance(num_instance.clone())
             .with_accumulator_indices(Some(accumulator_indices)),
-    );
+    )
+    .unwrap();
 
     let loader = EvmLoader::new::<Fq, Fr>();
     let protocol = protocol.loaded(&loader);
     let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader);
 
     let instances = transcript.load_instances(num_instance);
-    let proof = Plonk::read_proof(&svk, &protocol, &instances, &mut transcript);
-    Plonk::verify(&svk, &dk, &protocol, &instances, &proof);
+    let proof = Plonk::read_proof(&svk, &protocol, &instances, &mut transcript).unwrap();
+    Plonk::verify(&svk, &dk, &protocol, &instances, &proof).unwrap();
 
-    evm::compile_yul(&loader.yul_code())
+    evm::compile_yul(&loader.yul_code()).unwrap()
 }
 
 fn evm_verify(deployment_code: Vec<u8>, instances: Vec<Vec<Fr>>, proof: Vec<u8>) {
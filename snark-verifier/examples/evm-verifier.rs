@@ -237,7 +237,8 @@ fn gen_evm_verifier(
 ) -> Vec<u8> {
     let svk = params.get_g()[0].into();
     let dk = (params.g2(), params.s_g2()).into();
-    let protocol = compile(params, vk, Config::kzg().with_num_instance(num_instance.clone()));
+    let protocol =
+        compile(params, vk, Config::kzg().with_num_instance(num_instance.clone())).unwrap();
 
     let loader = EvmLoader::new::<Fq, Fr>();
     let protocol = protocol.loaded(&loader);
@@ -246,7 +247,7 @@ fn gen_evm_verifier(
 
     let instances = transcript.load_instances(num_instance);
 
-    let proof = Plonk::read_proof(&svk, &protocol, &instances, &mut transcript);
+    let proof = Plonk::read_proof(&svk, &protocol, &instances, &mut transcript).unwrap();
 
     // println!("svk: {:?}", svk);
     // println!("dk: {:?}", svk);
@@ -254,9 +255,9 @@ fn gen_evm_verifier(
     // println!("instances: {:?}", instances);
     // println!("proof {:?}", proof);
 
-    Plonk::verify(&svk, &dk, &protocol, &instances, &proof);
+    Plonk::verify(&svk, &dk, &protocol, &instances, &proof).unwrap();
 
-    evm::compile_yul(&loader.yul_code())
+    evm::compile_yul(&loader.yul_code()).unwrap()
 }
 
 fn evm_verify(deployment_code: Vec<u8>, instances: Vec<Vec<Fr>>, proof: Vec<u8>) -> bool {
@@ -209,7 +209,8 @@ mod common {
             Config::kzg()
                 .with_num_instance(ConcreteCircuit::num_instance())
                 .with_accumulator_indices(ConcreteCircuit::accumulator_indices()),
-        );
+        )
+        .unwrap();
 
         let instances = circuit.instances();
         let proof = gen_proof(params, pk, circuit, instances.clone());
@@ -265,7 +266,8 @@ mod common {
             Config::kzg()
                 .with_num_instance(ConcreteCircuit::num_instance())
                 .with_accumulator_indices(ConcreteCircuit::accumulator_indices()),
-        );
+        )
+        .unwrap();
         let instances = ConcreteCircuit::num_instance()
             .into_iter()
             .map(|n| iter::repeat_with(|| Fr::random(OsRng)).take(n).collect())
@@ -408,8 +410,8 @@ mod recursion {
             })
             .collect_vec();
         let mut transcript = PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, snark.proof());
-        let proof = Plonk::read_proof(svk, &protocol, &instances, &mut transcript);
-        let accumulators = Plonk::succinct_verify(svk, &protocol, &instances, &proof);
+        let proof = Plonk::read_proof(svk, &protocol, &instances, &mut transcript).unwrap();
+        let accumulators = Plonk::succinct_verify(svk, &protocol, &instances, &proof).unwrap();
 
         (
             instances
@@ -541,8 +543,9 @@ mod recursion {
                 let mut transcript =
                     PoseidonTranscript::<NativeLoader, _>::new(snark.proof.as_slice());
                 let proof =
-                    Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript);
-                Plonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof)
+                    Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript)
+                        .unwrap();
+                Plonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof).unwrap()
             };
 
             let accumulators = iter::empty()
@@ -908,8 +911,9 @@ fn main() {
         let svk = recursion_params.get_g()[0].into();
         let dk = (recursion_params.g2(), recursion_params.s_g2()).into();
         let mut transcript = PoseidonTranscript::<NativeLoader, _>::new(snark.proof.as_slice());
-        let proof = Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript);
-        Plonk::verify(&svk, &dk, &snark.protocol, &snark.instances, &proof)
+        let proof =
+            Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript).unwrap();
+        Plonk::verify(&svk, &dk, &snark.protocol, &snark.instances, &proof).unwrap()
     };
     assert!(accept)
 }
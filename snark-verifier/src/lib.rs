@@ -32,6 +32,8 @@ pub enum Error {
     AssertionFailure(String),
     /// Transcript error.
     Transcript(std::io::ErrorKind, String),
+    /// Constraint system feature not supported by protocol compilation.
+    UnsupportedFeature(String),
 }
 
 /// Various parameters for the aggregation circuit.
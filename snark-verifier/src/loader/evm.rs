@@ -5,10 +5,18 @@ mod util;
 #[cfg(test)]
 mod test;
 
+pub use code::{
+    BAD_CALLDATA_ENCODING, BAD_CALLDATA_LENGTH, BAD_CALLER, INVALID_INSTANCE, INVALID_POINT,
+    PAIRING_FAILED, VERIFICATION_FAILED,
+};
 pub use loader::{EcPoint, EvmLoader, Scalar};
 pub use util::{
-    compile_yul, encode_calldata, estimate_gas, fe_to_u256, modulus, u256_to_fe, ExecutorBuilder,
-    MemoryChunk,
+    assemble_yul, calldata_layout, compile_solidity, compile_yul, decode_accumulator,
+    decode_revert_reason, encode_batch_calldata, encode_calldata, encode_calldata_with_vk_address,
+    encode_verify_calldata, encode_vk_data, estimate_gas, fe_to_u256, get_contract_address,
+    modulus, u256_to_fe, vk_contract_codehash, vk_contract_deployment_code, CalldataLayout,
+    DebugArena, ExecutorBuilder, GasEstimate, GasReport, GasSection, Hardfork, MemoryChunk,
+    MAX_CODE_SIZE,
 };
 
 pub use ethereum_types::U256;
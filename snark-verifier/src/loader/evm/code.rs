@@ -1,3 +1,12 @@
+use crate::{
+    loader::evm::{
+        assemble_yul,
+        util::{execution_gas, verify_selector},
+    },
+    util::Itertools,
+};
+use ethereum_types::Address;
+
 pub enum Precompiled {
     BigModExp = 0x05,
     Bn254Add = 0x6,
@@ -5,21 +14,343 @@ pub enum Precompiled {
     Bn254Pairing = 0x8,
 }
 
+/// Revert reason when an elliptic curve point is not on the affine plane.
+pub const INVALID_POINT: &str = "invalid point";
+/// Revert reason when the final pairing check fails.
+pub const PAIRING_FAILED: &str = "pairing failed";
+/// Revert reason when an instance is not less than scalar modulus.
+pub const INVALID_INSTANCE: &str = "invalid instance";
+/// Revert reason when calldata doesn't have the expected length.
+pub const BAD_CALLDATA_LENGTH: &str = "bad calldata length";
+/// Revert reason when ABI encoded calldata has unexpected selector or head.
+pub const BAD_CALLDATA_ENCODING: &str = "bad calldata encoding";
+/// Revert reason when a part of split verifier is not called by its driver.
+pub const BAD_CALLER: &str = "bad caller";
+/// Revert reason of other failures, like a failed precompile call.
+pub const VERIFICATION_FAILED: &str = "verification failed";
+
+// Reverts with `Error(string)` ABI encoded as Solidity does, where `reason`
+// is at most 32 bytes left-aligned.
+const REVERT_WITH: &str = "function revert_with(reason, len) {
+                        mstore(0, 0x08c379a000000000000000000000000000000000000000000000000000000000)
+                        mstore(0x04, 0x20)
+                        mstore(0x24, len)
+                        mstore(0x44, reason)
+                        revert(0, 0x64)
+                    }";
+
+/// Returns code reverting with `reason`.
+pub(crate) fn revert_code(reason: &str) -> String {
+    let mut bytes = [0; 0x20];
+    bytes[..reason.len()].copy_from_slice(reason.as_bytes());
+    format!("revert_with(0x{}, {})", hex::encode(bytes), reason.len())
+}
+
 #[derive(Clone, Debug)]
 pub struct YulCode {
-    // runtime code area
-    runtime: String,
+    // runtime code area, in chunks which can be run by different contracts
+    runtime: Vec<String>,
+    // number of opened sections, whose code must stay in the same chunk
+    num_open_section: usize,
 }
 
 impl YulCode {
     pub fn new() -> Self {
-        YulCode { runtime: String::new() }
+        YulCode { runtime: Vec::new(), num_open_section: 0 }
     }
 
     pub fn code(&self, base_modulus: String, scalar_modulus: String) -> String {
+        object("plonk_verifier", &base_modulus, &scalar_modulus, &self.runtime.concat())
+    }
+
+    /// Returns size of runtime bytecode assembled by [`assemble_yul`].
+    pub fn runtime_size(
+        &self,
+        base_modulus: String,
+        scalar_modulus: String,
+    ) -> Result<usize, String> {
+        runtime_size(&base_modulus, &scalar_modulus, &self.runtime.concat())
+    }
+
+    /// Returns gas of opcodes executed by runtime code priced by
+    /// `opcode_gas`, see [`execution_gas`] for assumptions made.
+    pub fn execution_gas(
+        &self,
+        base_modulus: String,
+        scalar_modulus: String,
+        opcode_gas: impl Fn(u8) -> u64,
+    ) -> Result<u64, String> {
+        let code = format!(
+            "object \"Runtime\" {{ {} }}",
+            runtime_code(&base_modulus, &scalar_modulus, &self.runtime.concat())
+        );
+        execution_gas(&code, opcode_gas)
+    }
+
+    /// Returns code of a chain of contracts which run the runtime code chunk
+    /// by chunk, where each runtime bytecode assembled by [`assemble_yul`]
+    /// is at most `max_runtime_size` bytes.
+    ///
+    /// Except the first one, each contract expects memory before `state_len`
+    /// left by the previous one to be appended to the original calldata, and
+    /// except the last one, each contract returns its memory before
+    /// `state_len`. Since the appended memory is trusted, contracts except the
+    /// first one only accept calls from `driver`.
+    pub fn split_code(
+        &self,
+        base_modulus: String,
+        scalar_modulus: String,
+        state_len: usize,
+        max_runtime_size: usize,
+        driver: Address,
+    ) -> Result<Vec<String>, String> {
+        let prologue = format!(
+            "if not(eq(caller(), {driver:?})) {{ {} }}
+            calldatacopy(0, sub(calldatasize(), {state_len:#x}), {state_len:#x})\n",
+            revert_code(BAD_CALLER)
+        );
+        let epilogue = |len: usize| {
+            format!(
+                "if not(success) {{ {} }}\nreturn(0, {len:#x})\n",
+                revert_code(VERIFICATION_FAILED)
+            )
+        };
+
+        // Chunks are assembled independently, so the size is additive
+        let empty_size = runtime_size(&base_modulus, &scalar_modulus, "")?;
+        let base_size = runtime_size(
+            &base_modulus,
+            &scalar_modulus,
+            &format!("{prologue}{}", epilogue(state_len)),
+        )?;
+        let mut parts = vec![Vec::new()];
+        let mut size = base_size;
+        for chunk in self.runtime.iter() {
+            let chunk_size = runtime_size(&base_modulus, &scalar_modulus, chunk)? - empty_size;
+            if base_size + chunk_size > max_runtime_size {
+                return Err(format!(
+                    "Code chunk of {chunk_size} bytes doesn't fit in runtime size {max_runtime_size}"
+                ));
+            }
+            if size + chunk_size > max_runtime_size {
+                parts.push(Vec::new());
+                size = base_size;
+            }
+            parts.last_mut().unwrap().push(chunk.as_str());
+            size += chunk_size;
+        }
+
+        let num_part = parts.len();
+        Ok(parts
+            .into_iter()
+            .enumerate()
+            .map(|(idx, chunks)| {
+                let prologue = if idx == 0 { "" } else { prologue.as_str() };
+                let epilogue = epilogue(if idx == num_part - 1 { 0 } else { state_len });
+                let runtime = format!("{prologue}{}{epilogue}", chunks.concat());
+                object(&format!("plonk_verifier_{idx}"), &base_modulus, &scalar_modulus, &runtime)
+            })
+            .collect())
+    }
+
+    /// Returns Solidity contract with a typed `verify(uint256[], bytes)`
+    /// function, which wraps the same runtime code in inline assembly.
+    /// Constants in `constants` are hoisted as named contract constants.
+    ///
+    /// Calldata is checked by [`abi_check_code`] to be the canonical encoding
+    /// of `num_instance` instances and a proof of `proof_len` bytes before
+    /// anything is read.
+    pub fn solidity_code(
+        &self,
+        base_modulus: String,
+        scalar_modulus: String,
+        constants: &[(String, String)],
+        num_instance: usize,
+        proof_len: usize,
+    ) -> String {
+        let revert_with = REVERT_WITH;
+        let invalid_point = revert_code(INVALID_POINT);
+        let invalid_instance = revert_code(INVALID_INSTANCE);
+        let verification_failed = revert_code(VERIFICATION_FAILED);
+        let declarations = constants
+            .iter()
+            .map(|(name, value)| format!("    uint256 internal constant {name} = {value};"))
+            .join("\n");
+        let runtime = constants.iter().fold(
+            untyped(&format!(
+                "{}\n{}",
+                abi_check_code(num_instance, proof_len),
+                rewrite_calldataload(&self.runtime.concat())
+            )),
+            |runtime, (name, value)| runtime.replace(value.as_str(), name),
+        );
         format!(
-            "
-        object \"plonk_verifier\" {{
+            "// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+contract PlonkVerifier {{
+{declarations}
+
+    /// @notice Returns `true` if `proof` is valid for `instances`, otherwise reverts.
+    function verify(uint256[] calldata instances, bytes calldata proof)
+        external
+        view
+        returns (bool)
+    {{
+        assembly {{
+            {revert_with}
+            function validate_ec_point(x, y) -> valid {{
+                {{
+                    let x_lt_p := lt(x, {base_modulus})
+                    let y_lt_p := lt(y, {base_modulus})
+                    valid := and(x_lt_p, y_lt_p)
+                }}
+                {{
+                    let x_is_zero := eq(x, 0)
+                    let y_is_zero := eq(y, 0)
+                    let x_or_y_is_zero := or(x_is_zero, y_is_zero)
+                    let x_and_y_is_not_zero := iszero(x_or_y_is_zero)
+                    valid := and(x_and_y_is_not_zero, valid)
+                }}
+                {{
+                    let y_square := mulmod(y, y, {base_modulus})
+                    let x_square := mulmod(x, x, {base_modulus})
+                    let x_cube := mulmod(x_square, x, {base_modulus})
+                    let x_cube_plus_3 := addmod(x_cube, 3, {base_modulus})
+                    let y_square_eq_x_cube_plus_3 := eq(x_cube_plus_3, y_square)
+                    valid := and(y_square_eq_x_cube_plus_3, valid)
+                }}
+                if iszero(valid) {{ {invalid_point} }}
+            }}
+            function validate_instance(value) {{
+                if iszero(lt(value, {scalar_modulus})) {{ {invalid_instance} }}
+            }}
+            // Maps offset of the packed layout (instances followed by proof)
+            // into ABI encoded calldata
+            function cdload(offset, instances_offset, instances_len, proof_offset) -> value {{
+                switch lt(offset, instances_len)
+                case 0 {{ value := calldataload(add(proof_offset, sub(offset, instances_len))) }}
+                default {{ value := calldataload(add(instances_offset, offset)) }}
+            }}
+            let success := 1
+            let f_p := {base_modulus}
+            let f_q := {scalar_modulus}
+            let instances_len := mul(instances.length, 0x20)
+            {runtime}
+            if iszero(success) {{ {verification_failed} }}
+            mstore(0, 1)
+            return(0, 0x20)
+        }}
+    }}
+}}
+"
+        )
+    }
+
+    /// Inserts `code` before all runtime code as a separate chunk.
+    pub fn runtime_prepend(&mut self, mut code: String) {
+        code.push('\n');
+        self.runtime.insert(0, code);
+    }
+
+    pub fn runtime_append(&mut self, mut code: String) {
+        code.push('\n');
+        match self.runtime.last_mut() {
+            Some(chunk) if self.num_open_section > 0 => chunk.push_str(&code),
+            _ => self.runtime.push(code),
+        }
+    }
+
+    /// Keeps code appended until the matching [`YulCode::close_section`] in
+    /// the same chunk as the last one, which is needed when they share
+    /// variables declared at top-level.
+    pub fn open_section(&mut self) {
+        self.num_open_section += 1;
+    }
+
+    pub fn close_section(&mut self) {
+        self.num_open_section -= 1;
+    }
+}
+
+/// Returns code of a contract which calls `parts` in order with the original
+/// calldata, where each call has memory before `state_len` returned by the
+/// previous one appended, as expected by [`YulCode::split_code`]. Revert data
+/// of a failed part is bubbled up.
+pub fn driver_code(parts: &[Address], state_len: usize) -> String {
+    let calls = parts
+        .iter()
+        .enumerate()
+        .map(|(idx, part)| {
+            let in_len = if idx == 0 {
+                "cd_len".to_string()
+            } else {
+                format!("add(cd_len, {state_len:#x})")
+            };
+            let out_len = if idx == parts.len() - 1 { 0 } else { state_len };
+            format!(
+                "if iszero(call(gas(), {part:?}, 0, 0, {in_len}, cd_len, {out_len:#x})) {{
+                        returndatacopy(0, 0, returndatasize())
+                        revert(0, returndatasize())
+                    }}"
+            )
+        })
+        .join("\n                    ");
+    format!(
+        "
+        object \"plonk_verifier_driver\" {{
+            code {{
+                let size := datasize(\"Runtime\")
+                datacopy(0, dataoffset(\"Runtime\"), size)
+                return(0, size)
+            }}
+            object \"Runtime\" {{
+                code {{
+                    let cd_len := calldatasize()
+                    calldatacopy(0, 0, cd_len)
+                    {calls}
+                    return(0, 0)
+                }}
+            }}
+        }}"
+    )
+}
+
+/// Returns code checking that calldata is the canonical ABI encoding of
+/// `verify(uint256[] instances, bytes proof)` with `num_instance` instances and
+/// a proof of `proof_len` bytes, so instances start at `0x64` and proof starts
+/// at `0x84 + 0x20 * num_instance`.
+pub(crate) fn abi_check_code(num_instance: usize, proof_len: usize) -> String {
+    let proof_len_ptr = 0x64 + num_instance * 0x20;
+    let calldata_len = proof_len_ptr + 0x20 + proof_len;
+    format!(
+        "
+        if not(eq(calldatasize(), {calldata_len:#x})) {{ {} }}
+        {{
+            let valid:bool := eq(shr(224, calldataload(0x0)), 0x{})
+            valid := and(eq(calldataload(0x4), 0x40), valid)
+            valid := and(eq(calldataload(0x24), {:#x}), valid)
+            valid := and(eq(calldataload(0x44), {num_instance:#x}), valid)
+            valid := and(eq(calldataload({proof_len_ptr:#x}), {proof_len:#x}), valid)
+            if not(valid) {{ {} }}
+        }}",
+        revert_code(BAD_CALLDATA_LENGTH),
+        hex::encode(verify_selector()),
+        proof_len_ptr - 0x4,
+        revert_code(BAD_CALLDATA_ENCODING)
+    )
+}
+
+fn runtime_size(base_modulus: &str, scalar_modulus: &str, runtime: &str) -> Result<usize, String> {
+    let code =
+        format!("object \"Runtime\" {{ {} }}", runtime_code(base_modulus, scalar_modulus, runtime));
+    assemble_yul(&code).map(|bytecode| bytecode.len())
+}
+
+fn object(name: &str, base_modulus: &str, scalar_modulus: &str, runtime: &str) -> String {
+    format!(
+        "
+        object \"{name}\" {{
             code {{
                 function allocate(size) -> ptr {{
                     ptr := mload(0x40)
@@ -32,10 +363,23 @@ impl YulCode {
                 return(offset, size)
             }}
             object \"Runtime\" {{
-                code {{
+                {}
+            }}
+        }}",
+        runtime_code(base_modulus, scalar_modulus, runtime)
+    )
+}
+
+fn runtime_code(base_modulus: &str, scalar_modulus: &str, runtime: &str) -> String {
+    let revert_with = REVERT_WITH;
+    let invalid_point = revert_code(INVALID_POINT);
+    let invalid_instance = revert_code(INVALID_INSTANCE);
+    format!(
+        "code {{
                     let success:bool := true
                     let f_p := {base_modulus}
                     let f_q := {scalar_modulus}
+                    {revert_with}
                     function validate_ec_point(x, y) -> valid:bool {{
                         {{
                             let x_lt_p:bool := lt(x, {base_modulus})
@@ -57,17 +401,93 @@ impl YulCode {
                             let y_square_eq_x_cube_plus_3:bool := eq(x_cube_plus_3, y_square)
                             valid := and(y_square_eq_x_cube_plus_3, valid)
                         }}
+                        if not(valid) {{ {invalid_point} }}
                     }}
-                    {}
-                }}
-            }}
-        }}",
-            self.runtime
-        )
+                    function validate_instance(value) {{
+                        let valid:bool := lt(value, {scalar_modulus})
+                        if not(valid) {{ {invalid_instance} }}
+                    }}
+                    {runtime}
+                }}"
+    )
+}
+
+/// Replaces `calldataload(offset)` in runtime code with `cdload` defined in
+/// [`YulCode::solidity_code`], which reads from ABI encoded calldata instead.
+/// The `offset` may itself contain calls, so it ends at the matching `)`.
+fn rewrite_calldataload(runtime: &str) -> String {
+    const PATTERN: &str = "calldataload(";
+
+    let mut rewritten = String::with_capacity(runtime.len());
+    let mut rest = runtime;
+    while let Some(start) = rest.find(PATTERN) {
+        let (head, tail) = rest.split_at(start + PATTERN.len());
+        let mut depth = 0;
+        let end = tail
+            .find(|c| {
+                match c {
+                    '(' => depth += 1,
+                    ')' if depth == 0 => return true,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                false
+            })
+            .expect("Unbalanced parentheses in runtime code");
+        rewritten.push_str(&head[..start]);
+        rewritten.push_str(&format!(
+            "cdload({}, instances.offset, instances_len, proof.offset)",
+            rewrite_calldataload(&tail[..end])
+        ));
+        rest = &tail[end + 1..];
     }
+    rewritten.push_str(rest);
+    rewritten
+}
 
-    pub fn runtime_append(&mut self, mut code: String) {
-        code.push('\n');
-        self.runtime.push_str(&code);
+/// Translates typed Yul of `solc --yul` into untyped inline assembly of
+/// Solidity, where `bool` is an ordinary word and `not` is bitwise, by dropping
+/// `:bool` annotations and replacing logical `not` with `iszero`.
+fn untyped(code: &str) -> String {
+    let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.';
+
+    let mut untyped = String::with_capacity(code.len());
+    let mut rest = code;
+    while let Some(c) = rest.chars().next() {
+        if is_identifier(c) {
+            let len = rest.find(|c| !is_identifier(c)).unwrap_or(rest.len());
+            untyped.push_str(match &rest[..len] {
+                "not" => "iszero",
+                "true" => "1",
+                "false" => "0",
+                identifier => identifier,
+            });
+            rest = &rest[len..];
+        } else if let Some(tail) =
+            rest.strip_prefix(":bool").filter(|tail| !tail.starts_with(is_identifier))
+        {
+            rest = tail;
+        } else {
+            untyped.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    untyped
+}
+
+#[cfg(test)]
+mod test {
+    use super::rewrite_calldataload;
+
+    #[test]
+    fn test_rewrite_calldataload() {
+        assert_eq!(
+            rewrite_calldataload("let x := add(calldataload(add(0x20, mul(i, 0x40))), 1)"),
+            "let x := add(cdload(add(0x20, mul(i, 0x40)), instances.offset, instances_len, proof.offset), 1)"
+        );
+        assert_eq!(
+            rewrite_calldataload("calldataload(calldataload(0x0))"),
+            "cdload(cdload(0x0, instances.offset, instances_len, proof.offset), instances.offset, instances_len, proof.offset)"
+        );
     }
 }
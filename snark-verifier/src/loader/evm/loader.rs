@@ -1,8 +1,13 @@
 use crate::{
     loader::{
         evm::{
-            code::{Precompiled, YulCode},
+            code::{
+                abi_check_code, driver_code, revert_code, Precompiled, YulCode,
+                BAD_CALLDATA_LENGTH, PAIRING_FAILED, VERIFICATION_FAILED,
+            },
             fe_to_u256, modulus, u256_to_fe,
+            util::{opcode_gas, OpCounts},
+            GasEstimate, Hardfork,
         },
         EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader,
     },
@@ -12,7 +17,7 @@ use crate::{
     },
     Error,
 };
-use ethereum_types::{U256, U512};
+use ethereum_types::{Address, U256, U512};
 use hex;
 use std::{
     cell::RefCell,
@@ -51,6 +56,23 @@ impl<T: Debug> Value<T> {
     }
 }
 
+/// Calling convention of code generated by [`EvmLoader`], which are exclusive
+/// to each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    /// Takes packed calldata from [`encode_calldata`] and returns nothing.
+    ///
+    /// [`encode_calldata`]: crate::loader::evm::encode_calldata
+    Packed,
+    /// Same as [`Mode::Packed`], but logs gas consumed by metered sections.
+    GasMetering,
+    /// Called by `verify(uint256[] instances, bytes proof)` and returns `true`.
+    Abi,
+    /// Same as [`Mode::Packed`], but returns the accumulator instead of
+    /// performing the final pairing check.
+    DeferredPairing,
+}
+
 /// `Loader` implementation for generating yul code as EVM verifier.
 #[derive(Clone, Debug)]
 pub struct EvmLoader {
@@ -59,8 +81,16 @@ pub struct EvmLoader {
     code: RefCell<YulCode>,
     ptr: RefCell<usize>,
     cache: RefCell<HashMap<String, usize>>,
-    #[cfg(test)]
+    vk_constants: RefCell<Vec<(String, U256)>>,
+    op_counts: RefCell<OpCounts>,
+    mode: Mode,
+    // identifiers of all gas meterings in start order
     gas_metering_ids: RefCell<Vec<String>>,
+    // indices of ongoing gas meterings
+    open_gas_meterings: RefCell<Vec<usize>>,
+    num_instance: RefCell<usize>,
+    // pointer of lhs and rhs of accumulator to return when pairing is deferred
+    accumulator_ptr: RefCell<Option<usize>>,
 }
 
 fn hex_encode_u256(value: &U256) -> String {
@@ -72,6 +102,60 @@ fn hex_encode_u256(value: &U256) -> String {
 impl EvmLoader {
     /// Initialize a [`EvmLoader`] with base and scalar field.
     pub fn new<Base, Scalar>() -> Rc<Self>
+    where
+        Base: PrimeField<Repr = [u8; 0x20]>,
+        Scalar: PrimeField<Repr = [u8; 32]>,
+    {
+        Self::with_mode::<Base, Scalar>(Mode::Packed)
+    }
+
+    /// Initialize a [`EvmLoader`] with base and scalar field, which emits a
+    /// log with gas consumed by each section between
+    /// [`Loader::start_cost_metering`] and [`Loader::end_cost_metering`], to
+    /// be collected by [`GasReport::new`].
+    ///
+    /// [`GasReport::new`]: crate::loader::evm::GasReport::new
+    pub fn new_with_gas_metering<Base, Scalar>() -> Rc<Self>
+    where
+        Base: PrimeField<Repr = [u8; 0x20]>,
+        Scalar: PrimeField<Repr = [u8; 32]>,
+    {
+        Self::with_mode::<Base, Scalar>(Mode::GasMetering)
+    }
+
+    /// Initialize a [`EvmLoader`] with base and scalar field, whose generated
+    /// code is called by `verify(uint256[] instances, bytes proof)` with ABI
+    /// encoded calldata from [`encode_verify_calldata`] and returns `true`,
+    /// instead of taking the packed calldata from [`encode_calldata`]. The
+    /// encoding is expected to be the canonical one, so offsets in calldata
+    /// stay fixed.
+    ///
+    /// [`encode_verify_calldata`]: crate::loader::evm::encode_verify_calldata
+    /// [`encode_calldata`]: crate::loader::evm::encode_calldata
+    pub fn new_with_abi<Base, Scalar>() -> Rc<Self>
+    where
+        Base: PrimeField<Repr = [u8; 0x20]>,
+        Scalar: PrimeField<Repr = [u8; 32]>,
+    {
+        Self::with_mode::<Base, Scalar>(Mode::Abi)
+    }
+
+    /// Initialize a [`EvmLoader`] with base and scalar field, whose generated
+    /// code doesn't perform the final pairing check in [`EvmLoader::pairing`]
+    /// but returns the accumulator `(lhs, rhs)` as 4 words `lhs.x`, `lhs.y`,
+    /// `rhs.x` and `rhs.y`. The proof is valid only if
+    /// `e(lhs, g2) == e(rhs, s_g2)`, which is left to the caller, so
+    /// accumulators from many calls can be folded into one pairing check.
+    /// Generating code panics if [`EvmLoader::pairing`] is never called.
+    pub fn new_with_deferred_pairing<Base, Scalar>() -> Rc<Self>
+    where
+        Base: PrimeField<Repr = [u8; 0x20]>,
+        Scalar: PrimeField<Repr = [u8; 32]>,
+    {
+        Self::with_mode::<Base, Scalar>(Mode::DeferredPairing)
+    }
+
+    fn with_mode<Base, Scalar>(mode: Mode) -> Rc<Self>
     where
         Base: PrimeField<Repr = [u8; 0x20]>,
         Scalar: PrimeField<Repr = [u8; 32]>,
@@ -86,23 +170,190 @@ impl EvmLoader {
             code: RefCell::new(code),
             ptr: Default::default(),
             cache: Default::default(),
-            #[cfg(test)]
-            gas_metering_ids: RefCell::new(Vec::new()),
+            vk_constants: Default::default(),
+            op_counts: Default::default(),
+            mode,
+            gas_metering_ids: Default::default(),
+            open_gas_meterings: Default::default(),
+            num_instance: Default::default(),
+            accumulator_ptr: Default::default(),
         })
     }
 
     /// Returns generated yul code.
     pub fn yul_code(self: &Rc<Self>) -> String {
-        let code = "
-            if not(success) { revert(0, 0) }
-            return(0, 0)"
-            .to_string();
-        self.code.borrow_mut().runtime_append(code);
-        self.code
-            .borrow()
+        self.finalized_code()
             .code(hex_encode_u256(&self.base_modulus), hex_encode_u256(&self.scalar_modulus))
     }
 
+    /// Returns size of runtime bytecode of [`EvmLoader::yul_code`] assembled
+    /// by [`assemble_yul`], to be compared with the contract size limit
+    /// [`MAX_CODE_SIZE`].
+    ///
+    /// [`assemble_yul`]: crate::loader::evm::assemble_yul
+    /// [`MAX_CODE_SIZE`]: crate::loader::evm::MAX_CODE_SIZE
+    pub fn runtime_size(self: &Rc<Self>) -> Result<usize, String> {
+        self.finalized_code().runtime_size(
+            hex_encode_u256(&self.base_modulus),
+            hex_encode_u256(&self.scalar_modulus),
+        )
+    }
+
+    /// Returns gas estimate of calling [`EvmLoader::yul_code`] with valid
+    /// calldata under gas schedule of `hardfork`, from operations emitted so
+    /// far.
+    ///
+    /// It assumes every emitted statement is executed, which holds for
+    /// verifiers except the reverting branches, and that calldata has no
+    /// zero byte, so it slightly overestimates.
+    pub fn estimate_gas(self: &Rc<Self>, hardfork: Hardfork) -> Result<GasEstimate, String> {
+        let execution = self.finalized_code().execution_gas(
+            hex_encode_u256(&self.base_modulus),
+            hex_encode_u256(&self.scalar_modulus),
+            |opcode| opcode_gas(opcode, hardfork),
+        )?;
+        Ok(self.op_counts.borrow().estimate(hardfork, execution, self.state_len()))
+    }
+
+    /// Returns length of calldata expected by generated code, which is
+    /// enforced exactly.
+    pub fn calldata_len(&self) -> usize {
+        self.op_counts.borrow().calldata_len()
+    }
+
+    /// Returns generated code with calldata length checked in the beginning,
+    /// and with selector and head of ABI encoding checked in ABI mode.
+    fn checked_code(&self) -> YulCode {
+        let mut code = self.code.borrow().clone();
+        let calldata_len = self.calldata_len();
+        code.runtime_prepend(if self.mode == Mode::Abi {
+            let num_instance = *self.num_instance.borrow();
+            abi_check_code(num_instance, calldata_len - 0x84 - num_instance * 0x20)
+        } else {
+            format!(
+                "if not(eq(calldatasize(), {calldata_len:#x})) {{ {} }}",
+                revert_code(BAD_CALLDATA_LENGTH)
+            )
+        });
+        code
+    }
+
+    fn finalized_code(&self) -> YulCode {
+        let mut code = self.checked_code();
+        let vk_contract = self.op_counts.borrow().vk_contract_words.is_some();
+        let ret = match (self.mode, *self.accumulator_ptr.borrow()) {
+            (Mode::DeferredPairing, Some(ptr)) => format!("return({ptr:#x}, 0x80)"),
+            // Otherwise the verifier would accept without anything to fold
+            (Mode::DeferredPairing, None) => {
+                panic!("No accumulator is returned since pairing is never performed")
+            }
+            (Mode::Abi, _) => "mstore(0, 1) return(0, 0x20)".to_string(),
+            // Code hash of VK contract binds which verifying key is used
+            _ if vk_contract => {
+                "mstore(0, extcodehash(calldataload(sub(calldatasize(), 0x20)))) return(0, 0x20)"
+                    .to_string()
+            }
+            _ => "return(0, 0)".to_string(),
+        };
+        code.runtime_append(format!(
+            "
+            if not(success) {{ {} }}
+            {ret}",
+            revert_code(VERIFICATION_FAILED)
+        ));
+        code
+    }
+
+    /// Returns generated yul code split into a chain of contracts, each with
+    /// runtime bytecode assembled by [`assemble_yul`] at most
+    /// `max_runtime_size` bytes. Memory used by verification is passed along
+    /// the chain, so they should be called in order by the contract from
+    /// [`EvmLoader::driver_yul_code`] deployed at `driver`, and contracts
+    /// except the first one reject calls from anyone else. When the whole
+    /// verifier fits, only one contract is returned.
+    ///
+    /// [`assemble_yul`]: crate::loader::evm::assemble_yul
+    pub fn split_yul_code(
+        self: &Rc<Self>,
+        max_runtime_size: usize,
+        driver: Address,
+    ) -> Result<Vec<String>, String> {
+        if matches!(self.mode, Mode::Abi | Mode::DeferredPairing) {
+            return Err(format!("Splitting verifier in {:?} mode is not supported", self.mode));
+        }
+        if self.op_counts.borrow().vk_contract_words.is_some() {
+            return Err(
+                "Splitting verifier with verifying key contract is not supported".to_string()
+            );
+        }
+        self.checked_code().split_code(
+            hex_encode_u256(&self.base_modulus),
+            hex_encode_u256(&self.scalar_modulus),
+            self.state_len(),
+            max_runtime_size,
+            driver,
+        )
+    }
+
+    /// Returns yul code of the contract which takes the same calldata as
+    /// [`EvmLoader::yul_code`] and verifies by calling contracts from
+    /// [`EvmLoader::split_yul_code`] deployed at `parts` in order.
+    pub fn driver_yul_code(self: &Rc<Self>, parts: &[Address]) -> String {
+        driver_code(parts, self.state_len())
+    }
+
+    /// Returns length of memory passed between split contracts, which has
+    /// an extra word for `mstore8` right after the last allocated chunk in
+    /// transcript.
+    fn state_len(&self) -> usize {
+        self.ptr() + 0x20
+    }
+
+    /// Returns generated Solidity code, which has the same verification logic
+    /// as [`EvmLoader::yul_code`] but is called by
+    /// `verify(uint256[] instances, bytes proof)` with canonical ABI encoded
+    /// calldata, and has constants of verifying key named.
+    ///
+    /// It returns an error in ABI mode, where [`EvmLoader::yul_code`] already
+    /// takes the same calldata, with deferred pairing, or when verifying key
+    /// is read from another contract.
+    pub fn solidity_code(self: &Rc<Self>) -> Result<String, String> {
+        if !matches!(self.mode, Mode::Packed | Mode::GasMetering) {
+            return Err(format!("Solidity code is not supported in {:?} mode", self.mode));
+        }
+        if self.op_counts.borrow().vk_contract_words.is_some() {
+            return Err("Solidity code is not supported with verifying key contract".to_string());
+        }
+        let num_instance = *self.num_instance.borrow();
+        let constants = self
+            .vk_constants
+            .borrow()
+            .iter()
+            .map(|(name, value)| (name.clone(), hex_encode_u256(value)))
+            .collect_vec();
+        Ok(self.code.borrow().solidity_code(
+            hex_encode_u256(&self.base_modulus),
+            hex_encode_u256(&self.scalar_modulus),
+            &constants,
+            num_instance,
+            self.calldata_len() - num_instance * 0x20,
+        ))
+    }
+
+    /// Registers `value` as a constant of verifying key named by `kind` and
+    /// returns it hex encoded.
+    fn vk_constant(self: &Rc<Self>, kind: &str, value: &U256) -> String {
+        let mut vk_constants = self.vk_constants.borrow_mut();
+        if !vk_constants.iter().any(|(_, constant)| constant == value) {
+            let idx = vk_constants
+                .iter()
+                .filter(|(name, _)| name.strip_prefix(kind).unwrap_or_default().starts_with('_'))
+                .count();
+            vk_constants.push((format!("{kind}_{idx}"), *value));
+        }
+        hex_encode_u256(value)
+    }
+
     /// Allocates memory chunk with given `size` and returns pointer.
     pub fn allocate(self: &Rc<Self>, size: usize) -> usize {
         let ptr = *self.ptr.borrow();
@@ -145,15 +396,44 @@ impl EvmLoader {
 
     /// Calldata load a field element.
     pub fn calldataload_scalar(self: &Rc<Self>, offset: usize) -> Scalar {
+        let offset = self.calldata_offset(offset, false);
         let ptr = self.allocate(0x20);
         let code = format!("mstore({ptr:#x}, mod(calldataload({offset:#x}), f_q))");
         self.code.borrow_mut().runtime_append(code);
+        self.count_calldata(offset, 0x20);
+        self.scalar(Value::Memory(ptr))
+    }
+
+    /// Calldata load an instance, which must be less than scalar modulus
+    /// instead of being reduced, so each instance has a unique encoding.
+    pub fn calldataload_instance(self: &Rc<Self>, offset: usize) -> Scalar {
+        let offset = {
+            let mut num_instance = self.num_instance.borrow_mut();
+            assert!(
+                self.mode != Mode::Abi || offset == *num_instance * 0x20,
+                "Instances should be loaded before proof in ABI mode"
+            );
+            *num_instance += 1;
+            self.calldata_offset(offset, true)
+        };
+        let ptr = self.allocate(0x20);
+        let code = format!(
+            "
+        {{
+            let instance := calldataload({offset:#x})
+            validate_instance(instance)
+            mstore({ptr:#x}, instance)
+        }}"
+        );
+        self.code.borrow_mut().runtime_append(code);
+        self.count_calldata(offset, 0x20);
         self.scalar(Value::Memory(ptr))
     }
 
     /// Calldata load an elliptic curve point and validate it's on affine plane.
     /// Note that identity will cause the verification to fail.
     pub fn calldataload_ec_point(self: &Rc<Self>, offset: usize) -> EcPoint {
+        let offset = self.calldata_offset(offset, false);
         let x_ptr = self.allocate(0x40);
         let y_ptr = x_ptr + 0x20;
         let x_cd_ptr = offset;
@@ -170,6 +450,7 @@ impl EvmLoader {
         }}"
         );
         self.code.borrow_mut().runtime_append(code);
+        self.count_calldata(offset, 0x40);
         self.ec_point(Value::Memory(x_ptr))
     }
 
@@ -214,6 +495,84 @@ impl EvmLoader {
         self.ec_point(Value::Memory(ptr))
     }
 
+    /// Decode an elliptic curve point from limbs of x coordinate and parity of
+    /// y coordinate, where y is recovered as the square root of `x^3 + 3` by
+    /// exponent `(p + 1) / 4`, so it only supports BN254.
+    pub fn ec_point_from_compressed_limbs<const LIMBS: usize, const BITS: usize>(
+        self: &Rc<Self>,
+        x_limbs: [&Scalar; LIMBS],
+        y_parity: &Scalar,
+    ) -> EcPoint {
+        assert_eq!(self.base_modulus.low_u32() % 4, 3);
+
+        let ptr = self.allocate(0x40);
+        let cd_ptr = self.allocate(0xc0);
+        let mut code = String::new();
+        for (idx, limb) in x_limbs.iter().enumerate() {
+            let limb_i = self.push(limb);
+            let shift = idx * BITS;
+            if idx == 0 {
+                code.push_str(format!("let x := {limb_i}\n").as_str());
+            } else {
+                code.push_str(format!("x := add(x, shl({shift}, {limb_i}))\n").as_str());
+            }
+        }
+        let y_parity = self.push(y_parity);
+        let x_ptr = ptr;
+        let y_ptr = ptr + 0x20;
+        let exponent = (self.base_modulus + 1) >> 2;
+        let [base_ptr, exponent_ptr, modulus_ptr] = [0x60, 0x80, 0xa0].map(|idx| cd_ptr + idx);
+        let validate_code = self.validate_ec_point();
+        let code = format!(
+            "{{
+            {code}
+            mstore({x_ptr:#x}, x)
+            mstore({cd_ptr:#x}, 0x20)
+            mstore({:#x}, 0x20)
+            mstore({:#x}, 0x20)
+            mstore({base_ptr:#x}, addmod(mulmod(mulmod(x, x, f_p), x, f_p), 3, f_p))
+            mstore({exponent_ptr:#x}, {})
+            mstore({modulus_ptr:#x}, f_p)
+            success := and(eq(staticcall(gas(), {:#x}, {cd_ptr:#x}, 0xc0, {y_ptr:#x}, 0x20), 1), success)
+            let y_parity := {y_parity}
+            let y_parity_is_bit:bool := lt(y_parity, 2)
+            success := and(y_parity_is_bit, success)
+            let y := mload({y_ptr:#x})
+            let y_parity_matches:bool := eq(and(y, 1), y_parity)
+            if not(y_parity_matches) {{
+                y := sub(f_p, y)
+                mstore({y_ptr:#x}, y)
+            }}
+            {validate_code}
+        }}",
+            cd_ptr + 0x20,
+            cd_ptr + 0x40,
+            hex_encode_u256(&exponent),
+            Precompiled::BigModExp as usize,
+        );
+        self.code.borrow_mut().runtime_append(code);
+        self.op_counts.borrow_mut().modexp.push(exponent.bits());
+        self.ec_point(Value::Memory(ptr))
+    }
+
+    /// Returns offset in calldata of `offset` in the packed layout of
+    /// [`encode_calldata`], which is shifted by head of ABI encoding, and also
+    /// by length of proof if it's not an instance, in ABI mode.
+    ///
+    /// [`encode_calldata`]: crate::loader::evm::encode_calldata
+    fn calldata_offset(&self, offset: usize, is_instance: bool) -> usize {
+        match (self.mode, is_instance) {
+            (Mode::Abi, true) => 0x64 + offset,
+            (Mode::Abi, false) => 0x84 + offset,
+            _ => offset,
+        }
+    }
+
+    fn count_calldata(&self, offset: usize, len: usize) {
+        let mut op_counts = self.op_counts.borrow_mut();
+        op_counts.calldata_len = op_counts.calldata_len.max(offset + len);
+    }
+
     fn validate_ec_point(self: &Rc<Self>) -> String {
         "success := and(validate_ec_point(x, y), success)".to_string()
     }
@@ -238,15 +597,40 @@ impl EvmLoader {
         Scalar { loader: self.clone(), value }
     }
 
-    fn ec_point(self: &Rc<Self>, value: Value<(U256, U256)>) -> EcPoint {
+    pub(crate) fn ec_point(self: &Rc<Self>, value: Value<(U256, U256)>) -> EcPoint {
         EcPoint { loader: self.clone(), value }
     }
+
+    /// Copies `len` bytes of data from VK contract, whose address is the last
+    /// word of calldata, into `memory[ptr..ptr+len]`. Verification fails if
+    /// the code size doesn't match, which covers addresses without code. The
+    /// code hash of VK contract is returned by generated code.
+    pub(crate) fn extcodecopy_vk(self: &Rc<Self>, ptr: usize, len: usize) {
+        assert!(
+            matches!(self.mode, Mode::Packed | Mode::GasMetering),
+            "VK contract is not supported in {:?} mode",
+            self.mode
+        );
+        let code = format!(
+            "
+        {{
+            let vk := calldataload(sub(calldatasize(), 0x20))
+            success := and(eq(extcodesize(vk), {:#x}), success)
+            extcodecopy(vk, {ptr:#x}, 1, {len:#x})
+        }}",
+            len + 1
+        );
+        self.code.borrow_mut().runtime_append(code);
+        self.op_counts.borrow_mut().vk_contract_words = Some((len + 0x1f) / 0x20);
+    }
+
     /// Performs `KECCAK256` on `memory[ptr..ptr+len]` and returns pointer of
     /// hash.
     pub fn keccak256(self: &Rc<Self>, ptr: usize, len: usize) -> usize {
         let hash_ptr = self.allocate(0x20);
         let code = format!("mstore({hash_ptr:#x}, keccak256({ptr:#x}, {len}))");
         self.code.borrow_mut().runtime_append(code);
+        self.op_counts.borrow_mut().keccak256_words += (len + 0x1f) / 0x20;
         hash_ptr
     }
     /// Copies a field element into given `ptr`.
@@ -267,8 +651,8 @@ impl EvmLoader {
             Value::Constant((x, y)) => {
                 let x_ptr = ptr;
                 let y_ptr = ptr + 0x20;
-                let x = hex_encode_u256(&x);
-                let y = hex_encode_u256(&y);
+                let x = self.vk_constant("VK_G1_X", &x);
+                let y = self.vk_constant("VK_G1_Y", &y);
                 let code = format!(
                     "mstore({x_ptr:#x}, {x})
                     mstore({y_ptr:#x}, {y})"
@@ -300,6 +684,16 @@ impl EvmLoader {
             Precompiled::Bn254ScalarMul => (0x60, 0x40),
             Precompiled::Bn254Pairing => (0x180, 0x20),
         };
+        {
+            let mut op_counts = self.op_counts.borrow_mut();
+            match precompile {
+                // Only used by `invert` with exponent `r - 2`
+                Precompiled::BigModExp => op_counts.modexp.push((self.scalar_modulus - 2).bits()),
+                Precompiled::Bn254Add => op_counts.ec_add += 1,
+                Precompiled::Bn254ScalarMul => op_counts.ec_mul += 1,
+                Precompiled::Bn254Pairing => op_counts.pairing += 1,
+            }
+        }
         let a = precompile as usize;
         let code = format!("success := and(eq(staticcall(gas(), {a:#x}, {cd_ptr:#x}, {cd_len:#x}, {rd_ptr:#x}, {rd_len:#x}), 1), success)");
         self.code.borrow_mut().runtime_append(code);
@@ -333,7 +727,8 @@ impl EvmLoader {
         self.staticcall(Precompiled::Bn254ScalarMul, rd_ptr, rd_ptr);
         self.ec_point(Value::Memory(rd_ptr))
     }
-    /// Performs pairing.
+    /// Performs pairing, or keeps `lhs` and `rhs` to be returned when it's
+    /// deferred.
     pub fn pairing(
         self: &Rc<Self>,
         lhs: &EcPoint,
@@ -341,15 +736,23 @@ impl EvmLoader {
         rhs: &EcPoint,
         minus_s_g2: (U256, U256, U256, U256),
     ) {
+        if self.mode == Mode::DeferredPairing {
+            let mut accumulator_ptr = self.accumulator_ptr.borrow_mut();
+            assert!(accumulator_ptr.is_none(), "Deferred pairing should be performed once");
+            *accumulator_ptr = Some(self.dup_ec_point(lhs).ptr());
+            self.dup_ec_point(rhs);
+            return;
+        }
+
         let rd_ptr = self.dup_ec_point(lhs).ptr();
         self.allocate(0x80);
-        let g2_0 = hex_encode_u256(&g2.0);
+        let g2_0 = self.vk_constant("VK_G2", &g2.0);
         let g2_0_ptr = rd_ptr + 0x40;
-        let g2_1 = hex_encode_u256(&g2.1);
+        let g2_1 = self.vk_constant("VK_G2", &g2.1);
         let g2_1_ptr = rd_ptr + 0x60;
-        let g2_2 = hex_encode_u256(&g2.2);
+        let g2_2 = self.vk_constant("VK_G2", &g2.2);
         let g2_2_ptr = rd_ptr + 0x80;
-        let g2_3 = hex_encode_u256(&g2.3);
+        let g2_3 = self.vk_constant("VK_G2", &g2.3);
         let g2_3_ptr = rd_ptr + 0xa0;
         let code = format!(
             "mstore({g2_0_ptr:#x}, {g2_0})
@@ -360,13 +763,13 @@ impl EvmLoader {
         self.code.borrow_mut().runtime_append(code);
         self.dup_ec_point(rhs);
         self.allocate(0x80);
-        let minus_s_g2_0 = hex_encode_u256(&minus_s_g2.0);
+        let minus_s_g2_0 = self.vk_constant("VK_NEG_S_G2", &minus_s_g2.0);
         let minus_s_g2_0_ptr = rd_ptr + 0x100;
-        let minus_s_g2_1 = hex_encode_u256(&minus_s_g2.1);
+        let minus_s_g2_1 = self.vk_constant("VK_NEG_S_G2", &minus_s_g2.1);
         let minus_s_g2_1_ptr = rd_ptr + 0x120;
-        let minus_s_g2_2 = hex_encode_u256(&minus_s_g2.2);
+        let minus_s_g2_2 = self.vk_constant("VK_NEG_S_G2", &minus_s_g2.2);
         let minus_s_g2_2_ptr = rd_ptr + 0x140;
-        let minus_s_g2_3 = hex_encode_u256(&minus_s_g2.3);
+        let minus_s_g2_3 = self.vk_constant("VK_NEG_S_G2", &minus_s_g2.3);
         let minus_s_g2_3_ptr = rd_ptr + 0x160;
         let code = format!(
             "mstore({minus_s_g2_0_ptr:#x}, {minus_s_g2_0})
@@ -376,7 +779,7 @@ impl EvmLoader {
         );
         self.code.borrow_mut().runtime_append(code);
         self.staticcall(Precompiled::Bn254Pairing, rd_ptr, rd_ptr);
-        let code = format!("success := and(eq(mload({rd_ptr:#x}), 1), success)");
+        let code = format!("if iszero(mload({rd_ptr:#x})) {{ {} }}", revert_code(PAIRING_FAILED));
         self.code.borrow_mut().runtime_append(code);
     }
 
@@ -418,24 +821,43 @@ impl EvmLoader {
     }
 }
 
-#[cfg(test)]
 impl EvmLoader {
-    fn start_gas_metering(self: &Rc<Self>, identifier: &str) {
-        self.gas_metering_ids.borrow_mut().push(identifier.to_string());
-        let code = format!("let {identifier} := gas()");
-        self.code.borrow_mut().runtime_append(code);
-    }
-
-    fn end_gas_metering(self: &Rc<Self>) {
-        let code =
-            format!("log1(0, 0, sub({}, gas()))", self.gas_metering_ids.borrow().last().unwrap());
-        self.code.borrow_mut().runtime_append(code);
-    }
-
-    pub fn print_gas_metering(self: &Rc<Self>, costs: Vec<u64>) {
-        for (identifier, cost) in self.gas_metering_ids.borrow().iter().zip(costs) {
-            println!("{}: {}", identifier, cost);
+    /// Starts gas metering of a section with `identifier` of at most 32 bytes.
+    pub(crate) fn start_gas_metering(self: &Rc<Self>, identifier: &str) {
+        if self.mode != Mode::GasMetering {
+            return;
         }
+        assert!(identifier.len() <= 0x20, "Identifier {identifier} is longer than 32 bytes");
+
+        let mut gas_metering_ids = self.gas_metering_ids.borrow_mut();
+        let idx = gas_metering_ids.len();
+        gas_metering_ids.push(identifier.to_string());
+        self.open_gas_meterings.borrow_mut().push(idx);
+        let code = format!("let gas_metering_{idx} := gas()");
+        let mut yul_code = self.code.borrow_mut();
+        yul_code.runtime_append(code);
+        yul_code.open_section();
+    }
+
+    /// Emits `log3` with identifier, depth and gas consumed as topics, where
+    /// nested ones are emitted before the outer one.
+    pub(crate) fn end_gas_metering(self: &Rc<Self>) {
+        if self.mode != Mode::GasMetering {
+            return;
+        }
+
+        let idx = self.open_gas_meterings.borrow_mut().pop().unwrap();
+        let depth = self.open_gas_meterings.borrow().len();
+        let identifier = {
+            let mut bytes = [0; 0x20];
+            let identifier = &self.gas_metering_ids.borrow()[idx];
+            bytes[..identifier.len()].copy_from_slice(identifier.as_bytes());
+            format!("0x{}", hex::encode(bytes))
+        };
+        let code = format!("log3(0, 0, {identifier}, {depth}, sub(gas_metering_{idx}, gas()))");
+        let mut yul_code = self.code.borrow_mut();
+        yul_code.runtime_append(code);
+        yul_code.close_section();
     }
 }
 
@@ -869,12 +1291,10 @@ where
     C: CurveAffine,
     C::Scalar: PrimeField<Repr = [u8; 0x20]>,
 {
-    #[cfg(test)]
     fn start_cost_metering(&self, identifier: &str) {
         self.start_gas_metering(identifier)
     }
 
-    #[cfg(test)]
     fn end_cost_metering(&self) {
         self.end_gas_metering()
     }
@@ -1,8 +1,5 @@
-use crate::{
-    loader::evm::{test::tui::Tui, util::ExecutorBuilder},
-    util::Itertools,
-};
-use ethereum_types::{Address, U256};
+use crate::loader::evm::{test::tui::Tui, util::ExecutorBuilder, GasReport};
+use ethereum_types::Address;
 use std::env::var_os;
 
 mod tui;
@@ -14,7 +11,7 @@ fn debug() -> bool {
     )
 }
 
-pub fn execute(deployment_code: Vec<u8>, calldata: Vec<u8>) -> (bool, u64, Vec<u64>) {
+pub fn execute(deployment_code: Vec<u8>, calldata: Vec<u8>) -> (bool, u64, GasReport) {
     assert!(
         deployment_code.len() <= 0x6000,
         "Contract size {} exceeds the limit 24576",
@@ -28,17 +25,13 @@ pub fn execute(deployment_code: Vec<u8>, calldata: Vec<u8>) -> (bool, u64, Vec<u
         ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).set_debugger(debug).build();
 
     let contract = evm.deploy(caller, deployment_code.into(), 0.into()).address.unwrap();
+    let calldata_len = calldata.len();
     let result = evm.call_raw(caller, contract, calldata.into(), 0.into());
-
-    let costs = result
-        .logs
-        .into_iter()
-        .map(|log| U256::from_big_endian(log.topics[0].as_bytes()).as_u64())
-        .collect_vec();
+    let report = GasReport::new(calldata_len, &result);
 
     if debug {
         Tui::new(result.debug.unwrap().flatten(0), 0).start();
     }
 
-    (!result.reverted, result.gas_used, costs)
+    (!result.reverted, result.gas_used, report)
 }
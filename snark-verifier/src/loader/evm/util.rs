@@ -1,17 +1,35 @@
 use crate::{
-    cost::Cost,
-    util::{arithmetic::PrimeField, Itertools},
+    cost::{Cost, CostEstimation},
+    loader::native::NativeLoader,
+    pcs::{self, kzg::KzgAccumulator, MultiOpenScheme},
+    util::{
+        arithmetic::{CurveAffine, PrimeField},
+        hash::{Digest, Keccak256},
+        Itertools,
+    },
+    verifier::PlonkProof,
+    Protocol,
 };
-use ethereum_types::U256;
+use ethereum_types::{Address, H256, U256};
 use std::{
     io::Write,
     iter,
     process::{Command, Stdio},
 };
 
+mod assembler;
 pub(crate) mod executor;
+mod gas;
 
-pub use executor::ExecutorBuilder;
+pub use assembler::assemble_yul;
+pub use executor::{get_contract_address, DebugArena, ExecutorBuilder};
+pub use gas::{GasEstimate, GasReport, GasSection, Hardfork};
+
+pub(crate) use assembler::execution_gas;
+pub(crate) use gas::{opcode_gas, OpCounts};
+
+/// Contract size limit of runtime bytecode from EIP-170.
+pub const MAX_CODE_SIZE: usize = 0x6000;
 
 /// Memory chunk in EVM.
 #[derive(Debug)]
@@ -92,31 +110,275 @@ where
         .collect()
 }
 
+/// Encode instances and proof of each proof into calldata of verifier of
+/// several proofs, as [`encode_calldata`] of them concatenated.
+pub fn encode_batch_calldata<F>(instances_and_proofs: &[(Vec<Vec<F>>, Vec<u8>)]) -> Vec<u8>
+where
+    F: PrimeField<Repr = [u8; 32]>,
+{
+    instances_and_proofs
+        .iter()
+        .flat_map(|(instances, proof)| encode_calldata(instances, proof))
+        .collect()
+}
+
+/// Layout of calldata from [`encode_calldata`] expected by verifier of
+/// `protocol`, in byte offsets. Calldata from
+/// [`encode_calldata_with_vk_address`] has an extra word at `len`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CalldataLayout {
+    /// Offset of each instance, grouped by column.
+    pub instances: Vec<Vec<usize>>,
+    /// Offset of each commitment in proof, in the order read by verifier,
+    /// with ones of multi-open scheme at the end.
+    pub commitments: Vec<usize>,
+    /// Offset of each evaluation in proof, in the order read by verifier.
+    pub evaluations: Vec<usize>,
+    /// Total length.
+    pub len: usize,
+}
+
+/// Returns [`CalldataLayout`] expected by verifier of `protocol` with
+/// multi-open scheme `MOS`, which only supports schemes whose proof consists
+/// of commitments, like the KZG ones.
+pub fn calldata_layout<C, MOS>(protocol: &Protocol<C>) -> CalldataLayout
+where
+    C: CurveAffine,
+    MOS: MultiOpenScheme<C, NativeLoader> + CostEstimation<C, Input = Vec<pcs::Query<C::Scalar>>>,
+{
+    let pcs_cost = MOS::estimate_cost(&PlonkProof::<C, NativeLoader, MOS>::empty_queries(protocol));
+    assert_eq!(pcs_cost.num_evaluation, 0, "Evaluations in proof of multi-open scheme");
+
+    let mut len = 0;
+    let mut take = |size: usize, n: usize| {
+        iter::repeat_with(|| {
+            len += size;
+            len - size
+        })
+        .take(n)
+        .collect_vec()
+    };
+    let instances = protocol.num_instance.iter().map(|n| take(0x20, *n)).collect();
+    let mut commitments =
+        take(0x40, protocol.num_witness.iter().sum::<usize>() + protocol.quotient.num_chunk());
+    let evaluations = take(0x20, protocol.evaluations.len());
+    commitments.extend(take(0x40, pcs_cost.num_commitment));
+
+    CalldataLayout { instances, commitments, evaluations, len }
+}
+
+/// Encode instances, proof and address of the VK contract into calldata of
+/// verifier generated with [`EvmTranscript::new_with_vk_contract`], where the
+/// address is appended as the last word.
+///
+/// The address is chosen by the caller, so the verifier accepts a proof for
+/// any VK contract of the same constraint system. It returns the code hash of
+/// the VK contract, which must be compared with [`vk_contract_codehash`] of
+/// the expected one.
+///
+/// [`EvmTranscript::new_with_vk_contract`]: crate::system::halo2::transcript::evm::EvmTranscript::new_with_vk_contract
+pub fn encode_calldata_with_vk_address<F>(
+    instances: &[Vec<F>],
+    proof: &[u8],
+    vk_address: Address,
+) -> Vec<u8>
+where
+    F: PrimeField<Repr = [u8; 32]>,
+{
+    iter::empty()
+        .chain(encode_calldata(instances, proof))
+        .chain(H256::from(vk_address).to_fixed_bytes())
+        .collect()
+}
+
+/// Encode preprocessed commitments and `transcript_initial_state` of
+/// `protocol` into the data stored in VK contract, as big-endian words of
+/// coordinates followed by the scalar.
+pub fn encode_vk_data<C>(protocol: &Protocol<C>) -> Vec<u8>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    let ec_point_bytes = |ec_point: &C| {
+        let coordinates = ec_point.coordinates().unwrap();
+        [coordinates.x(), coordinates.y()]
+            .into_iter()
+            .flat_map(|coordinate| {
+                coordinate.to_repr().as_ref().iter().rev().cloned().collect_vec()
+            })
+            .collect_vec()
+    };
+    iter::empty()
+        .chain(protocol.preprocessed.iter().flat_map(ec_point_bytes))
+        .chain(
+            protocol
+                .transcript_initial_state
+                .iter()
+                .flat_map(|scalar| scalar.to_repr().iter().rev().cloned().collect_vec()),
+        )
+        .collect()
+}
+
+/// Returns deployment bytecode of VK contract, whose runtime bytecode is
+/// `vk_data` prefixed by `STOP`, so it can't be called meaningfully.
+pub fn vk_contract_deployment_code(vk_data: &[u8]) -> Vec<u8> {
+    let runtime_len = vk_data.len() + 1;
+    assert!(runtime_len <= MAX_CODE_SIZE, "VK data exceeds the contract size limit");
+
+    let [len_hi, len_lo] = (runtime_len as u16).to_be_bytes();
+    iter::empty()
+        // PUSH2 runtime_len, DUP1, PUSH1 runtime_offset, PUSH1 0, CODECOPY
+        .chain([0x61, len_hi, len_lo, 0x80, 0x60, 0x0c, 0x60, 0x00, 0x39])
+        // PUSH1 0, RETURN
+        .chain([0x60, 0x00, 0xf3])
+        // STOP
+        .chain([0x00])
+        .chain(vk_data.iter().cloned())
+        .collect()
+}
+
+/// Returns code hash of VK contract deployed by
+/// [`vk_contract_deployment_code`] of `vk_data`, which is returned by
+/// verifier generated with [`EvmTranscript::new_with_vk_contract`].
+///
+/// [`EvmTranscript::new_with_vk_contract`]: crate::system::halo2::transcript::evm::EvmTranscript::new_with_vk_contract
+pub fn vk_contract_codehash(vk_data: &[u8]) -> H256 {
+    H256::from_slice(&Keccak256::new().chain_update([0x00]).chain_update(vk_data).finalize())
+}
+
+/// Encode instances and proof into ABI encoded calldata of
+/// `verify(uint256[] instances, bytes proof)` of the Solidity verifier from
+/// [`EvmLoader::solidity_code`], or of the verifier generated by
+/// [`EvmLoader::new_with_abi`].
+///
+/// [`EvmLoader::solidity_code`]: crate::loader::evm::EvmLoader::solidity_code
+/// [`EvmLoader::new_with_abi`]: crate::loader::evm::EvmLoader::new_with_abi
+pub fn encode_verify_calldata<F>(instances: &[Vec<F>], proof: &[u8]) -> Vec<u8>
+where
+    F: PrimeField<Repr = [u8; 32]>,
+{
+    let word = |value: usize| U256::from(value);
+    let encode_word = |value: U256| {
+        let mut bytes = [0; 32];
+        value.to_big_endian(&mut bytes);
+        bytes
+    };
+    let num_instance = instances.iter().map(Vec::len).sum::<usize>();
+
+    iter::empty()
+        .chain(verify_selector())
+        .chain(encode_word(word(0x40)))
+        .chain(encode_word(word(0x60 + num_instance * 0x20)))
+        .chain(encode_word(word(num_instance)))
+        .chain(encode_calldata(instances, &[]))
+        .chain(encode_word(word(proof.len())))
+        .chain(proof.iter().cloned())
+        .chain(iter::repeat(0).take((32 - proof.len() % 32) % 32))
+        .collect()
+}
+
+/// Returns selector of `verify(uint256[],bytes)`.
+pub(crate) fn verify_selector() -> [u8; 4] {
+    Keccak256::digest(b"verify(uint256[],bytes)")[..4].try_into().unwrap()
+}
+
+/// Decode revert reason from revert data of `Error(string)` ABI encoded, which
+/// is emitted by generated verifiers and Solidity. Returns `None` for other
+/// revert data.
+pub fn decode_revert_reason(data: &[u8]) -> Option<String> {
+    let selector = Keccak256::digest(b"Error(string)");
+    if data.len() < 0x44 || data[..4] != selector[..4] {
+        return None;
+    }
+    let word = |offset: usize| {
+        data.get(offset..offset + 0x20)
+            .map(U256::from_big_endian)
+            .filter(|word| word.bits() <= 64)
+            .map(|word| word.as_usize())
+    };
+    let offset = 4 + word(4)?;
+    let len = word(offset)?;
+    let reason = data.get(offset + 0x20..offset + 0x20 + len)?;
+    String::from_utf8(reason.to_vec()).ok()
+}
+
+/// Decode [`KzgAccumulator`] from return data of verifier generated with
+/// [`EvmLoader::new_with_deferred_pairing`]. Returns `None` if the data isn't
+/// 4 words of canonical coordinates of points on curve.
+///
+/// [`EvmLoader::new_with_deferred_pairing`]: crate::loader::evm::EvmLoader::new_with_deferred_pairing
+pub fn decode_accumulator<C>(data: &[u8]) -> Option<KzgAccumulator<C, NativeLoader>>
+where
+    C: CurveAffine,
+    C::Base: PrimeField<Repr = [u8; 32]>,
+{
+    if data.len() != 0x80 {
+        return None;
+    }
+    let [lhs, rhs] = [0, 0x40].map(|offset| {
+        let [x, y] = [offset, offset + 0x20].map(|offset| {
+            let mut repr = [0; 32];
+            repr.copy_from_slice(&data[offset..offset + 0x20]);
+            repr.reverse();
+            Option::<C::Base>::from(C::Base::from_repr(repr))
+        });
+        Option::<C>::from(C::from_xy(x?, y?))
+    });
+    Some(KzgAccumulator::new(lhs?, rhs?))
+}
+
 /// Estimate gas cost with given [`Cost`].
+///
+/// This is a rough formula, see [`EvmLoader::estimate_gas`] for an estimate
+/// from the generated verifier itself.
+///
+/// [`EvmLoader::estimate_gas`]: crate::loader::evm::EvmLoader::estimate_gas
 pub fn estimate_gas(cost: Cost) -> usize {
     let proof_size = cost.num_commitment * 64 + (cost.num_evaluation + cost.num_instance) * 32;
 
     let intrinsic_cost = 21000;
     let calldata_cost = (proof_size as f64 * 15.25).ceil() as usize;
-    let ec_operation_cost = 113100 + (cost.num_msm - 2) * 6350;
+    let ec_operation_cost = 113100 + cost.num_msm.saturating_sub(2) * 6350;
 
     intrinsic_cost + calldata_cost + ec_operation_cost
 }
 
-/// Compile given yul `code` into deployment bytecode.
-pub fn compile_yul(code: &str) -> Vec<u8> {
+/// Compile given Solidity `code` into deployment bytecode, or returns the
+/// compiler's error output.
+pub fn compile_solidity(code: &str) -> Result<Vec<u8>, String> {
+    solc(&["--bin", "-"], code)
+}
+
+/// Compile given yul `code` into deployment bytecode, or returns the
+/// compiler's error output.
+///
+/// When `solc` is not available, [`assemble_yul`] can be used instead.
+pub fn compile_yul(code: &str) -> Result<Vec<u8>, String> {
+    solc(&["--bin", "--yul", "-"], code)
+}
+
+fn solc(args: &[&str], code: &str) -> Result<Vec<u8>, String> {
     let mut cmd = Command::new("solc")
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .arg("--bin")
-        .arg("--yul")
-        .arg("-")
+        .stderr(Stdio::piped())
+        .args(args)
         .spawn()
-        .unwrap();
-    cmd.stdin.take().unwrap().write_all(code.as_bytes()).unwrap();
-    let output = cmd.wait_with_output().unwrap().stdout;
-    let binary = *split_by_ascii_whitespace(&output).last().unwrap();
-    hex::decode(binary).unwrap()
+        .map_err(|err| format!("Failed to spawn solc: {err}"))?;
+    cmd.stdin
+        .take()
+        .unwrap()
+        .write_all(code.as_bytes())
+        .map_err(|err| format!("Failed to write code to solc: {err}"))?;
+    let output = cmd.wait_with_output().map_err(|err| format!("Failed to wait solc: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).into_owned());
+    }
+    let binary = split_by_ascii_whitespace(&output.stdout)
+        .last()
+        .copied()
+        .ok_or_else(|| String::from_utf8_lossy(&output.stderr).into_owned())?;
+    hex::decode(binary).map_err(|err| format!("Invalid bytecode from solc: {err}"))
 }
 
 fn split_by_ascii_whitespace(bytes: &[u8]) -> Vec<&[u8]> {
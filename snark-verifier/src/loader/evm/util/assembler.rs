@@ -0,0 +1,1023 @@
+//! Assembler for the subset of Yul emitted by [`EvmLoader`], which turns it
+//! into deployment bytecode without an external compiler.
+//!
+//! Supported are objects with nested sub-objects, blocks, function
+//! definitions with at most one return variable, `let`, assignment, `if`, and
+//! calls of user functions or EVM builtins. Type annotations follow the typed
+//! Yul dialect used by `solc --yul`, so `not` of a `bool` is `iszero`.
+//!
+//! [`EvmLoader`]: crate::loader::evm::EvmLoader
+
+use ethereum_types::U256;
+use std::collections::HashMap;
+
+/// Assemble given Yul `code` into deployment bytecode.
+pub fn assemble_yul(code: &str) -> Result<Vec<u8>, String> {
+    let tokens = tokenize(code)?;
+    let object = Parser { tokens, idx: 0 }.object()?;
+    assemble_object(&object)
+}
+
+/// Returns gas of opcodes executed by code of the top-level object in given
+/// Yul `code`, priced by `opcode_gas`, assuming every statement in the code
+/// is executed and every function call executes the whole function body.
+/// Dynamic gas like memory expansion is not included.
+pub(crate) fn execution_gas(code: &str, opcode_gas: impl Fn(u8) -> u64) -> Result<u64, String> {
+    let tokens = tokenize(code)?;
+    let object = Parser { tokens, idx: 0 }.object()?;
+    Assembler::new(&object)?.execution_gas(None, &opcode_gas, &mut Vec::new())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Identifier(String),
+    Number(U256),
+    String(String),
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    Comma,
+    Colon,
+    Assign,
+    Arrow,
+}
+
+fn tokenize(code: &str) -> Result<Vec<Token>, String> {
+    let bytes = code.as_bytes();
+    let is_identifier = |byte: u8, start: bool| {
+        byte.is_ascii_alphabetic()
+            || byte == b'_'
+            || byte == b'$'
+            || (!start && (byte.is_ascii_digit() || byte == b'.'))
+    };
+
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let byte = bytes[idx];
+        let start = idx;
+        match byte {
+            _ if byte.is_ascii_whitespace() => idx += 1,
+            b'/' if bytes.get(idx + 1) == Some(&b'/') => {
+                while idx < bytes.len() && bytes[idx] != b'\n' {
+                    idx += 1;
+                }
+            }
+            b'/' if bytes.get(idx + 1) == Some(&b'*') => {
+                let end = code[idx + 2..].find("*/").ok_or("Unterminated comment")?;
+                idx += end + 4;
+            }
+            b'{' | b'}' | b'(' | b')' | b',' => {
+                tokens.push(match byte {
+                    b'{' => Token::LBrace,
+                    b'}' => Token::RBrace,
+                    b'(' => Token::LParen,
+                    b')' => Token::RParen,
+                    _ => Token::Comma,
+                });
+                idx += 1;
+            }
+            b':' if bytes.get(idx + 1) == Some(&b'=') => {
+                tokens.push(Token::Assign);
+                idx += 2;
+            }
+            b':' => {
+                tokens.push(Token::Colon);
+                idx += 1;
+            }
+            b'-' if bytes.get(idx + 1) == Some(&b'>') => {
+                tokens.push(Token::Arrow);
+                idx += 2;
+            }
+            b'"' => {
+                let end = code[idx + 1..].find('"').ok_or("Unterminated string literal")?;
+                tokens.push(Token::String(code[idx + 1..idx + 1 + end].to_string()));
+                idx += end + 2;
+            }
+            _ if byte.is_ascii_digit() => {
+                while idx < bytes.len() && bytes[idx].is_ascii_alphanumeric() {
+                    idx += 1;
+                }
+                let literal = &code[start..idx];
+                let value = match literal.strip_prefix("0x") {
+                    Some(hex) => U256::from_str_radix(hex, 16).ok(),
+                    None => U256::from_dec_str(literal).ok(),
+                }
+                .ok_or_else(|| format!("Invalid number literal {literal}"))?;
+                tokens.push(Token::Number(value));
+            }
+            _ if is_identifier(byte, true) => {
+                while idx < bytes.len() && is_identifier(bytes[idx], false) {
+                    idx += 1;
+                }
+                tokens.push(Token::Identifier(code[start..idx].to_string()));
+            }
+            _ => return Err(format!("Unexpected character {:?}", byte as char)),
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Type {
+    U256,
+    Bool,
+}
+
+#[derive(Clone, Debug)]
+enum Expression {
+    Literal(U256, Type),
+    String(String),
+    Identifier(String),
+    Call(String, Vec<Expression>),
+}
+
+#[derive(Clone, Debug)]
+struct Function {
+    name: String,
+    params: Vec<(String, Type)>,
+    returns: Vec<(String, Type)>,
+    body: Vec<Statement>,
+}
+
+#[derive(Clone, Debug)]
+enum Statement {
+    Block(Vec<Statement>),
+    Function(Function),
+    Let(Vec<(String, Option<Type>)>, Option<Expression>),
+    Assign(Vec<String>, Expression),
+    If(Expression, Vec<Statement>),
+    Expression(Expression),
+}
+
+#[derive(Clone, Debug)]
+struct Object {
+    name: String,
+    code: Vec<Statement>,
+    objects: Vec<Object>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    idx: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.idx)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self.tokens.get(self.idx).cloned().ok_or("Unexpected end of code")?;
+        self.idx += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(format!("Expected {expected:?} but found {token:?}")),
+        }
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        let matched = self.peek() == Some(expected);
+        if matched {
+            self.idx += 1;
+        }
+        matched
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Identifier(identifier) => Ok(identifier),
+            token => Err(format!("Expected identifier but found {token:?}")),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), String> {
+        match self.identifier()? {
+            identifier if identifier == keyword => Ok(()),
+            identifier => Err(format!("Expected {keyword} but found {identifier}")),
+        }
+    }
+
+    fn ty(&mut self) -> Result<Option<Type>, String> {
+        if !self.eat(&Token::Colon) {
+            return Ok(None);
+        }
+        match self.identifier()?.as_str() {
+            "u256" => Ok(Some(Type::U256)),
+            "bool" => Ok(Some(Type::Bool)),
+            ty => Err(format!("Unsupported type {ty}")),
+        }
+    }
+
+    fn typed_identifiers(&mut self) -> Result<Vec<(String, Option<Type>)>, String> {
+        let mut identifiers = vec![(self.identifier()?, self.ty()?)];
+        while self.eat(&Token::Comma) {
+            identifiers.push((self.identifier()?, self.ty()?));
+        }
+        Ok(identifiers)
+    }
+
+    fn object(&mut self) -> Result<Object, String> {
+        self.keyword("object")?;
+        let name = match self.next()? {
+            Token::String(name) => name,
+            token => Err(format!("Expected object name but found {token:?}"))?,
+        };
+        self.expect(Token::LBrace)?;
+        self.keyword("code")?;
+        let code = self.block()?;
+        let mut objects = Vec::new();
+        while !self.eat(&Token::RBrace) {
+            objects.push(self.object()?);
+        }
+        Ok(Object { name, code, objects })
+    }
+
+    fn block(&mut self) -> Result<Vec<Statement>, String> {
+        self.expect(Token::LBrace)?;
+        let mut statements = Vec::new();
+        while !self.eat(&Token::RBrace) {
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<Statement, String> {
+        if self.peek() == Some(&Token::LBrace) {
+            return Ok(Statement::Block(self.block()?));
+        }
+        let identifier = self.identifier()?;
+        match identifier.as_str() {
+            "function" => {
+                let name = self.identifier()?;
+                self.expect(Token::LParen)?;
+                let mut params = Vec::new();
+                if !self.eat(&Token::RParen) {
+                    params = self.typed_identifiers()?;
+                    self.expect(Token::RParen)?;
+                }
+                let returns =
+                    if self.eat(&Token::Arrow) { self.typed_identifiers()? } else { Vec::new() };
+                let with_default = |identifiers: Vec<(String, Option<Type>)>| {
+                    identifiers
+                        .into_iter()
+                        .map(|(name, ty)| (name, ty.unwrap_or(Type::U256)))
+                        .collect()
+                };
+                let body = self.block()?;
+                Ok(Statement::Function(Function {
+                    name,
+                    params: with_default(params),
+                    returns: with_default(returns),
+                    body,
+                }))
+            }
+            "let" => {
+                let identifiers = self.typed_identifiers()?;
+                let value = if self.eat(&Token::Assign) { Some(self.expression()?) } else { None };
+                Ok(Statement::Let(identifiers, value))
+            }
+            "if" => {
+                let condition = self.expression()?;
+                Ok(Statement::If(condition, self.block()?))
+            }
+            "for" | "switch" | "break" | "continue" | "leave" => {
+                Err(format!("Unsupported statement {identifier}"))
+            }
+            _ => match self.peek() {
+                Some(Token::LParen) => Ok(Statement::Expression(self.call(identifier)?)),
+                _ => {
+                    let mut identifiers = vec![identifier];
+                    while self.eat(&Token::Comma) {
+                        identifiers.push(self.identifier()?);
+                    }
+                    self.expect(Token::Assign)?;
+                    Ok(Statement::Assign(identifiers, self.expression()?))
+                }
+            },
+        }
+    }
+
+    fn call(&mut self, name: String) -> Result<Expression, String> {
+        self.expect(Token::LParen)?;
+        let mut args = Vec::new();
+        if !self.eat(&Token::RParen) {
+            args.push(self.expression()?);
+            while self.eat(&Token::Comma) {
+                args.push(self.expression()?);
+            }
+            self.expect(Token::RParen)?;
+        }
+        Ok(Expression::Call(name, args))
+    }
+
+    fn expression(&mut self) -> Result<Expression, String> {
+        match self.next()? {
+            Token::Number(value) => {
+                self.ty()?;
+                Ok(Expression::Literal(value, Type::U256))
+            }
+            Token::String(string) => Ok(Expression::String(string)),
+            Token::Identifier(identifier) => match identifier.as_str() {
+                "true" => Ok(Expression::Literal(U256::one(), Type::Bool)),
+                "false" => Ok(Expression::Literal(U256::zero(), Type::Bool)),
+                _ if self.peek() == Some(&Token::LParen) => self.call(identifier),
+                _ => Ok(Expression::Identifier(identifier)),
+            },
+            token => Err(format!("Unexpected token {token:?}")),
+        }
+    }
+}
+
+/// Returns opcode, number of arguments and number of return values of EVM
+/// builtin.
+fn builtin(name: &str) -> Option<(u8, usize, usize)> {
+    Some(match name {
+        "stop" => (0x00, 0, 0),
+        "add" => (0x01, 2, 1),
+        "mul" => (0x02, 2, 1),
+        "sub" => (0x03, 2, 1),
+        "div" => (0x04, 2, 1),
+        "sdiv" => (0x05, 2, 1),
+        "mod" => (0x06, 2, 1),
+        "smod" => (0x07, 2, 1),
+        "addmod" => (0x08, 3, 1),
+        "mulmod" => (0x09, 3, 1),
+        "exp" => (0x0a, 2, 1),
+        "signextend" => (0x0b, 2, 1),
+        "lt" => (0x10, 2, 1),
+        "gt" => (0x11, 2, 1),
+        "slt" => (0x12, 2, 1),
+        "sgt" => (0x13, 2, 1),
+        "eq" => (0x14, 2, 1),
+        "iszero" => (0x15, 1, 1),
+        "and" => (0x16, 2, 1),
+        "or" => (0x17, 2, 1),
+        "xor" => (0x18, 2, 1),
+        "not" => (0x19, 1, 1),
+        "byte" => (0x1a, 2, 1),
+        "shl" => (0x1b, 2, 1),
+        "shr" => (0x1c, 2, 1),
+        "sar" => (0x1d, 2, 1),
+        "keccak256" => (0x20, 2, 1),
+        "address" => (0x30, 0, 1),
+        "balance" => (0x31, 1, 1),
+        "origin" => (0x32, 0, 1),
+        "caller" => (0x33, 0, 1),
+        "callvalue" => (0x34, 0, 1),
+        "calldataload" => (0x35, 1, 1),
+        "calldatasize" => (0x36, 0, 1),
+        "calldatacopy" => (0x37, 3, 0),
+        "codesize" => (0x38, 0, 1),
+        "codecopy" | "datacopy" => (0x39, 3, 0),
+        "gasprice" => (0x3a, 0, 1),
+        "extcodesize" => (0x3b, 1, 1),
+        "extcodecopy" => (0x3c, 4, 0),
+        "returndatasize" => (0x3d, 0, 1),
+        "returndatacopy" => (0x3e, 3, 0),
+        "extcodehash" => (0x3f, 1, 1),
+        "blockhash" => (0x40, 1, 1),
+        "coinbase" => (0x41, 0, 1),
+        "timestamp" => (0x42, 0, 1),
+        "number" => (0x43, 0, 1),
+        "difficulty" => (0x44, 0, 1),
+        "gaslimit" => (0x45, 0, 1),
+        "chainid" => (0x46, 0, 1),
+        "selfbalance" => (0x47, 0, 1),
+        "pop" => (0x50, 1, 0),
+        "mload" => (0x51, 1, 1),
+        "mstore" => (0x52, 2, 0),
+        "mstore8" => (0x53, 2, 0),
+        "sload" => (0x54, 1, 1),
+        "sstore" => (0x55, 2, 0),
+        "msize" => (0x59, 0, 1),
+        "gas" => (0x5a, 0, 1),
+        "log0" => (0xa0, 2, 0),
+        "log1" => (0xa1, 3, 0),
+        "log2" => (0xa2, 4, 0),
+        "log3" => (0xa3, 5, 0),
+        "log4" => (0xa4, 6, 0),
+        "create" => (0xf0, 3, 1),
+        "call" => (0xf1, 7, 1),
+        "callcode" => (0xf2, 7, 1),
+        "return" => (0xf3, 2, 0),
+        "delegatecall" => (0xf4, 6, 1),
+        "create2" => (0xf5, 4, 1),
+        "staticcall" => (0xfa, 6, 1),
+        "revert" => (0xfd, 2, 0),
+        "invalid" => (0xfe, 0, 0),
+        "selfdestruct" => (0xff, 1, 0),
+        _ => return None,
+    })
+}
+
+const STOP: u8 = 0x00;
+const ISZERO: u8 = 0x15;
+const POP: u8 = 0x50;
+const JUMP: u8 = 0x56;
+const JUMPI: u8 = 0x57;
+const JUMPDEST: u8 = 0x5b;
+const PUSH1: u8 = 0x60;
+const PUSH2: u8 = 0x61;
+const PUSH4: u8 = 0x63;
+const PUSH32: u8 = 0x7f;
+const DUP1: u8 = 0x80;
+const SWAP1: u8 = 0x90;
+
+#[derive(Clone, Debug)]
+struct FunctionInfo {
+    label: usize,
+    params: Vec<Type>,
+    returns: Vec<Type>,
+}
+
+type Scopes = Vec<HashMap<String, FunctionInfo>>;
+
+#[derive(Clone, Debug)]
+struct Slot {
+    name: Option<String>,
+    ty: Type,
+}
+
+#[derive(Default)]
+struct Assembler<'a> {
+    code: Vec<u8>,
+    labels: Vec<Option<usize>>,
+    label_fixups: Vec<(usize, usize)>,
+    data_offset_fixups: Vec<(usize, usize)>,
+    objects: Vec<(&'a str, Vec<u8>)>,
+    stack: Vec<Slot>,
+    scopes: Scopes,
+    pending: Vec<(Function, Scopes)>,
+    // label of function being assembled, or `None` for the top-level code
+    context: Option<usize>,
+    // code range of top-level code and each function
+    regions: HashMap<Option<usize>, (usize, usize)>,
+    // function calls as pairs of caller context and callee label
+    calls: Vec<(Option<usize>, usize)>,
+}
+
+fn assemble_object(object: &Object) -> Result<Vec<u8>, String> {
+    Assembler::new(object)?.finalize()
+}
+
+impl<'a> Assembler<'a> {
+    fn new(object: &'a Object) -> Result<Self, String> {
+        let objects = object
+            .objects
+            .iter()
+            .map(|sub_object| Ok((sub_object.name.as_str(), assemble_object(sub_object)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let mut assembler = Assembler { objects, ..Default::default() };
+        assembler.block(&object.code)?;
+        assembler.code.push(STOP);
+        assembler.regions.insert(None, (0, assembler.code.len()));
+        while let Some((function, scopes)) = assembler.pending.pop() {
+            assembler.function(&function, scopes)?;
+        }
+        Ok(assembler)
+    }
+
+    fn execution_gas(
+        &self,
+        context: Option<usize>,
+        opcode_gas: &impl Fn(u8) -> u64,
+        callers: &mut Vec<Option<usize>>,
+    ) -> Result<u64, String> {
+        if callers.contains(&context) {
+            return Err("Recursive function is not supported".to_string());
+        }
+
+        let (start, end) = self.regions[&context];
+        let mut gas = 0;
+        let mut pc = start;
+        while pc < end {
+            let opcode = self.code[pc];
+            gas += opcode_gas(opcode);
+            pc += match opcode {
+                PUSH1..=PUSH32 => (opcode - PUSH1) as usize + 2,
+                _ => 1,
+            };
+        }
+
+        callers.push(context);
+        for (_, callee) in self.calls.iter().filter(|(caller, _)| *caller == context) {
+            gas += self.execution_gas(Some(*callee), opcode_gas, callers)?;
+        }
+        callers.pop();
+        Ok(gas)
+    }
+
+    fn new_label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn place_label(&mut self, label: usize) {
+        self.labels[label] = Some(self.code.len());
+        self.code.push(JUMPDEST);
+    }
+
+    fn push_label(&mut self, label: usize) {
+        self.code.push(PUSH2);
+        self.label_fixups.push((self.code.len(), label));
+        self.code.extend([0, 0]);
+    }
+
+    fn push_value(&mut self, value: U256) {
+        let mut bytes = [0; 32];
+        value.to_big_endian(&mut bytes);
+        let len = 32 - bytes.iter().take_while(|byte| **byte == 0).count();
+        let len = len.max(1);
+        self.code.push(PUSH1 + len as u8 - 1);
+        self.code.extend(&bytes[32 - len..]);
+    }
+
+    fn stack_push(&mut self, ty: Type) {
+        self.stack.push(Slot { name: None, ty });
+    }
+
+    fn stack_pop(&mut self, n: usize) {
+        self.stack.truncate(self.stack.len() - n);
+    }
+
+    fn depth(&self, name: &str) -> Result<(usize, Type), String> {
+        self.stack
+            .iter()
+            .rev()
+            .position(|slot| slot.name.as_deref() == Some(name))
+            .map(|depth| (depth, self.stack[self.stack.len() - 1 - depth].ty))
+            .ok_or_else(|| format!("Undeclared variable {name}"))
+    }
+
+    fn dup(&mut self, depth: usize) -> Result<(), String> {
+        if depth >= 16 {
+            return Err("Stack too deep".to_string());
+        }
+        self.code.push(DUP1 + depth as u8);
+        Ok(())
+    }
+
+    fn swap(&mut self, depth: usize) -> Result<(), String> {
+        if depth == 0 || depth > 16 {
+            return Err("Stack too deep".to_string());
+        }
+        self.code.push(SWAP1 + depth as u8 - 1);
+        Ok(())
+    }
+
+    fn lookup_function(&self, name: &str) -> Option<FunctionInfo> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned()
+    }
+
+    fn block(&mut self, statements: &[Statement]) -> Result<(), String> {
+        let mut scope = HashMap::new();
+        for statement in statements {
+            if let Statement::Function(function) = statement {
+                let info = FunctionInfo {
+                    label: self.new_label(),
+                    params: function.params.iter().map(|(_, ty)| *ty).collect(),
+                    returns: function.returns.iter().map(|(_, ty)| *ty).collect(),
+                };
+                if scope.insert(function.name.clone(), info).is_some() {
+                    return Err(format!("Duplicate function {}", function.name));
+                }
+            }
+        }
+        self.scopes.push(scope);
+        for statement in statements {
+            if let Statement::Function(function) = statement {
+                self.pending.push((function.clone(), self.scopes.clone()));
+            }
+        }
+
+        let height = self.stack.len();
+        for statement in statements {
+            self.statement(statement)?;
+        }
+        for _ in height..self.stack.len() {
+            self.code.push(POP);
+        }
+        self.stack.truncate(height);
+
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), String> {
+        match statement {
+            Statement::Block(statements) => self.block(statements)?,
+            Statement::Function(_) => {}
+            Statement::Let(identifiers, value) => {
+                let tys = match value {
+                    Some(value) => self.expression(value, identifiers.len())?,
+                    None => identifiers
+                        .iter()
+                        .map(|_| {
+                            self.push_value(U256::zero());
+                            self.stack_push(Type::U256);
+                            Type::U256
+                        })
+                        .collect(),
+                };
+                let height = self.stack.len() - identifiers.len();
+                for (slot, ((name, ty), inferred)) in
+                    self.stack[height..].iter_mut().zip(identifiers.iter().zip(tys))
+                {
+                    *slot = Slot { name: Some(name.clone()), ty: ty.unwrap_or(inferred) };
+                }
+            }
+            Statement::Assign(identifiers, value) => {
+                self.expression(value, identifiers.len())?;
+                for name in identifiers.iter().rev() {
+                    let (depth, _) = self.depth(name)?;
+                    self.swap(depth)?;
+                    self.code.push(POP);
+                    self.stack_pop(1);
+                }
+            }
+            Statement::If(condition, statements) => {
+                self.expression(condition, 1)?;
+                let end = self.new_label();
+                self.code.push(ISZERO);
+                self.push_label(end);
+                self.code.push(JUMPI);
+                self.stack_pop(1);
+                self.block(statements)?;
+                self.place_label(end);
+            }
+            Statement::Expression(expression) => {
+                self.expression(expression, 0)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Pushes `num_value` values of `expression` onto stack and returns
+    /// their types.
+    fn expression(
+        &mut self,
+        expression: &Expression,
+        num_value: usize,
+    ) -> Result<Vec<Type>, String> {
+        let tys = match expression {
+            Expression::Literal(value, ty) => {
+                self.push_value(*value);
+                self.stack_push(*ty);
+                vec![*ty]
+            }
+            Expression::String(string) => {
+                return Err(format!("Unexpected string literal {string:?}"));
+            }
+            Expression::Identifier(name) => {
+                let (depth, ty) = self.depth(name)?;
+                self.dup(depth)?;
+                self.stack_push(ty);
+                vec![ty]
+            }
+            Expression::Call(name, args) => self.call(name, args)?,
+        };
+        if tys.len() != num_value {
+            return Err(format!("Expected {num_value} values but found {}", tys.len()));
+        }
+        Ok(tys)
+    }
+
+    fn call(&mut self, name: &str, args: &[Expression]) -> Result<Vec<Type>, String> {
+        if let "datasize" | "dataoffset" = name {
+            let object = match args {
+                [Expression::String(object)] => object,
+                _ => return Err(format!("Invalid arguments of {name}")),
+            };
+            let idx = self
+                .objects
+                .iter()
+                .position(|(name, _)| name == object)
+                .ok_or_else(|| format!("Unknown object {object}"))?;
+            if name == "datasize" {
+                self.push_value(self.objects[idx].1.len().into());
+            } else {
+                self.code.push(PUSH4);
+                self.data_offset_fixups.push((self.code.len(), idx));
+                self.code.extend([0; 4]);
+            }
+            self.stack_push(Type::U256);
+            return Ok(vec![Type::U256]);
+        }
+
+        if let Some(function) = self.lookup_function(name) {
+            if args.len() != function.params.len() {
+                return Err(format!("Invalid number of arguments of {name}"));
+            }
+            self.calls.push((self.context, function.label));
+            let ret = self.new_label();
+            self.push_label(ret);
+            self.stack_push(Type::U256);
+            for arg in args.iter().rev() {
+                self.expression(arg, 1)?;
+            }
+            self.push_label(function.label);
+            self.code.push(JUMP);
+            self.place_label(ret);
+            self.stack_pop(args.len() + 1);
+            for ty in function.returns.iter() {
+                self.stack_push(*ty);
+            }
+            return Ok(function.returns);
+        }
+
+        let (opcode, num_arg, num_ret) =
+            builtin(name).ok_or_else(|| format!("Unknown function {name}"))?;
+        if args.len() != num_arg {
+            return Err(format!("Invalid number of arguments of {name}"));
+        }
+        let mut tys = args
+            .iter()
+            .rev()
+            .map(|arg| Ok(self.expression(arg, 1)?[0]))
+            .collect::<Result<Vec<_>, String>>()?;
+        tys.reverse();
+        let (opcode, ty) = match name {
+            // Follow the typed dialect, where `not` of `bool` is logical
+            "not" if tys[0] == Type::Bool => (ISZERO, Type::Bool),
+            "lt" | "gt" | "slt" | "sgt" | "eq" | "iszero" => (opcode, Type::Bool),
+            "and" | "or" | "xor" if tys.iter().all(|ty| *ty == Type::Bool) => (opcode, Type::Bool),
+            _ => (opcode, Type::U256),
+        };
+        self.code.push(opcode);
+        self.stack_pop(num_arg);
+        let tys = vec![ty; num_ret];
+        for ty in tys.iter() {
+            self.stack_push(*ty);
+        }
+        Ok(tys)
+    }
+
+    fn function(&mut self, function: &Function, scopes: Scopes) -> Result<(), String> {
+        let info =
+            scopes.iter().rev().find_map(|scope| scope.get(&function.name)).cloned().unwrap();
+        if function.returns.len() > 1 {
+            return Err(format!("Function {} has more than one return value", function.name));
+        }
+
+        self.scopes = scopes;
+        self.stack = Vec::new();
+        self.context = Some(info.label);
+        let start = self.code.len();
+        self.place_label(info.label);
+        self.stack_push(Type::U256);
+        for (name, ty) in function.params.iter().rev() {
+            self.stack.push(Slot { name: Some(name.clone()), ty: *ty });
+        }
+        for (name, ty) in function.returns.iter() {
+            self.push_value(U256::zero());
+            self.stack.push(Slot { name: Some(name.clone()), ty: *ty });
+        }
+
+        self.block(&function.body)?;
+
+        let num_param = function.params.len();
+        if function.returns.len() == 1 {
+            self.swap(num_param + 1)?;
+            for _ in 0..num_param {
+                self.code.extend([SWAP1, POP]);
+            }
+        } else {
+            for _ in 0..num_param {
+                self.code.push(POP);
+            }
+        }
+        self.code.push(JUMP);
+        self.regions.insert(self.context, (start, self.code.len()));
+        Ok(())
+    }
+
+    fn finalize(mut self) -> Result<Vec<u8>, String> {
+        if self.code.len() > u16::MAX as usize {
+            return Err("Code is too large to be addressed by PUSH2".to_string());
+        }
+        for (position, label) in self.label_fixups.iter() {
+            let destination = self.labels[*label].unwrap() as u16;
+            self.code[*position..*position + 2].copy_from_slice(&destination.to_be_bytes());
+        }
+        let offsets = self
+            .objects
+            .iter()
+            .scan(self.code.len(), |offset, (_, code)| {
+                let current = *offset;
+                *offset += code.len();
+                Some(current as u32)
+            })
+            .collect::<Vec<_>>();
+        for (position, idx) in self.data_offset_fixups.iter() {
+            self.code[*position..*position + 4].copy_from_slice(&offsets[*idx].to_be_bytes());
+        }
+        Ok(self
+            .code
+            .into_iter()
+            .chain(self.objects.into_iter().flat_map(|(_, code)| code))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::assemble_yul;
+    use crate::{loader::evm::ExecutorBuilder, util::Itertools};
+    use ethereum_types::{Address, U256};
+
+    /// Deploys given runtime `object` and returns output of calling it.
+    fn call(object: &str) -> Vec<u8> {
+        let code = format!(
+            r#"object "Test" {{
+                code {{
+                    datacopy(0, dataoffset("Runtime"), datasize("Runtime"))
+                    return(0, datasize("Runtime"))
+                }}
+                {object}
+            }}"#
+        );
+        let caller = Address::from_low_u64_be(0xfe);
+        let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+        let contract =
+            evm.deploy(caller, assemble_yul(&code).unwrap().into(), 0.into()).address.unwrap();
+        let result = evm.call_raw(caller, contract, Vec::<u8>::new().into(), 0.into());
+        assert!(!result.reverted);
+        result.result.to_vec()
+    }
+
+    fn call_code(code: &str) -> Vec<U256> {
+        call(&format!(r#"object "Runtime" {{ code {{ {code} }} }}"#))
+            .chunks(32)
+            .map(U256::from_big_endian)
+            .collect()
+    }
+
+    fn assemble_code(code: &str) -> Result<Vec<u8>, String> {
+        assemble_yul(&format!(r#"object "Test" {{ code {{ {code} }} }}"#))
+    }
+
+    #[test]
+    fn test_function_call() {
+        let output = call_code(
+            "
+            function mul_add(a, b, c) -> r {
+                r := add(a, mul(b, c))
+            }
+            function answer() -> r {
+                r := mul_add(2, 4, 10)
+            }
+            function store(ptr, a, b) {
+                mstore(ptr, a)
+                mstore(add(ptr, 0x20), b)
+            }
+            store(0, mul_add(1, 2, 3), answer())
+            return(0, 0x40)
+            ",
+        );
+        assert_eq!(output, [U256::from(7), U256::from(42)]);
+    }
+
+    #[test]
+    fn test_block_scope() {
+        let output = call_code(
+            "
+            let x := 1
+            {
+                let y := 2
+                {
+                    let z := 3
+                    x := add(x, z)
+                }
+                x := add(x, y)
+            }
+            {
+                let y := 5
+                x := add(x, y)
+            }
+            function f(a) -> r {
+                {
+                    let t := mul(a, 2)
+                    r := add(t, 1)
+                }
+            }
+            let w := f(4)
+            mstore(0, x)
+            mstore(0x20, w)
+            return(0, 0x40)
+            ",
+        );
+        assert_eq!(output, [U256::from(11), U256::from(9)]);
+    }
+
+    #[test]
+    fn test_multi_target_assignment() {
+        let output = call_code(
+            "
+            let a, b
+            mstore(0, add(a, b))
+            a := 1
+            b := 2
+            mstore(0x20, sub(b, a))
+            return(0, 0x40)
+            ",
+        );
+        assert_eq!(output, [U256::from(0), U256::from(1)]);
+
+        assert_eq!(
+            assemble_code("let a, b a, b := add(1, 2)"),
+            Err("Expected 2 values but found 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_if() {
+        let output = call_code(
+            "
+            function max(a, b) -> r {
+                r := a
+                if gt(b, a) {
+                    r := b
+                }
+            }
+            let c := 0
+            if not(eq(c, 0)) {
+                c := 1
+            }
+            mstore(0, max(3, 5))
+            mstore(0x20, max(7, 2))
+            mstore(0x40, c)
+            return(0, 0x60)
+            ",
+        );
+        assert_eq!(output, [U256::from(5), U256::from(7), U256::from(0)]);
+    }
+
+    #[test]
+    fn test_data_size_and_offset() {
+        let inner = r#"object "B" { code { mstore(0, 1) return(0, 0x20) } }"#;
+        let output = call(&format!(
+            r#"object "Runtime" {{
+                code {{
+                    mstore(0, datasize("A"))
+                    mstore(0x20, sub(dataoffset("B"), dataoffset("A")))
+                    datacopy(0x40, dataoffset("B"), datasize("B"))
+                    return(0, add(0x40, datasize("B")))
+                }}
+                object "A" {{ code {{ }} }}
+                {inner}
+            }}"#
+        ));
+        assert_eq!(U256::from_big_endian(&output[..0x20]), U256::one());
+        assert_eq!(U256::from_big_endian(&output[0x20..0x40]), U256::one());
+        assert_eq!(output[0x40..], assemble_yul(inner).unwrap());
+
+        assert_eq!(assemble_code(r#"pop(datasize("C"))"#), Err("Unknown object C".to_string()));
+    }
+
+    #[test]
+    fn test_stack_too_deep() {
+        let declare = |n: usize| (0..n).map(|i| format!("let v{i} := {}", i + 1)).join("\n");
+
+        let output = call_code(&format!("{}\nmstore(0, v0)\nreturn(0, 0x20)", declare(16)));
+        assert_eq!(output, [U256::one()]);
+
+        assert_eq!(
+            assemble_code(&format!("{}\nmstore(0, v0)", declare(17))),
+            Err("Stack too deep".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unsupported_statement() {
+        for (statement, code) in [
+            ("for", "for { let i := 0 } lt(i, 2) { i := add(i, 1) } { }"),
+            ("switch", "switch calldatasize() case 0 { }"),
+            ("leave", "function f() { leave }"),
+        ] {
+            assert_eq!(assemble_code(code), Err(format!("Unsupported statement {statement}")));
+        }
+    }
+
+    #[test]
+    fn test_multiple_return_values() {
+        assert_eq!(
+            assemble_code(
+                "
+                function pair() -> a, b {
+                    a := 1
+                    b := 2
+                }
+                let x, y := pair()
+                "
+            ),
+            Err("Function pair has more than one return value".to_string())
+        );
+    }
+}
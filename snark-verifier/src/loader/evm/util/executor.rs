@@ -4,9 +4,10 @@
 use bytes::Bytes;
 use ethereum_types::{Address, H256, U256, U64};
 use revm::{
-    evm_inner, opcode, spec_opcode_gas, Account, BlockEnv, CallInputs, CallScheme, CreateInputs,
-    CreateScheme, Database, DatabaseCommit, EVMData, Env, ExecutionResult, Gas, GasInspector,
-    InMemoryDB, Inspector, Interpreter, Memory, OpCode, Return, TransactOut, TransactTo, TxEnv,
+    evm_inner, opcode, spec_opcode_gas, Account, AccountInfo, BlockEnv, CallInputs, CallScheme,
+    CreateInputs, CreateScheme, Database, DatabaseCommit, EVMData, Env, ExecutionResult, Gas,
+    GasInspector, InMemoryDB, Inspector, Interpreter, Memory, OpCode, Return, TransactOut,
+    TransactTo, TxEnv,
 };
 use sha3::{Digest, Keccak256};
 use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
@@ -21,7 +22,8 @@ fn keccak256(data: impl AsRef<[u8]>) -> [u8; 32] {
     Keccak256::digest(data.as_ref()).into()
 }
 
-fn get_contract_address(sender: impl Into<Address>, nonce: impl Into<U256>) -> Address {
+/// Returns address of contract deployed by `sender` with `nonce`.
+pub fn get_contract_address(sender: impl Into<Address>, nonce: impl Into<U256>) -> Address {
     let mut stream = rlp::RlpStream::new();
     stream.begin_list(2);
     stream.append(&sender.into());
@@ -108,6 +110,25 @@ impl<DB: Database> Inspector<DB> for LogCollector {
     }
 }
 
+/// Tracks the highest memory size of any call frame.
+#[derive(Clone, Debug, Default)]
+struct MemoryTracker {
+    memory_size: usize,
+}
+
+impl<DB: Database> Inspector<DB> for MemoryTracker {
+    fn step_end(
+        &mut self,
+        interpreter: &mut Interpreter,
+        _: &mut EVMData<'_, DB>,
+        _: bool,
+        _: Return,
+    ) -> Return {
+        self.memory_size = self.memory_size.max(interpreter.memory.len());
+        Return::Continue
+    }
+}
+
 #[derive(Clone, Debug, Copy)]
 pub enum CallKind {
     Call,
@@ -412,6 +433,7 @@ macro_rules! call_inspectors {
 struct InspectorData {
     logs: Vec<Log>,
     debug: Option<DebugArena>,
+    memory_size: usize,
 }
 
 #[derive(Default)]
@@ -419,6 +441,7 @@ struct InspectorStack {
     gas: Option<Rc<RefCell<GasInspector>>>,
     logs: Option<LogCollector>,
     debugger: Option<Debugger>,
+    memory: Option<MemoryTracker>,
 }
 
 impl InspectorStack {
@@ -426,6 +449,7 @@ impl InspectorStack {
         InspectorData {
             logs: self.logs.map(|logs| logs.logs).unwrap_or_default(),
             debug: self.debugger.map(|debugger| debugger.arena),
+            memory_size: self.memory.map(|memory| memory.memory_size).unwrap_or_default(),
         }
     }
 }
@@ -505,7 +529,8 @@ impl<DB: Database> Inspector<DB> for InspectorStack {
             [
                 &mut self.gas.as_deref().map(|gas| gas.borrow_mut()),
                 &mut self.logs,
-                &mut self.debugger
+                &mut self.debugger,
+                &mut self.memory
             ],
             {
                 let status = inspector.step_end(interpreter, data, is_static, status);
@@ -663,6 +688,8 @@ pub struct RawCallResult {
     pub logs: Vec<Log>,
     /// Debug information if any
     pub debug: Option<DebugArena>,
+    /// Highest memory size in bytes of any call frame
+    pub memory_size: usize,
     /// State changes if any
     pub state_changeset: Option<HashMap<Address, Account>>,
     /// Environment
@@ -721,10 +748,21 @@ impl Executor {
         Executor { db: InMemoryDB::default(), debugger, gas_limit }
     }
 
+    /// Set `debugger`, which records every step of following calls.
+    pub fn set_debugger(&mut self, enable: bool) {
+        self.debugger = enable;
+    }
+
     pub fn db_mut(&mut self) -> &mut InMemoryDB {
         &mut self.db
     }
 
+    /// Set `nonce` of account `address`, which decides address of the next
+    /// contract it deploys.
+    pub fn set_nonce(&mut self, address: Address, nonce: u64) {
+        self.db.insert_account_info(address, AccountInfo { nonce, ..Default::default() });
+    }
+
     pub fn deploy(&mut self, from: Address, code: Bytes, value: U256) -> DeployResult {
         let env = self.build_test_env(from, TransactTo::Create(CreateScheme::Create), code, value);
         let result = self.call_raw_with_env(env);
@@ -772,7 +810,7 @@ impl Executor {
             TransactOut::Call(ref data) => data.to_owned(),
             _ => Bytes::default(),
         };
-        let InspectorData { logs, debug } = inspector.collect_inspector_states();
+        let InspectorData { logs, debug, memory_size } = inspector.collect_inspector_states();
 
         RawCallResult {
             exit_reason,
@@ -782,6 +820,7 @@ impl Executor {
             gas_refunded,
             logs: logs.to_vec(),
             debug,
+            memory_size,
             state_changeset: Some(state_changeset.into_iter().collect()),
             env,
             out,
@@ -795,8 +834,11 @@ impl Executor {
     }
 
     fn inspector(&self) -> InspectorStack {
-        let mut stack =
-            InspectorStack { logs: Some(LogCollector::default()), ..Default::default() };
+        let mut stack = InspectorStack {
+            logs: Some(LogCollector::default()),
+            memory: Some(MemoryTracker::default()),
+            ..Default::default()
+        };
         if self.debugger {
             let gas_inspector = Rc::new(RefCell::new(GasInspector::default()));
             stack.gas = Some(gas_inspector.clone());
@@ -0,0 +1,223 @@
+use crate::loader::evm::util::executor::RawCallResult;
+use ethereum_types::U256;
+use serde::{Deserialize, Serialize};
+
+/// Hardfork whose gas schedule is used to estimate gas. Hardforks after
+/// Berlin don't reprice anything a generated verifier does, but they are
+/// listed to be explicit about the target chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Hardfork {
+    /// EIP-1108 repricing of BN254 precompiles and EIP-2028 calldata cost.
+    Istanbul,
+    /// EIP-2929 access lists and EIP-2565 repricing of `ModExp`.
+    Berlin,
+    London,
+    Shanghai,
+    #[default]
+    Cancun,
+}
+
+/// Gas estimate of a call to a generated verifier, split by source.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GasEstimate {
+    /// Base cost of a transaction.
+    pub intrinsic: u64,
+    /// Cost of calldata, assuming all bytes are non-zero.
+    pub calldata: u64,
+    /// Static cost of executed opcodes, and dynamic cost of `KECCAK256`,
+    /// copies and cold account access.
+    pub execution: u64,
+    /// Cost of memory expansion.
+    pub memory: u64,
+    /// Cost of precompiles.
+    pub precompile: u64,
+}
+
+impl GasEstimate {
+    /// Returns total gas.
+    pub fn total(&self) -> u64 {
+        self.intrinsic + self.calldata + self.execution + self.memory + self.precompile
+    }
+}
+
+/// Operations with dynamic cost emitted by [`EvmLoader`].
+///
+/// [`EvmLoader`]: crate::loader::evm::EvmLoader
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OpCounts {
+    pub(crate) ec_add: usize,
+    pub(crate) ec_mul: usize,
+    pub(crate) pairing: usize,
+    // bit length of exponent of each `ModExp`
+    pub(crate) modexp: Vec<usize>,
+    pub(crate) keccak256_words: usize,
+    pub(crate) calldata_len: usize,
+    // number of words copied from VK contract, if any
+    pub(crate) vk_contract_words: Option<usize>,
+}
+
+impl OpCounts {
+    /// Returns length of calldata read, including the address of VK contract
+    /// if any.
+    pub(crate) fn calldata_len(&self) -> usize {
+        self.calldata_len + self.vk_contract_words.map_or(0, |_| 0x20)
+    }
+
+    /// Returns [`GasEstimate`] with given `execution` gas of opcodes and
+    /// `memory_len` of the highest memory touched.
+    pub(crate) fn estimate(
+        &self,
+        hardfork: Hardfork,
+        execution: u64,
+        memory_len: usize,
+    ) -> GasEstimate {
+        let calldata_len = self.calldata_len();
+        let dynamic = 6 * self.keccak256_words as u64
+            + self.vk_contract_words.map_or(0, |words| {
+                let cold_account_access = if hardfork >= Hardfork::Berlin { 2500 } else { 0 };
+                3 * words as u64 + cold_account_access
+            });
+        let memory_words = ((memory_len + 0x1f) / 0x20) as u64;
+
+        let modexp = |exponent_bits: usize| {
+            let iteration_count = exponent_bits.saturating_sub(1) as u64;
+            if hardfork >= Hardfork::Berlin {
+                // Multiplication complexity of 32 bytes operands is 16
+                (16 * iteration_count / 3).max(200)
+            } else {
+                1024 * iteration_count / 20
+            }
+        };
+        let precompile = 150 * self.ec_add as u64
+            + 6000 * self.ec_mul as u64
+            + (45000 + 34000 * 2) * self.pairing as u64
+            + self.modexp.iter().map(|bits| modexp(*bits)).sum::<u64>();
+
+        GasEstimate {
+            intrinsic: 21000,
+            calldata: 16 * calldata_len as u64,
+            execution: execution + dynamic,
+            memory: 3 * memory_words + memory_words * memory_words / 512,
+            precompile,
+        }
+    }
+}
+
+/// Returns static gas of `opcode` in `hardfork`, where account access is
+/// assumed to be warm, and storage is assumed to be warm and dirty.
+pub(crate) fn opcode_gas(opcode: u8, hardfork: Hardfork) -> u64 {
+    let berlin = hardfork >= Hardfork::Berlin;
+    let account_access = if berlin { 100 } else { 700 };
+    match opcode {
+        // STOP, RETURN, REVERT, INVALID
+        0x00 | 0xf3 | 0xfd | 0xfe => 0,
+        // ADD, SUB
+        0x01 | 0x03 => 3,
+        // MUL, DIV, SDIV, MOD, SMOD, SIGNEXTEND
+        0x02 | 0x04..=0x07 | 0x0b => 5,
+        // ADDMOD, MULMOD, JUMP
+        0x08 | 0x09 | 0x56 => 8,
+        // EXP
+        0x0a => 10,
+        // Comparison and bitwise operations
+        0x10..=0x1d => 3,
+        // KECCAK256
+        0x20 => 30,
+        // BALANCE, EXTCODESIZE, EXTCODECOPY, EXTCODEHASH
+        0x31 | 0x3b | 0x3c | 0x3f => account_access,
+        // CALLDATALOAD, CALLDATACOPY, CODECOPY, RETURNDATACOPY
+        0x35 | 0x37 | 0x39 | 0x3e => 3,
+        // BLOCKHASH
+        0x40 => 20,
+        // SELFBALANCE
+        0x47 => 5,
+        // MLOAD, MSTORE, MSTORE8
+        0x51..=0x53 => 3,
+        // SLOAD, SSTORE
+        0x54 | 0x55 => {
+            if berlin {
+                100
+            } else {
+                800
+            }
+        }
+        // JUMPI
+        0x57 => 10,
+        // JUMPDEST
+        0x5b => 1,
+        // PUSH0
+        0x5f => 2,
+        // PUSH, DUP, SWAP
+        0x60..=0x9f => 3,
+        // LOG
+        0xa0..=0xa4 => 375 * (opcode - 0xa0 + 1) as u64,
+        // CREATE, CREATE2
+        0xf0 | 0xf5 => 32000,
+        // CALL, CALLCODE, DELEGATECALL, STATICCALL
+        0xf1 | 0xf2 | 0xf4 | 0xfa => account_access,
+        // SELFDESTRUCT
+        0xff => 5000,
+        // The rest of environmental information, PC, MSIZE and GAS
+        _ => 2,
+    }
+}
+
+/// Gas profiling report of a call to a verifier generated by
+/// [`EvmLoader::new_with_gas_metering`].
+///
+/// [`EvmLoader::new_with_gas_metering`]: crate::loader::evm::EvmLoader::new_with_gas_metering
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasReport {
+    /// Total gas used by the transaction.
+    pub gas_used: u64,
+    /// Length of calldata in bytes.
+    pub calldata_len: usize,
+    /// Highest memory size in bytes of any call frame.
+    pub memory_size: usize,
+    /// Gas consumed by each top-level metered section in order.
+    pub sections: Vec<GasSection>,
+}
+
+/// Gas consumed by a metered section, including its nested sections.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasSection {
+    /// Identifier passed to [`Loader::start_cost_metering`].
+    ///
+    /// [`Loader::start_cost_metering`]: crate::loader::Loader::start_cost_metering
+    pub name: String,
+    /// Gas consumed.
+    pub gas: u64,
+    /// Nested sections in order.
+    pub sections: Vec<GasSection>,
+}
+
+impl GasReport {
+    /// Collects [`GasReport`] from `result` of calling with `calldata_len`
+    /// bytes of calldata, where sections are rebuilt from logs emitted by gas
+    /// metering. Logs are emitted when a section ends, so nested sections
+    /// come before the outer one with higher depth.
+    pub fn new(calldata_len: usize, result: &RawCallResult) -> Self {
+        let mut stack = Vec::<(usize, GasSection)>::new();
+        for log in result.logs.iter().filter(|log| log.topics.len() == 3) {
+            let [name, depth, gas] = [0, 1, 2].map(|idx| log.topics[idx].as_bytes());
+            let name = String::from_utf8_lossy(name).trim_end_matches('\0').to_string();
+            let depth = U256::from_big_endian(depth).low_u64() as usize;
+            let gas = U256::from_big_endian(gas).low_u64();
+
+            let num_nested = stack.iter().rev().take_while(|(d, _)| *d > depth).count();
+            let sections = stack
+                .split_off(stack.len() - num_nested)
+                .into_iter()
+                .map(|(_, section)| section)
+                .collect();
+            stack.push((depth, GasSection { name, gas, sections }));
+        }
+
+        GasReport {
+            gas_used: result.gas_used,
+            calldata_len,
+            memory_size: result.memory_size,
+            sections: stack.into_iter().map(|(_, section)| section).collect(),
+        }
+    }
+}
@@ -12,6 +12,7 @@ use crate::{
 use rand::Rng;
 use std::fmt::Debug;
 
+pub mod ipa;
 pub mod kzg;
 
 pub trait PolynomialCommitmentScheme<C, L>: Clone + Debug
@@ -62,7 +63,7 @@ where
         svk: &Self::SuccinctVerifyingKey,
         queries: &[Query<C::Scalar>],
         transcript: &mut T,
-    ) -> Self::Proof
+    ) -> Result<Self::Proof, Error>
     where
         T: TranscriptRead<C, L>;
 
@@ -73,7 +74,7 @@ where
         point: &L::LoadedScalar,
         queries: &[Query<C::Scalar, L::LoadedScalar>],
         proof: &Self::Proof,
-    ) -> Self::Accumulator;
+    ) -> Result<Self::Accumulator, Error>;
 }
 
 /// Accumulation scheme verifier.
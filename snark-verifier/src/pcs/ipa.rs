@@ -24,10 +24,12 @@ mod decider;
 mod multiopen;
 
 pub use accumulation::{IpaAs, IpaAsProof};
-pub use accumulator::IpaAccumulator;
+pub use accumulator::{IpaAccumulator, IpaLimbsEncoding};
 pub use decider::IpaDecidingKey;
 pub use multiopen::{Bgh19, Bgh19Proof, Bgh19SuccinctVerifyingKey};
 
+/// Inner product argument polynomial commitment scheme. The second generic
+/// `MOS` stands for different kind of multi-open scheme.
 #[derive(Clone, Debug)]
 pub struct Ipa<C, MOS>(PhantomData<(C, MOS)>);
 
@@ -156,11 +158,8 @@ where
 
         let h_prime = h * &proof.xi_0;
         let lhs = {
-            let c_prime = match (
-                s.as_ref(),
-                proof.c_bar_alpha.as_ref(),
-                proof.omega_prime.as_ref(),
-            ) {
+            let c_prime = match (s.as_ref(), proof.c_bar_alpha.as_ref(), proof.omega_prime.as_ref())
+            {
                 (Some(s), Some((c_bar, alpha)), Some(omega_prime)) => {
                     let s = Msm::<C, L>::base(s);
                     commitment.clone() + Msm::base(c_bar) * alpha - s * omega_prime
@@ -219,7 +218,7 @@ impl<C: CurveAffine> IpaProvingKey<C> {
     pub fn svk(&self) -> IpaSuccinctVerifyingKey<C> {
         IpaSuccinctVerifyingKey::new(self.domain.clone(), self.h, self.s)
     }
-    
+
     /// Returns [`IpaDecidingKey`].
     pub fn dk(&self) -> IpaDecidingKey<C> {
         IpaDecidingKey::new(self.g.clone())
@@ -245,9 +244,7 @@ impl<C: CurveAffine> IpaProvingKey<C> {
         let domain = Domain::new(k, root_of_unity(k));
         let mut g = vec![C::default(); 1 << k];
         C::Curve::batch_normalize(
-            &iter::repeat_with(|| C::Curve::random(&mut rng))
-                .take(1 << k)
-                .collect_vec(),
+            &iter::repeat_with(|| C::Curve::random(&mut rng)).take(1 << k).collect_vec(),
             &mut g,
         );
         let h = C::Curve::random(&mut rng).to_affine();
@@ -307,14 +304,7 @@ where
         u: L::LoadedEcPoint,
         c: L::LoadedScalar,
     ) -> Self {
-        Self {
-            c_bar_alpha,
-            omega_prime,
-            xi_0,
-            rounds,
-            u,
-            c,
-        }
+        Self { c_bar_alpha, omega_prime, xi_0, rounds, u, c }
     }
 
     /// Read [`crate::pcs::AccumulationScheme::Proof`] from transcript.
@@ -343,14 +333,7 @@ where
         .collect::<Result<Vec<_>, _>>()?;
         let u = transcript.read_ec_point()?;
         let c = transcript.read_scalar()?;
-        Ok(Self {
-            c_bar_alpha,
-            omega_prime,
-            xi_0,
-            rounds,
-            u,
-            c,
-        })
+        Ok(Self { c_bar_alpha, omega_prime, xi_0, rounds, u, c })
     }
 
     /// Returns $\{\xi_0, \xi_1, ...\}$.
@@ -363,10 +346,7 @@ where
         let mut xi_inv = self.xi().into_iter().map(Fraction::one_over).collect_vec();
         L::batch_invert(xi_inv.iter_mut().filter_map(Fraction::denom_mut));
         xi_inv.iter_mut().for_each(Fraction::evaluate);
-        xi_inv
-            .into_iter()
-            .map(|xi_inv| xi_inv.evaluated().clone())
-            .collect()
+        xi_inv.into_iter().map(|xi_inv| xi_inv.evaluated().clone()).collect()
     }
 }
 
@@ -422,8 +402,12 @@ fn h_coeffs<F: Field>(xi: &[F], scalar: F) -> Vec<F> {
     coeffs
 }
 
-#[cfg(all(test, feature = "system_halo2"))]
+#[cfg(test)]
 mod test {
+    use crate::halo2_curves::pasta::pallas;
+    use crate::halo2_proofs::transcript::{
+        Blake2bRead, Blake2bWrite, TranscriptReadBuffer, TranscriptWriterBuffer,
+    };
     use crate::{
         pcs::{
             ipa::{self, IpaProvingKey},
@@ -431,10 +415,6 @@ mod test {
         },
         util::{arithmetic::Field, msm::Msm, poly::Polynomial},
     };
-    use halo2_curves::pasta::pallas;
-    use halo2_proofs::transcript::{
-        Blake2bRead, Blake2bWrite, TranscriptReadBuffer, TranscriptWriterBuffer,
-    };
     use rand::rngs::OsRng;
 
     #[test]
@@ -194,7 +194,7 @@ where
                     u,
                     iter::empty()
                         .chain([b, a])
-                        .chain(iter::repeat_with(C::Scalar::zero).take(pk.domain.n - 2))
+                        .chain(iter::repeat(C::Scalar::ZERO).take(pk.domain.n - 2))
                         .collect(),
                 )
             }))
@@ -1,4 +1,4 @@
-use crate::{loader::Loader, util::arithmetic::CurveAffine};
+use crate::{loader::Loader, util::arithmetic::CurveAffine, Error};
 
 /// Inner product argument accumulator.
 #[derive(Clone, Debug)]
@@ -23,3 +23,114 @@ where
         Self { xi, u }
     }
 }
+
+/// `AccumulatorEncoding` that encodes [`IpaAccumulator`] into the challenges
+/// $\xi$ followed by limbs of $U$.
+///
+/// Challenges are already in scalar field so they are kept as is, while $U$
+/// contains base field elements, so its coordinates are split into limbs the
+/// same way as [`crate::pcs::kzg::LimbsEncoding`].
+#[derive(Clone, Debug)]
+pub struct IpaLimbsEncoding<const LIMBS: usize, const BITS: usize>;
+
+fn check_repr_len<const LIMBS: usize>(len: usize) -> Result<(), Error> {
+    if len <= 2 * LIMBS {
+        return Err(Error::AssertionFailure(format!(
+            "Invalid length of accumulator representation, expected more than {}, but got {len}",
+            2 * LIMBS
+        )));
+    }
+    Ok(())
+}
+
+mod native {
+    use crate::{
+        loader::native::NativeLoader,
+        pcs::{
+            ipa::{IpaAccumulator, IpaLimbsEncoding},
+            AccumulatorEncoding, PolynomialCommitmentScheme,
+        },
+        util::{
+            arithmetic::{fe_from_limbs, CurveAffine},
+            Itertools,
+        },
+        Error,
+    };
+
+    use super::check_repr_len;
+
+    impl<C, PCS, const LIMBS: usize, const BITS: usize> AccumulatorEncoding<C, NativeLoader, PCS>
+        for IpaLimbsEncoding<LIMBS, BITS>
+    where
+        C: CurveAffine,
+        PCS: PolynomialCommitmentScheme<
+            C,
+            NativeLoader,
+            Accumulator = IpaAccumulator<C, NativeLoader>,
+        >,
+    {
+        fn from_repr(repr: &[&C::Scalar]) -> Result<PCS::Accumulator, Error> {
+            check_repr_len::<LIMBS>(repr.len())?;
+
+            let (xi, limbs) = repr.split_at(repr.len() - 2 * LIMBS);
+            let [x, y] = [&limbs[..LIMBS], &limbs[LIMBS..]].map(|limbs| {
+                fe_from_limbs::<_, _, LIMBS, BITS>(
+                    limbs.iter().map(|limb| **limb).collect_vec().try_into().unwrap(),
+                )
+            });
+            let u = Option::<C>::from(C::from_xy(x, y)).ok_or_else(|| {
+                Error::AssertionFailure("Accumulator is not on curve".to_string())
+            })?;
+
+            Ok(IpaAccumulator::new(xi.iter().map(|xi| **xi).collect(), u))
+        }
+    }
+}
+
+#[cfg(feature = "loader_halo2")]
+mod halo2 {
+    use crate::{
+        loader::halo2::{Halo2Loader, Scalar},
+        pcs::{
+            ipa::{IpaAccumulator, IpaLimbsEncoding},
+            kzg::LimbsEncodingInstructions,
+            AccumulatorEncoding, PolynomialCommitmentScheme,
+        },
+        util::{arithmetic::CurveAffine, Itertools},
+        Error,
+    };
+    use std::rc::Rc;
+
+    use super::check_repr_len;
+
+    impl<'a, C, PCS, EccChip, const LIMBS: usize, const BITS: usize>
+        AccumulatorEncoding<C, Rc<Halo2Loader<'a, C, EccChip>>, PCS>
+        for IpaLimbsEncoding<LIMBS, BITS>
+    where
+        C: CurveAffine,
+        PCS: PolynomialCommitmentScheme<
+            C,
+            Rc<Halo2Loader<'a, C, EccChip>>,
+            Accumulator = IpaAccumulator<C, Rc<Halo2Loader<'a, C, EccChip>>>,
+        >,
+        EccChip: LimbsEncodingInstructions<'a, C, LIMBS, BITS>,
+    {
+        fn from_repr(repr: &[&Scalar<'a, C, EccChip>]) -> Result<PCS::Accumulator, Error> {
+            check_repr_len::<LIMBS>(repr.len())?;
+
+            let loader = repr[0].loader();
+
+            let (xi, limbs) = repr.split_at(repr.len() - 2 * LIMBS);
+            let u = loader
+                .ecc_chip()
+                .assign_ec_point_from_limbs(
+                    &mut loader.ctx_mut(),
+                    &limbs.iter().map(|limb| limb.assigned()).collect_vec(),
+                )
+                .map_err(|err| Error::AssertionFailure(format!("{err:?}")))?;
+            let u = loader.ec_point_from_assigned(u);
+
+            Ok(IpaAccumulator::new(xi.iter().map(|xi| (*xi).clone()).collect(), u))
+        }
+    }
+}
@@ -1,4 +1,3 @@
-
 /// Inner product argument deciding key.
 #[derive(Clone, Debug)]
 pub struct IpaDecidingKey<C> {
@@ -52,9 +51,7 @@ mod native {
             accumulators: Vec<IpaAccumulator<C, NativeLoader>>,
         ) -> bool {
             assert!(!accumulators.is_empty());
-            !accumulators
-                .into_iter()
-                .any(|accumulator| !Self::decide(dk, accumulator))
+            !accumulators.into_iter().any(|accumulator| !Self::decide(dk, accumulator))
         }
     }
 }
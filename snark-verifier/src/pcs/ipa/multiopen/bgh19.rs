@@ -1,11 +1,12 @@
 use crate::{
+    cost::{Cost, CostEstimation},
     loader::{LoadedScalar, Loader, ScalarLoader},
     pcs::{
         ipa::{Ipa, IpaProof, IpaSuccinctVerifyingKey, Round},
         MultiOpenScheme, Query,
     },
     util::{
-        arithmetic::{ilog2, CurveAffine, Domain, FieldExt, Fraction},
+        arithmetic::{ilog2, CurveAffine, Domain, Fraction, PrimeField},
         msm::Msm,
         transcript::TranscriptRead,
         Itertools,
@@ -18,12 +19,16 @@ use std::{
     marker::PhantomData,
 };
 
+/// Verifier of multi-open inner product argument. It is for the implementation
+/// in [`halo2_proofs`], which is previously
+/// <https://github.com/zcash/halo2/blob/main/halo2_proofs/src/poly/multiopen.rs>.
 #[derive(Clone, Debug)]
 pub struct Bgh19;
 
 impl<C, L> MultiOpenScheme<C, L> for Ipa<C, Bgh19>
 where
     C: CurveAffine,
+    C::Scalar: Ord,
     L: Loader<C>,
 {
     type SuccinctVerifyingKey = Bgh19SuccinctVerifyingKey<C>;
@@ -55,9 +60,8 @@ where
         let p = {
             let coeffs = query_set_coeffs(&sets, x, &proof.x_3);
 
-            let powers_of_x_1 = proof
-                .x_1
-                .powers(sets.iter().map(|set| set.polys.len()).max().unwrap());
+            let powers_of_x_1 =
+                proof.x_1.powers(sets.iter().map(|set| set.polys.len()).max().unwrap());
             let f_eval = {
                 let powers_of_x_2 = proof.x_2.powers(sets.len());
                 let f_evals = sets
@@ -91,6 +95,7 @@ where
     }
 }
 
+/// SuccinctVerifyingKey for [`Bgh19`].
 #[derive(Clone, Debug)]
 pub struct Bgh19SuccinctVerifyingKey<C: CurveAffine> {
     g: C,
@@ -98,14 +103,18 @@ pub struct Bgh19SuccinctVerifyingKey<C: CurveAffine> {
 }
 
 impl<C: CurveAffine> Bgh19SuccinctVerifyingKey<C> {
+    /// Initialize a [`Bgh19SuccinctVerifyingKey`] with the first generator `g`,
+    /// the blinding base `w` and the evaluation base `u` of [`halo2_proofs`]'s
+    /// IPA parameters.
     pub fn new(domain: Domain<C::Scalar>, g: C, w: C, u: C) -> Self {
-        Self {
-            g,
-            ipa: IpaSuccinctVerifyingKey::new(domain, u, Some(w)),
-        }
+        Self { g, ipa: IpaSuccinctVerifyingKey::new(domain, u, Some(w)) }
     }
 }
 
+/// Structured proof of [`Bgh19`].
+///
+/// Note that [`halo2_proofs`] doesn't write the final folded generator `G` into
+/// the transcript, so it's expected to be appended at the end of the proof.
 #[derive(Clone, Debug)]
 pub struct Bgh19Proof<C, L>
 where
@@ -170,16 +179,12 @@ where
 
 fn query_sets<F, T>(queries: &[Query<F, T>]) -> Vec<QuerySet<F, T>>
 where
-    F: FieldExt,
+    F: PrimeField + Ord,
     T: Clone,
 {
-    let poly_shifts = queries.iter().fold(
-        Vec::<(usize, Vec<F>, Vec<&T>)>::new(),
-        |mut poly_shifts, query| {
-            if let Some(pos) = poly_shifts
-                .iter()
-                .position(|(poly, _, _)| *poly == query.poly)
-            {
+    let poly_shifts =
+        queries.iter().fold(Vec::<(usize, Vec<F>, Vec<&T>)>::new(), |mut poly_shifts, query| {
+            if let Some(pos) = poly_shifts.iter().position(|(poly, _, _)| *poly == query.poly) {
                 let (_, shifts, evals) = &mut poly_shifts[pos];
                 if !shifts.contains(&query.shift) {
                     shifts.push(query.shift);
@@ -189,52 +194,40 @@ where
                 poly_shifts.push((query.poly, vec![query.shift], vec![&query.eval]));
             }
             poly_shifts
-        },
-    );
-
-    poly_shifts.into_iter().fold(
-        Vec::<QuerySet<F, T>>::new(),
-        |mut sets, (poly, shifts, evals)| {
-            if let Some(pos) = sets.iter().position(|set| {
-                BTreeSet::from_iter(set.shifts.iter()) == BTreeSet::from_iter(shifts.iter())
-            }) {
-                let set = &mut sets[pos];
-                if !set.polys.contains(&poly) {
-                    set.polys.push(poly);
-                    set.evals.push(
-                        set.shifts
-                            .iter()
-                            .map(|lhs| {
-                                let idx = shifts.iter().position(|rhs| lhs == rhs).unwrap();
-                                evals[idx]
-                            })
-                            .collect(),
-                    );
-                }
-            } else {
-                let set = QuerySet {
-                    shifts,
-                    polys: vec![poly],
-                    evals: vec![evals],
-                };
-                sets.push(set);
+        });
+
+    poly_shifts.into_iter().fold(Vec::<QuerySet<F, T>>::new(), |mut sets, (poly, shifts, evals)| {
+        if let Some(pos) = sets.iter().position(|set| {
+            BTreeSet::from_iter(set.shifts.iter()) == BTreeSet::from_iter(shifts.iter())
+        }) {
+            let set = &mut sets[pos];
+            if !set.polys.contains(&poly) {
+                set.polys.push(poly);
+                set.evals.push(
+                    set.shifts
+                        .iter()
+                        .map(|lhs| {
+                            let idx = shifts.iter().position(|rhs| lhs == rhs).unwrap();
+                            evals[idx]
+                        })
+                        .collect(),
+                );
             }
-            sets
-        },
-    )
+        } else {
+            let set = QuerySet { shifts, polys: vec![poly], evals: vec![evals] };
+            sets.push(set);
+        }
+        sets
+    })
 }
 
 fn query_set_coeffs<F, T>(sets: &[QuerySet<F, T>], x: &T, x_3: &T) -> Vec<QuerySetCoeff<F, T>>
 where
-    F: FieldExt,
+    F: PrimeField + Ord,
     T: LoadedScalar<F>,
 {
     let loader = x.loader();
-    let superset = sets
-        .iter()
-        .flat_map(|set| set.shifts.clone())
-        .sorted()
-        .dedup();
+    let superset = sets.iter().flat_map(|set| set.shifts.clone()).sorted().dedup();
 
     let size = 2.max(
         ilog2((sets.iter().map(|set| set.shifts.len()).max().unwrap() - 1).next_power_of_two()) + 1,
@@ -265,7 +258,7 @@ struct QuerySet<'a, F, T> {
 
 impl<'a, F, T> QuerySet<'a, F, T>
 where
-    F: FieldExt,
+    F: PrimeField,
     T: LoadedScalar<F>,
 {
     fn msm<C: CurveAffine, L: Loader<C, LoadedScalar = T>>(
@@ -317,7 +310,7 @@ struct QuerySetCoeff<F, T> {
 
 impl<F, T> QuerySetCoeff<F, T>
 where
-    F: FieldExt,
+    F: PrimeField + Ord,
     T: LoadedScalar<F>,
 {
     fn new(shifts: &[F], powers_of_x: &[T], x_3: &T, x_3_minus_x_shift_i: &BTreeMap<F, T>) -> Self {
@@ -343,11 +336,8 @@ where
                 .iter()
                 .enumerate()
                 .skip(1)
-                .filter_map(|(i, power_of_x)| {
-                    (k_minus_one & (1 << i) == 1).then(|| power_of_x.clone())
-                })
-                .reduce(|acc, value| acc * value)
-                .unwrap_or_else(|| loader.load_one())
+                .filter_map(|(i, power_of_x)| (k_minus_one & (1 << i) == 1).then_some(power_of_x))
+                .fold(loader.load_one(), |acc, value| acc * value)
         };
 
         let barycentric_weights = shifts
@@ -362,14 +352,9 @@ where
             .map(Fraction::one_over)
             .collect_vec();
 
-        let f_eval_coeff = Fraction::one_over(
-            loader.product(
-                &shifts
-                    .iter()
-                    .map(|shift| x_3_minus_x_shift_i.get(shift).unwrap())
-                    .collect_vec(),
-            ),
-        );
+        let f_eval_coeff = Fraction::one_over(loader.product(
+            &shifts.iter().map(|shift| x_3_minus_x_shift_i.get(shift).unwrap()).collect_vec(),
+        ));
 
         Self {
             eval_coeffs: barycentric_weights,
@@ -396,13 +381,8 @@ where
                 .for_each(Fraction::evaluate);
 
             let loader = self.f_eval_coeff.evaluated().loader();
-            let barycentric_weights_sum = loader.sum(
-                &self
-                    .eval_coeffs
-                    .iter()
-                    .map(Fraction::evaluated)
-                    .collect_vec(),
-            );
+            let barycentric_weights_sum =
+                loader.sum(&self.eval_coeffs.iter().map(Fraction::evaluated).collect_vec());
             self.r_eval_coeff = Some(Fraction::one_over(barycentric_weights_sum));
 
             return vec![self.r_eval_coeff.as_mut().unwrap().denom_mut().unwrap()];
@@ -415,3 +395,18 @@ where
         self.r_eval_coeff.as_mut().unwrap().evaluate();
     }
 }
+
+impl<C> CostEstimation<C> for Ipa<C, Bgh19>
+where
+    C: CurveAffine,
+    C::Scalar: Ord,
+{
+    type Input = Vec<Query<C::Scalar>>;
+
+    /// Estimate cost of [`Bgh19`] without the `2k` commitments of IPA rounds,
+    /// since domain size is not known from queries.
+    fn estimate_cost(queries: &Vec<Query<C::Scalar>>) -> Cost {
+        let num_q_eval = query_sets(queries).len();
+        Cost::new(0, 3, num_q_eval + 2, 3)
+    }
+}
@@ -13,12 +13,12 @@ mod decider;
 mod multiopen;
 
 pub use accumulation::{KzgAs, KzgAsProvingKey, KzgAsVerifyingKey};
-pub use accumulator::{KzgAccumulator, LimbsEncoding};
+pub use accumulator::{CompressedLimbsEncoding, KzgAccumulator, LimbsEncoding};
 pub use decider::KzgDecidingKey;
 pub use multiopen::{Bdfg21, Bdfg21Proof, Gwc19, Gwc19Proof};
 
 #[cfg(feature = "loader_halo2")]
-pub use accumulator::LimbsEncodingInstructions;
+pub use accumulator::{CompressedLimbsEncodingInstructions, LimbsEncodingInstructions};
 
 #[derive(Clone, Debug)]
 pub struct Kzg<M, MOS>(PhantomData<(M, MOS)>);
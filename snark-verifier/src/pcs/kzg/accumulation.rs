@@ -130,9 +130,11 @@ where
             transcript.common_ec_point(&accumulator.rhs)?;
         }
 
-        let blind = vk
-            .zk()
-            .then(|| (transcript.read_ec_point().unwrap(), transcript.read_ec_point().unwrap()));
+        let blind = if vk.zk() {
+            Some((transcript.read_ec_point()?, transcript.read_ec_point()?))
+        } else {
+            None
+        };
 
         let r = transcript.squeeze_challenge();
 
@@ -1,4 +1,8 @@
-use crate::{loader::Loader, util::arithmetic::CurveAffine};
+use crate::{
+    loader::Loader,
+    util::arithmetic::{fe_to_big, CurveAffine, Field},
+    Error,
+};
 use std::fmt::Debug;
 
 /// KZG accumulator, containing lhs G1 and rhs G1 of pairing.
@@ -33,19 +37,55 @@ where
 #[derive(Clone, Debug)]
 pub struct LimbsEncoding<const LIMBS: usize, const BITS: usize>;
 
+/// `AccumulatorEncoding` that encodes `Accumulator` into limbs of x
+/// coordinate and parity of y coordinate of each point, which takes
+/// `2 * (LIMBS + 1)` scalars instead of `4 * LIMBS` of [`LimbsEncoding`].
+///
+/// The y coordinate is recovered as the square root of `x^3 + a * x + b` with
+/// the same parity, so the encoded y coordinate has to be canonical.
+///
+/// It's opt-in: aggregation circuits of this crate and `snark-verifier-sdk`,
+/// including its EVM verifier generation, only support [`LimbsEncoding`]. So
+/// the fewer instances and calldata only apply to a circuit built on this
+/// crate directly, which should expose limbs from
+/// `assign_ec_point_to_compressed_limbs` of
+/// `CompressedLimbsEncodingInstructions`, and be verified with this encoding
+/// as the `AccumulatorEncoding` of `Plonk` by its next layer and its EVM
+/// verifier.
+#[derive(Clone, Debug)]
+pub struct CompressedLimbsEncoding<const LIMBS: usize, const BITS: usize>;
+
+fn check_num_limbs(num_limbs: usize, expected: usize) -> Result<(), Error> {
+    if num_limbs != expected {
+        return Err(Error::AssertionFailure(format!(
+            "Invalid number of accumulator limbs, expected {expected}, but got {num_limbs}"
+        )));
+    }
+    Ok(())
+}
+
+fn ec_point_from_x_and_y_parity<C: CurveAffine>(x: C::Base, y_parity: bool) -> Option<C> {
+    let y = Option::<C::Base>::from((x.square() * x + C::a() * x + C::b()).sqrt())?;
+    let y = if fe_to_big(y).bit(0) == y_parity { y } else { -y };
+    Option::from(C::from_xy(x, y))
+}
+
 mod native {
     use crate::{
         loader::native::NativeLoader,
         pcs::{
-            kzg::{KzgAccumulator, LimbsEncoding},
+            kzg::{CompressedLimbsEncoding, KzgAccumulator, LimbsEncoding},
             AccumulatorEncoding, PolynomialCommitmentScheme,
         },
         util::{
-            arithmetic::{fe_from_limbs, CurveAffine},
+            arithmetic::{fe_from_limbs, fe_to_big, fe_to_limbs, CurveAffine, Field},
             Itertools,
         },
         Error,
     };
+    use std::iter;
+
+    use super::{check_num_limbs, ec_point_from_x_and_y_parity};
 
     impl<C, PCS, const LIMBS: usize, const BITS: usize> AccumulatorEncoding<C, NativeLoader, PCS>
         for LimbsEncoding<LIMBS, BITS>
@@ -58,7 +98,7 @@ mod native {
         >,
     {
         fn from_repr(limbs: &[&C::Scalar]) -> Result<PCS::Accumulator, Error> {
-            assert_eq!(limbs.len(), 4 * LIMBS);
+            check_num_limbs(limbs.len(), 4 * LIMBS)?;
 
             let [lhs_x, lhs_y, rhs_x, rhs_y]: [_; 4] = limbs
                 .chunks(LIMBS)
@@ -71,10 +111,67 @@ mod native {
                 .collect_vec()
                 .try_into()
                 .unwrap();
-            let accumulator = KzgAccumulator::new(
-                C::from_xy(lhs_x, lhs_y).unwrap(),
-                C::from_xy(rhs_x, rhs_y).unwrap(),
-            );
+            let [lhs, rhs] = [(lhs_x, lhs_y), (rhs_x, rhs_y)].map(|(x, y)| {
+                Option::<C>::from(C::from_xy(x, y)).ok_or_else(|| {
+                    Error::AssertionFailure("Accumulator is not on curve".to_string())
+                })
+            });
+            let accumulator = KzgAccumulator::new(lhs?, rhs?);
+
+            Ok(accumulator)
+        }
+    }
+
+    impl<const LIMBS: usize, const BITS: usize> CompressedLimbsEncoding<LIMBS, BITS> {
+        /// Encode [`KzgAccumulator`] into limbs of x coordinate and parity of
+        /// y coordinate of each point, which panics if any point is identity.
+        pub fn to_repr<C: CurveAffine>(
+            accumulator: &KzgAccumulator<C, NativeLoader>,
+        ) -> Vec<C::Scalar> {
+            [accumulator.lhs, accumulator.rhs]
+                .iter()
+                .flat_map(|ec_point| {
+                    let coordinates = ec_point.coordinates().unwrap();
+                    let y_parity = fe_to_big(*coordinates.y()).bit(0);
+                    iter::empty()
+                        .chain(fe_to_limbs::<_, _, LIMBS, BITS>(*coordinates.x()))
+                        .chain([C::Scalar::from(y_parity as u64)])
+                })
+                .collect()
+        }
+    }
+
+    impl<C, PCS, const LIMBS: usize, const BITS: usize> AccumulatorEncoding<C, NativeLoader, PCS>
+        for CompressedLimbsEncoding<LIMBS, BITS>
+    where
+        C: CurveAffine,
+        PCS: PolynomialCommitmentScheme<
+            C,
+            NativeLoader,
+            Accumulator = KzgAccumulator<C, NativeLoader>,
+        >,
+    {
+        fn from_repr(limbs: &[&C::Scalar]) -> Result<PCS::Accumulator, Error> {
+            check_num_limbs(limbs.len(), 2 * (LIMBS + 1))?;
+
+            let [lhs, rhs] = [&limbs[..LIMBS + 1], &limbs[LIMBS + 1..]].map(|limbs| {
+                let x = fe_from_limbs::<_, _, LIMBS, BITS>(
+                    limbs[..LIMBS].iter().map(|limb| **limb).collect_vec().try_into().unwrap(),
+                );
+                let y_parity = match *limbs[LIMBS] {
+                    y_parity if y_parity == C::Scalar::ZERO => false,
+                    y_parity if y_parity == C::Scalar::ONE => true,
+                    _ => {
+                        return Err(Error::AssertionFailure(
+                            "Parity of y coordinate is not a bit".to_string(),
+                        ))
+                    }
+                };
+                ec_point_from_x_and_y_parity(x, y_parity).ok_or_else(|| {
+                    Error::AssertionFailure("Accumulator is not on curve".to_string())
+                })
+            });
+            let accumulator = KzgAccumulator::new(lhs?, rhs?);
 
             Ok(accumulator)
         }
@@ -86,7 +183,7 @@ mod evm {
     use crate::{
         loader::evm::{EvmLoader, Scalar},
         pcs::{
-            kzg::{KzgAccumulator, LimbsEncoding},
+            kzg::{CompressedLimbsEncoding, KzgAccumulator, LimbsEncoding},
             AccumulatorEncoding, PolynomialCommitmentScheme,
         },
         util::{
@@ -97,6 +194,8 @@ mod evm {
     };
     use std::rc::Rc;
 
+    use super::check_num_limbs;
+
     impl<C, PCS, const LIMBS: usize, const BITS: usize> AccumulatorEncoding<C, Rc<EvmLoader>, PCS>
         for LimbsEncoding<LIMBS, BITS>
     where
@@ -109,7 +208,7 @@ mod evm {
         >,
     {
         fn from_repr(limbs: &[&Scalar]) -> Result<PCS::Accumulator, Error> {
-            assert_eq!(limbs.len(), 4 * LIMBS);
+            check_num_limbs(limbs.len(), 4 * LIMBS)?;
 
             let loader = limbs[0].loader();
 
@@ -128,10 +227,37 @@ mod evm {
             Ok(accumulator)
         }
     }
+
+    impl<C, PCS, const LIMBS: usize, const BITS: usize> AccumulatorEncoding<C, Rc<EvmLoader>, PCS>
+        for CompressedLimbsEncoding<LIMBS, BITS>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+        PCS: PolynomialCommitmentScheme<
+            C,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<C, Rc<EvmLoader>>,
+        >,
+    {
+        fn from_repr(limbs: &[&Scalar]) -> Result<PCS::Accumulator, Error> {
+            check_num_limbs(limbs.len(), 2 * (LIMBS + 1))?;
+
+            let loader = limbs[0].loader();
+
+            let [lhs, rhs] = [&limbs[..LIMBS + 1], &limbs[LIMBS + 1..]].map(|limbs| {
+                loader.ec_point_from_compressed_limbs::<LIMBS, BITS>(
+                    limbs[..LIMBS].to_vec().try_into().unwrap(),
+                    limbs[LIMBS],
+                )
+            });
+
+            Ok(KzgAccumulator::new(lhs, rhs))
+        }
+    }
 }
 
 #[cfg(feature = "loader_halo2")]
-pub use halo2::LimbsEncodingInstructions;
+pub use halo2::{CompressedLimbsEncodingInstructions, LimbsEncodingInstructions};
 
 #[cfg(feature = "loader_halo2")]
 mod halo2 {
@@ -139,17 +265,19 @@ mod halo2 {
         halo2_proofs::{circuit::Value, plonk},
         loader::halo2::{EccInstructions, Halo2Loader, Scalar, Valuetools},
         pcs::{
-            kzg::{KzgAccumulator, LimbsEncoding},
+            kzg::{CompressedLimbsEncoding, KzgAccumulator, LimbsEncoding},
             AccumulatorEncoding, PolynomialCommitmentScheme,
         },
         util::{
-            arithmetic::{fe_from_limbs, CurveAffine},
+            arithmetic::{fe_from_limbs, CurveAffine, Field},
             Itertools,
         },
         Error,
     };
     use std::{iter, ops::Deref, rc::Rc};
 
+    use super::{check_num_limbs, ec_point_from_x_and_y_parity};
+
     fn ec_point_from_limbs<C: CurveAffine, const LIMBS: usize, const BITS: usize>(
         limbs: &[Value<&C::Scalar>],
     ) -> Value<C> {
@@ -169,6 +297,24 @@ mod halo2 {
         x.zip(y).map(|(x, y)| C::from_xy(x, y).unwrap())
     }
 
+    fn ec_point_from_compressed_limbs<C: CurveAffine, const LIMBS: usize, const BITS: usize>(
+        limbs: &[Value<&C::Scalar>],
+    ) -> Value<C> {
+        assert_eq!(limbs.len(), LIMBS + 1);
+
+        let x = limbs[..LIMBS]
+            .iter()
+            .cloned()
+            .fold_zipped(Vec::new(), |mut acc, limb| {
+                acc.push(*limb);
+                acc
+            })
+            .map(|limbs| fe_from_limbs::<_, _, LIMBS, BITS>(limbs.try_into().unwrap()));
+        let y_parity = limbs[LIMBS].map(|y_parity| *y_parity == C::Scalar::ONE);
+
+        x.zip(y_parity).map(|(x, y_parity)| ec_point_from_x_and_y_parity(x, y_parity).unwrap())
+    }
+
     /// Instructions to encode/decode a elliptic curve point into/from limbs.
     pub trait LimbsEncodingInstructions<'a, C: CurveAffine, const LIMBS: usize, const BITS: usize>:
         EccInstructions<'a, C>
@@ -188,6 +334,32 @@ mod halo2 {
         ) -> Result<Vec<Self::AssignedCell>, plonk::Error>;
     }
 
+    /// Instructions to encode/decode a elliptic curve point into/from limbs of
+    /// x coordinate and parity of y coordinate.
+    pub trait CompressedLimbsEncodingInstructions<
+        'a,
+        C: CurveAffine,
+        const LIMBS: usize,
+        const BITS: usize,
+    >: EccInstructions<'a, C>
+    {
+        /// Decode and assign an elliptic curve point from limbs of x
+        /// coordinate and parity of y coordinate.
+        fn assign_ec_point_from_compressed_limbs(
+            &self,
+            ctx: &mut Self::Context,
+            limbs: &[impl Deref<Target = Self::AssignedScalar>],
+        ) -> Result<Self::AssignedEcPoint, plonk::Error>;
+
+        /// Encode an elliptic curve point into limbs of x coordinate and
+        /// parity of y coordinate.
+        fn assign_ec_point_to_compressed_limbs(
+            &self,
+            ctx: &mut Self::Context,
+            ec_point: impl Deref<Target = Self::AssignedEcPoint>,
+        ) -> Result<Vec<Self::AssignedCell>, plonk::Error>;
+    }
+
     impl<'a, C, PCS, EccChip, const LIMBS: usize, const BITS: usize>
         AccumulatorEncoding<C, Rc<Halo2Loader<'a, C, EccChip>>, PCS> for LimbsEncoding<LIMBS, BITS>
     where
@@ -200,7 +372,7 @@ mod halo2 {
         EccChip: LimbsEncodingInstructions<'a, C, LIMBS, BITS>,
     {
         fn from_repr(limbs: &[&Scalar<'a, C, EccChip>]) -> Result<PCS::Accumulator, Error> {
-            assert_eq!(limbs.len(), 4 * LIMBS);
+            check_num_limbs(limbs.len(), 4 * LIMBS)?;
 
             let loader = limbs[0].loader();
 
@@ -211,18 +383,72 @@ mod halo2 {
                         &mut loader.ctx_mut(),
                         &limbs.iter().map(|limb| limb.assigned()).collect_vec(),
                     )
-                    .unwrap();
-                loader.ec_point_from_assigned(assigned)
+                    .map_err(|err| Error::AssertionFailure(format!("{err:?}")))?;
+                Ok::<_, Error>(loader.ec_point_from_assigned(assigned))
             });
 
-            Ok(KzgAccumulator::new(lhs, rhs))
+            Ok(KzgAccumulator::new(lhs?, rhs?))
+        }
+    }
+
+    impl<'a, C, PCS, EccChip, const LIMBS: usize, const BITS: usize>
+        AccumulatorEncoding<C, Rc<Halo2Loader<'a, C, EccChip>>, PCS>
+        for CompressedLimbsEncoding<LIMBS, BITS>
+    where
+        C: CurveAffine,
+        PCS: PolynomialCommitmentScheme<
+            C,
+            Rc<Halo2Loader<'a, C, EccChip>>,
+            Accumulator = KzgAccumulator<C, Rc<Halo2Loader<'a, C, EccChip>>>,
+        >,
+        EccChip: CompressedLimbsEncodingInstructions<'a, C, LIMBS, BITS>,
+    {
+        fn from_repr(limbs: &[&Scalar<'a, C, EccChip>]) -> Result<PCS::Accumulator, Error> {
+            check_num_limbs(limbs.len(), 2 * (LIMBS + 1))?;
+
+            let loader = limbs[0].loader();
+
+            let [lhs, rhs] = [&limbs[..LIMBS + 1], &limbs[LIMBS + 1..]].map(|limbs| {
+                let assigned = loader
+                    .ecc_chip()
+                    .assign_ec_point_from_compressed_limbs(
+                        &mut loader.ctx_mut(),
+                        &limbs.iter().map(|limb| limb.assigned()).collect_vec(),
+                    )
+                    .map_err(|err| Error::AssertionFailure(format!("{err:?}")))?;
+                Ok::<_, Error>(loader.ec_point_from_assigned(assigned))
+            });
+
+            Ok(KzgAccumulator::new(lhs?, rhs?))
         }
     }
 
     mod halo2_lib {
         use super::*;
-        use halo2_base::utils::{BigPrimeField as PrimeField, CurveAffineExt};
-        use halo2_ecc::ecc::BaseFieldEccChip;
+        use halo2_base::{
+            gates::{GateInstructions, RangeInstructions},
+            utils::{BigPrimeField as PrimeField, CurveAffineExt},
+            AssignedValue,
+        };
+        use halo2_ecc::{bigint::CRTInteger, ecc::BaseFieldEccChip, fields::FieldChip};
+
+        // Returns parity of `y` after enforcing it's canonical, otherwise
+        // `y + p` would have the opposite parity.
+        fn assign_y_parity<C, const BITS: usize>(
+            chip: &BaseFieldEccChip<C>,
+            ctx: &mut halo2_base::Context<'_, C::Scalar>,
+            y: &CRTInteger<C::Scalar>,
+        ) -> AssignedValue<C::Scalar>
+        where
+            C: CurveAffineExt,
+            C::ScalarExt: PrimeField,
+            C::Base: PrimeField,
+        {
+            chip.field_chip().enforce_less_than(ctx, y);
+            let bits =
+                chip.field_chip().range().gate().num_to_bits(ctx, &y.truncation.limbs[0], BITS);
+            bits[0].clone()
+        }
 
         impl<'a, C, const LIMBS: usize, const BITS: usize>
             LimbsEncodingInstructions<'a, C, LIMBS, BITS> for BaseFieldEccChip<C>
@@ -267,5 +493,50 @@ mod halo2 {
                     .collect())
             }
         }
+
+        impl<'a, C, const LIMBS: usize, const BITS: usize>
+            CompressedLimbsEncodingInstructions<'a, C, LIMBS, BITS> for BaseFieldEccChip<C>
+        where
+            C: CurveAffineExt,
+            C::ScalarExt: PrimeField,
+            C::Base: PrimeField,
+        {
+            fn assign_ec_point_from_compressed_limbs(
+                &self,
+                ctx: &mut Self::Context,
+                limbs: &[impl Deref<Target = Self::AssignedScalar>],
+            ) -> Result<Self::AssignedEcPoint, plonk::Error> {
+                assert_eq!(limbs.len(), LIMBS + 1);
+
+                let ec_point = self.assign_point::<C>(
+                    ctx,
+                    ec_point_from_compressed_limbs::<_, LIMBS, BITS>(
+                        &limbs.iter().map(|limb| limb.value()).collect_vec(),
+                    ),
+                );
+                let y_parity = assign_y_parity::<C, BITS>(self, ctx, ec_point.y());
+
+                for (src, dst) in limbs
+                    .iter()
+                    .zip_eq(iter::empty().chain(ec_point.x().limbs()).chain([&y_parity]))
+                {
+                    ctx.region.constrain_equal(src.cell(), dst.cell())?;
+                }
+
+                Ok(ec_point)
+            }
+
+            fn assign_ec_point_to_compressed_limbs(
+                &self,
+                ctx: &mut Self::Context,
+                ec_point: impl Deref<Target = Self::AssignedEcPoint>,
+            ) -> Result<Vec<Self::AssignedCell>, plonk::Error> {
+                let y_parity = assign_y_parity::<C, BITS>(self, ctx, ec_point.y());
+                Ok(iter::empty()
+                    .chain(ec_point.x().limbs().iter().cloned())
+                    .chain([y_parity])
+                    .collect())
+            }
+        }
     }
 }
@@ -105,7 +105,9 @@ mod evm {
                     U256::from_little_endian(&y.as_ref()[..32]),
                 )
             });
+            loader.start_gas_metering("pairing");
             loader.pairing(&lhs, g2, &rhs, minus_s_g2);
+            loader.end_gas_metering();
         }
 
         fn decide_all(
@@ -13,6 +13,7 @@ use crate::{
         transcript::TranscriptRead,
         Itertools,
     },
+    Error,
 };
 use std::{
     collections::{BTreeMap, BTreeSet},
@@ -38,7 +39,7 @@ where
         _: &KzgSuccinctVerifyingKey<M::G1Affine>,
         _: &[Query<M::Scalar>],
         transcript: &mut T,
-    ) -> Bdfg21Proof<M::G1Affine, L>
+    ) -> Result<Bdfg21Proof<M::G1Affine, L>, Error>
     where
         T: TranscriptRead<M::G1Affine, L>,
     {
@@ -51,7 +52,7 @@ where
         z: &L::LoadedScalar,
         queries: &[Query<M::Scalar, L::LoadedScalar>],
         proof: &Bdfg21Proof<M::G1Affine, L>,
-    ) -> Self::Accumulator {
+    ) -> Result<Self::Accumulator, Error> {
         let sets = query_sets(queries);
         let f = {
             let coeffs = query_set_coeffs(&sets, z, &proof.z_prime);
@@ -72,7 +73,7 @@ where
         let rhs = Msm::base(&proof.w_prime);
         let lhs = f + rhs.clone() * &proof.z_prime;
 
-        KzgAccumulator::new(lhs.evaluate(Some(svk.g)), rhs.evaluate(Some(svk.g)))
+        Ok(KzgAccumulator::new(lhs.evaluate(Some(svk.g)), rhs.evaluate(Some(svk.g))))
     }
 }
 
@@ -95,13 +96,13 @@ where
     C: CurveAffine,
     L: Loader<C>,
 {
-    fn read<T: TranscriptRead<C, L>>(transcript: &mut T) -> Self {
+    fn read<T: TranscriptRead<C, L>>(transcript: &mut T) -> Result<Self, Error> {
         let mu = transcript.squeeze_challenge();
         let gamma = transcript.squeeze_challenge();
-        let w = transcript.read_ec_point().unwrap();
+        let w = transcript.read_ec_point()?;
         let z_prime = transcript.squeeze_challenge();
-        let w_prime = transcript.read_ec_point().unwrap();
-        Bdfg21Proof { mu, gamma, w, z_prime, w_prime }
+        let w_prime = transcript.read_ec_point()?;
+        Ok(Bdfg21Proof { mu, gamma, w, z_prime, w_prime })
     }
 }
 
@@ -11,6 +11,7 @@ use crate::{
         transcript::TranscriptRead,
         Itertools,
     },
+    Error,
 };
 
 /// Verifier of multi-open KZG. It is for the GWC implementation
@@ -31,7 +32,7 @@ where
         _: &Self::SuccinctVerifyingKey,
         queries: &[Query<M::Scalar>],
         transcript: &mut T,
-    ) -> Self::Proof
+    ) -> Result<Self::Proof, Error>
     where
         T: TranscriptRead<M::G1Affine, L>,
     {
@@ -44,7 +45,7 @@ where
         z: &L::LoadedScalar,
         queries: &[Query<M::Scalar, L::LoadedScalar>],
         proof: &Self::Proof,
-    ) -> Self::Accumulator {
+    ) -> Result<Self::Accumulator, Error> {
         let sets = query_sets(queries);
         let powers_of_u = &proof.u.powers(sets.len());
         let f = {
@@ -66,10 +67,10 @@ where
             .collect_vec();
         let lhs = f + rhs.iter().zip(z_omegas).map(|(uw, z_omega)| uw.clone() * &z_omega).sum();
 
-        KzgAccumulator::new(
+        Ok(KzgAccumulator::new(
             lhs.evaluate(Some(svk.g)),
             rhs.into_iter().sum::<Msm<_, _>>().evaluate(Some(svk.g)),
-        )
+        ))
     }
 }
 
@@ -89,14 +90,14 @@ where
     C: CurveAffine,
     L: Loader<C>,
 {
-    fn read<T>(queries: &[Query<C::Scalar>], transcript: &mut T) -> Self
+    fn read<T>(queries: &[Query<C::Scalar>], transcript: &mut T) -> Result<Self, Error>
     where
         T: TranscriptRead<C, L>,
     {
         let v = transcript.squeeze_challenge();
-        let ws = transcript.read_n_ec_points(query_sets(queries).len()).unwrap();
+        let ws = transcript.read_n_ec_points(query_sets(queries).len())?;
         let u = transcript.squeeze_challenge();
-        Gwc19Proof { v, ws, u }
+        Ok(Gwc19Proof { v, ws, u })
     }
 }
 
@@ -1,5 +1,7 @@
 //! [`halo2_proofs`] proof system
 //! Reference <https://hackmd.io/@dieGzUCgSGmRZFQ7SDxXCA/rk_0OgSla> for mv-lookup
+//! and <https://zcash.github.io/halo2/design/proving-system/lookup.html> for
+//! classic lookup.
 use crate::{
     halo2_proofs::{
         plonk::{
@@ -19,24 +21,38 @@ use crate::{
         },
         Itertools,
     },
-    Protocol,
+    Error, Protocol,
 };
 use halo2_base::halo2_proofs::halo2curves::ff::{FromUniformBytes, PrimeField};
 use num_integer::Integer;
 use std::{io, iter, mem::size_of};
 
+pub mod strategy;
 pub mod transcript;
 
 #[cfg(test)]
 #[cfg(feature = "loader_halo2")]
 pub(crate) mod test;
 
+/// Lookup argument used by the [`halo2_proofs`] prover.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LookupArgument {
+    /// Log-derivative lookup argument (mv-lookup), which commits to multiplicity
+    /// `m` and running sum `phi` for each lookup.
+    #[default]
+    LogDerivative,
+    /// Classic plookup-style lookup argument, which commits to permuted input,
+    /// permuted table and grand product `z` for each lookup.
+    Classic,
+}
+
 /// Configuration for converting a [`VerifyingKey`] of [`halo2_proofs`] into
 /// [`PlonkProtocol`].
 #[derive(Clone, Debug, Default)]
 pub struct Config {
     zk: bool,
     query_instance: bool,
+    lookup_argument: LookupArgument,
     num_proof: usize,
     num_instance: Vec<usize>,
     accumulator_indices: Option<Vec<(usize, usize)>>,
@@ -54,6 +70,12 @@ impl Config {
     }
 
     /// Set `zk`
+    ///
+    /// Disabling it compiles verifying keys of halo2 forks without blinding
+    /// rows, so there is no random polynomial nor `l_last`/`l_blind` terms.
+    /// The halo2 prover this crate tests against always creates zk proofs, so
+    /// the non-zk protocol is only checked structurally and not against real
+    /// proofs.
     pub fn set_zk(mut self, zk: bool) -> Self {
         self.zk = zk;
         self
@@ -65,6 +87,12 @@ impl Config {
         self
     }
 
+    /// Set `lookup_argument`
+    pub fn set_lookup_argument(mut self, lookup_argument: LookupArgument) -> Self {
+        self.lookup_argument = lookup_argument;
+        self
+    }
+
     /// Set `num_proof`
     pub fn with_num_proof(mut self, num_proof: usize) -> Self {
         assert!(num_proof > 0);
@@ -89,18 +117,29 @@ impl Config {
 }
 
 /// Convert a [`VerifyingKey`] of [`halo2_proofs`] into [`PlonkProtocol`].
+///
+/// Returns [`Error::UnsupportedFeature`] if the constraint system uses
+/// anything the compilation doesn't understand.
 pub fn compile<'a, C: CurveAffine, P: Params<'a, C>>(
     params: &P,
     vk: &VerifyingKey<C>,
     config: Config,
-) -> Protocol<C>
+) -> Result<Protocol<C>, Error>
 where
     C::ScalarExt: FromUniformBytes<64>,
 {
     assert_eq!(vk.get_domain().k(), params.k());
 
     let cs = vk.cs();
-    let Config { zk, query_instance, num_proof, num_instance, accumulator_indices } = config;
+    let Config {
+        zk,
+        query_instance,
+        lookup_argument,
+        num_proof,
+        num_instance,
+        accumulator_indices,
+    } = config;
+    check_supported(cs, lookup_argument)?;
 
     let k = params.k() as usize;
     let domain = Domain::new(k, root_of_unity(k));
@@ -113,7 +152,11 @@ where
         .map(Into::into)
         .collect();
 
-    let polynomials = &Polynomials::new(cs, zk, query_instance, num_instance, num_proof);
+    // Quotient is split into as many chunks as the prover does, which is fixed
+    // by the domain of verifying key in both modes.
+    let degree = vk.get_domain().get_quotient_poly_degree() + 1;
+    let polynomials =
+        &Polynomials::new(cs, zk, query_instance, lookup_argument, degree, num_instance, num_proof);
 
     let evaluations = iter::empty()
         .chain((0..num_proof).flat_map(move |t| polynomials.instance_queries(t)))
@@ -122,7 +165,8 @@ where
         .chain(polynomials.random_query())
         .chain(polynomials.permutation_fixed_queries())
         .chain((0..num_proof).flat_map(move |t| polynomials.permutation_z_queries::<true>(t)))
-        .chain((0..num_proof).flat_map(move |t| polynomials.lookup_queries(t)))
+        .chain((0..num_proof).flat_map(move |t| polynomials.lookup_queries::<true>(t)))
+        .chain((0..num_proof).flat_map(move |t| polynomials.shuffle_queries(t)))
         .collect();
 
     let queries = (0..num_proof)
@@ -131,7 +175,8 @@ where
                 .chain(polynomials.instance_queries(t))
                 .chain(polynomials.advice_queries(t))
                 .chain(polynomials.permutation_z_queries::<false>(t))
-                .chain(polynomials.lookup_queries(t))
+                .chain(polynomials.lookup_queries::<false>(t))
+                .chain(polynomials.shuffle_queries(t))
         })
         .chain(polynomials.fixed_queries())
         .chain(polynomials.permutation_fixed_queries())
@@ -152,7 +197,17 @@ where
         .map(|accumulator_indices| polynomials.accumulator_indices(accumulator_indices))
         .unwrap_or_default();
 
-    Protocol {
+    // Numerator has to fit in the quotient chunks committed by the prover, which
+    // is not the case if it used a lookup argument of higher degree.
+    let quotient = polynomials.quotient();
+    if quotient.numerator.degree() > degree {
+        return Err(Error::UnsupportedFeature(format!(
+            "Quotient numerator of degree {} exceeds degree {degree} of verifying key",
+            quotient.numerator.degree()
+        )));
+    }
+
+    Ok(Protocol {
         domain,
         preprocessed,
         num_instance: polynomials.num_instance(),
@@ -160,12 +215,26 @@ where
         num_challenge: polynomials.num_challenge(),
         evaluations,
         queries,
-        quotient: polynomials.quotient(),
+        quotient,
         transcript_initial_state: Some(transcript_initial_state),
         instance_committing_key,
         linearization: None,
         accumulator_indices,
-    }
+    })
+}
+
+/// Returns [`Protocol::digest`] of the [`Protocol`] compiled from the
+/// [`VerifyingKey`], which can be compared against a cached [`Protocol`] to
+/// check if it still matches the circuit.
+pub fn protocol_digest<'a, C: CurveAffine, P: Params<'a, C>>(
+    params: &P,
+    vk: &VerifyingKey<C>,
+    config: Config,
+) -> Result<[u8; 32], Error>
+where
+    C::ScalarExt: FromUniformBytes<64>,
+{
+    compile(params, vk, config).map(|protocol| protocol.digest())
 }
 
 impl From<poly::Rotation> for Rotation {
@@ -178,6 +247,7 @@ struct Polynomials<'a, F: PrimeField> {
     cs: &'a ConstraintSystem<F>,
     zk: bool,
     query_instance: bool,
+    lookup_argument: LookupArgument,
     degree: usize,
     num_proof: usize,
     num_fixed: usize,
@@ -188,9 +258,12 @@ struct Polynomials<'a, F: PrimeField> {
     advice_index: Vec<usize>,
     challenge_index: Vec<usize>,
     num_lookup_m: usize,
+    num_lookup_permuted: usize,
     permutation_chunk_size: usize,
     num_permutation_z: usize,
     num_lookup_phi: usize,
+    num_lookup_z: usize,
+    num_shuffle_z: usize,
 }
 
 impl<'a, F: PrimeField> Polynomials<'a, F> {
@@ -198,11 +271,11 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
         cs: &'a ConstraintSystem<F>,
         zk: bool,
         query_instance: bool,
+        lookup_argument: LookupArgument,
+        degree: usize,
         num_instance: Vec<usize>,
         num_proof: usize,
     ) -> Self {
-        // TODO: Re-enable optional-zk when it's merged in pse/halo2.
-        let degree = if zk { cs.degree() } else { unimplemented!() };
         let permutation_chunk_size = if zk || cs.permutation().get_columns().len() >= degree {
             degree - 2
         } else {
@@ -231,10 +304,18 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
         assert_eq!(num_advice.iter().sum::<usize>(), cs.num_advice_columns());
         assert_eq!(num_challenge.iter().sum::<usize>(), cs.num_challenges());
 
+        let num_lookup = cs.lookups().len();
+        let (num_lookup_m, num_lookup_phi, num_lookup_permuted, num_lookup_z) =
+            match lookup_argument {
+                LookupArgument::LogDerivative => (num_lookup, num_lookup, 0, 0),
+                LookupArgument::Classic => (0, 0, 2 * num_lookup, num_lookup),
+            };
+
         Self {
             cs,
             zk,
             query_instance,
+            lookup_argument,
             degree,
             num_proof,
             num_fixed: cs.num_fixed_columns(),
@@ -244,13 +325,16 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
             num_challenge,
             advice_index,
             challenge_index,
-            num_lookup_m: cs.lookups().len(),
+            num_lookup_m,
+            num_lookup_permuted,
             permutation_chunk_size,
             num_permutation_z: Integer::div_ceil(
                 &cs.permutation().get_columns().len(),
                 &permutation_chunk_size,
             ),
-            num_lookup_phi: cs.lookups().len(),
+            num_lookup_phi,
+            num_lookup_z,
+            num_shuffle_z: cs.shuffles().len(),
         }
     }
 
@@ -266,8 +350,13 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
         iter::empty()
             .chain(self.num_advice.clone().iter().map(|num| self.num_proof * num))
             .chain([
-                self.num_proof * self.num_lookup_m,
-                self.num_proof * (self.num_permutation_z + self.num_lookup_phi) + self.zk as usize,
+                self.num_proof * (self.num_lookup_m + self.num_lookup_permuted),
+                self.num_proof
+                    * (self.num_permutation_z
+                        + self.num_lookup_phi
+                        + self.num_lookup_z
+                        + self.num_shuffle_z)
+                    + self.zk as usize,
             ])
             .collect()
     }
@@ -391,10 +480,66 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
         (m, phi)
     }
 
-    fn lookup_queries(&'a self, t: usize) -> impl IntoIterator<Item = Query> + 'a {
-        (0..self.num_lookup_phi).flat_map(move |i| {
-            let (m, phi) = self.lookup_poly(t, i);
-            [Query::new(phi, 0), Query::new(phi, 1), Query::new(m, 0)]
+    fn classic_lookup_poly(&'a self, t: usize, i: usize) -> (usize, usize, usize) {
+        let permuted_offset = self.cs_witness_offset();
+        let z_offset = permuted_offset
+            + self.num_witness()[self.num_advice.len()]
+            + self.num_proof * self.num_permutation_z;
+        let z = z_offset + t * self.num_lookup_z + i;
+        let permuted_input = permuted_offset + 2 * (t * self.num_lookup_z + i);
+        let permuted_table = permuted_input + 1;
+        (z, permuted_input, permuted_table)
+    }
+
+    fn lookup_queries<const EVAL: bool>(
+        &'a self,
+        t: usize,
+    ) -> impl IntoIterator<Item = Query> + 'a {
+        match (self.lookup_argument, EVAL) {
+            (LookupArgument::LogDerivative, _) => (0..self.num_lookup_phi)
+                .flat_map(move |i| {
+                    let (m, phi) = self.lookup_poly(t, i);
+                    [Query::new(phi, 0), Query::new(phi, 1), Query::new(m, 0)]
+                })
+                .collect_vec(),
+            (LookupArgument::Classic, true) => (0..self.num_lookup_z)
+                .flat_map(move |i| {
+                    let (z, permuted_input, permuted_table) = self.classic_lookup_poly(t, i);
+                    [
+                        Query::new(z, 0),
+                        Query::new(z, 1),
+                        Query::new(permuted_input, 0),
+                        Query::new(permuted_input, -1),
+                        Query::new(permuted_table, 0),
+                    ]
+                })
+                .collect_vec(),
+            (LookupArgument::Classic, false) => (0..self.num_lookup_z)
+                .flat_map(move |i| {
+                    let (z, permuted_input, permuted_table) = self.classic_lookup_poly(t, i);
+                    [
+                        Query::new(z, 0),
+                        Query::new(permuted_input, 0),
+                        Query::new(permuted_table, 0),
+                        Query::new(permuted_input, -1),
+                        Query::new(z, 1),
+                    ]
+                })
+                .collect_vec(),
+        }
+    }
+
+    fn shuffle_poly(&'a self, t: usize, i: usize) -> usize {
+        let z_offset = self.cs_witness_offset()
+            + self.num_witness()[self.num_advice.len()]
+            + self.num_proof * (self.num_permutation_z + self.num_lookup_phi + self.num_lookup_z);
+        z_offset + t * self.num_shuffle_z + i
+    }
+
+    fn shuffle_queries(&'a self, t: usize) -> impl IntoIterator<Item = Query> + 'a {
+        (0..self.num_shuffle_z).flat_map(move |i| {
+            let z = self.shuffle_poly(t, i);
+            [Query::new(z, 0), Query::new(z, 1)]
         })
     }
 
@@ -494,7 +639,6 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
         let one = &Expression::Constant(F::ONE);
         let l_0 = &Expression::<F>::CommonPolynomial(CommonPolynomial::Lagrange(0));
         let l_last = &self.l_last();
-        let l_active = &self.l_active();
         let identity = &Expression::<F>::CommonPolynomial(CommonPolynomial::Identity);
         let beta = &self.beta();
         let gamma = &self.gamma();
@@ -572,7 +716,7 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
                                 .reduce(|acc, expr| acc * expr)
                                 .unwrap();
                             if self.zk {
-                                l_active * (left - right)
+                                self.l_active() * (left - right)
                             } else {
                                 left - right
                             }
@@ -582,10 +726,15 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
             .collect_vec()
     }
 
-    fn lookup_constraints(&'a self, t: usize) -> impl IntoIterator<Item = Expression<F>> + 'a {
+    fn lookup_constraints(&'a self, t: usize) -> Vec<Expression<F>> {
+        match self.lookup_argument {
+            LookupArgument::LogDerivative => self.log_derivative_lookup_constraints(t),
+            LookupArgument::Classic => self.classic_lookup_constraints(t),
+        }
+    }
+
+    fn log_derivative_lookup_constraints(&'a self, t: usize) -> Vec<Expression<F>> {
         let l_0 = &Expression::<F>::CommonPolynomial(CommonPolynomial::Lagrange(0));
-        let l_last = &self.l_last();
-        let l_active = &self.l_active();
         let beta = &self.beta();
 
         let polys = (0..self.num_lookup_phi)
@@ -617,31 +766,117 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
                     .map(|expressions| compress(expressions) + beta)
                     .collect_vec();
                 let table = &(compress(lookup.table_expressions()) + beta);
-                iter::empty().chain(Some(l_0 * phi)).chain(self.zk.then(|| l_last * phi)).chain(
-                    Some(if self.zk {
-                        let input_prod = &inputs.iter().cloned().product::<Expression<_>>();
-                        let lhs = table * input_prod * (phi_omega - phi);
-                        let rhs = (inputs.len() > 1)
-                            .then(|| {
-                                (0..inputs.len())
-                                    .map(|i| {
-                                        izip!(0.., &inputs)
-                                            .filter_map(|(j, input)| (i != j).then_some(input))
-                                            .cloned()
-                                            .product()
-                                    })
-                                    .sum::<Expression<_>>()
-                                    * table
+                let input_prod = &inputs.iter().cloned().product::<Expression<_>>();
+                let lhs = table * input_prod * (phi_omega - phi);
+                let rhs = (inputs.len() > 1)
+                    .then(|| {
+                        (0..inputs.len())
+                            .map(|i| {
+                                izip!(0.., &inputs)
+                                    .filter_map(|(j, input)| (i != j).then_some(input))
+                                    .cloned()
+                                    .product()
                             })
-                            .unwrap_or_else(|| table.clone())
-                            - m * input_prod;
+                            .sum::<Expression<_>>()
+                            * table
+                    })
+                    .unwrap_or_else(|| table.clone())
+                    - m * input_prod;
+
+                // Without zk, the running sum wraps around so it's checked on
+                // every row and no `l_last` is needed.
+                iter::empty()
+                    .chain(Some(l_0 * phi))
+                    .chain(self.zk.then(|| self.l_last() * phi))
+                    .chain(Some(if self.zk { self.l_active() * (lhs - rhs) } else { lhs - rhs }))
+            })
+            .collect_vec()
+    }
+
+    fn classic_lookup_constraints(&'a self, t: usize) -> Vec<Expression<F>> {
+        let one = &Expression::Constant(F::ONE);
+        let l_0 = &Expression::<F>::CommonPolynomial(CommonPolynomial::Lagrange(0));
+        let beta = &self.beta();
+        let gamma = &self.gamma();
 
-                        l_active * (lhs - rhs)
-                    } else {
-                        unimplemented!()
-                    }),
+        let polys = (0..self.num_lookup_z)
+            .map(|i| {
+                let (z, permuted_input, permuted_table) = self.classic_lookup_poly(t, i);
+                (
+                    Expression::<F>::Polynomial(Query::new(z, 0)),
+                    Expression::<F>::Polynomial(Query::new(z, 1)),
+                    Expression::<F>::Polynomial(Query::new(permuted_input, 0)),
+                    Expression::<F>::Polynomial(Query::new(permuted_input, -1)),
+                    Expression::<F>::Polynomial(Query::new(permuted_table, 0)),
                 )
             })
+            .collect_vec();
+
+        let compress = |expressions: &[plonk::Expression<F>]| {
+            Expression::DistributePowers(
+                expressions.iter().map(|expression| self.convert(expression, t)).collect(),
+                self.theta().into(),
+            )
+        };
+
+        self.cs
+            .lookups()
+            .iter()
+            .zip(polys.iter())
+            .flat_map(
+                |(
+                    lookup,
+                    (z, z_omega, permuted_input, permuted_input_omega_inv, permuted_table),
+                )| {
+                    // Only a single input set is allowed, which is checked by `check_supported`
+                    let input = compress(&lookup.input_expressions()[0]);
+                    let table = compress(lookup.table_expressions());
+                    let product = z_omega * (permuted_input + beta) * (permuted_table + gamma)
+                        - z * (input + beta) * (table + gamma);
+                    let permuted = (permuted_input - permuted_table)
+                        * (permuted_input - permuted_input_omega_inv);
+                    iter::empty()
+                        .chain(Some(l_0 * (one - z)))
+                        .chain(self.zk.then(|| self.l_last() * (z * z - z)))
+                        .chain(Some(if self.zk { self.l_active() * product } else { product }))
+                        .chain(Some(l_0 * (permuted_input - permuted_table)))
+                        .chain(Some(if self.zk { self.l_active() * permuted } else { permuted }))
+                },
+            )
+            .collect_vec()
+    }
+
+    fn shuffle_constraints(&'a self, t: usize) -> impl IntoIterator<Item = Expression<F>> + 'a {
+        let one = &Expression::Constant(F::ONE);
+        let l_0 = &Expression::<F>::CommonPolynomial(CommonPolynomial::Lagrange(0));
+        let gamma = &self.gamma();
+
+        let compress = |expressions: &[plonk::Expression<F>]| {
+            Expression::DistributePowers(
+                expressions.iter().map(|expression| self.convert(expression, t)).collect(),
+                self.theta().into(),
+            )
+        };
+
+        self.cs
+            .shuffles()
+            .iter()
+            .enumerate()
+            .flat_map(|(i, shuffle)| {
+                let z = self.shuffle_poly(t, i);
+                let (z, z_omega) = (
+                    &Expression::<F>::Polynomial(Query::new(z, 0)),
+                    &Expression::<F>::Polynomial(Query::new(z, 1)),
+                );
+                let input = compress(shuffle.input_expressions()) + gamma;
+                let shuffle = compress(shuffle.shuffle_expressions()) + gamma;
+                let product = z_omega * shuffle - z * input;
+
+                iter::empty()
+                    .chain(Some(l_0 * (one - z)))
+                    .chain(self.zk.then(|| self.l_last() * (z * z - z)))
+                    .chain(Some(if self.zk { self.l_active() * product } else { product }))
+            })
             .collect_vec()
     }
 
@@ -652,6 +887,7 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
                     .chain(self.gate_constraints(t))
                     .chain(self.permutation_constraints(t))
                     .chain(self.lookup_constraints(t))
+                    .chain(self.shuffle_constraints(t))
             })
             .collect_vec();
         let numerator = Expression::DistributePowers(constraints, self.alpha().into());
@@ -674,6 +910,61 @@ impl<'a, F: PrimeField> Polynomials<'a, F> {
     }
 }
 
+/// Returns [`Error::UnsupportedFeature`] if [`ConstraintSystem`] contains
+/// anything that [`Polynomials`] doesn't know how to translate.
+fn check_supported<F: PrimeField>(
+    cs: &ConstraintSystem<F>,
+    lookup_argument: LookupArgument,
+) -> Result<(), Error> {
+    if let Some(phase) = iter::empty()
+        .chain(cs.advice_column_phase())
+        .chain(cs.challenge_phase())
+        .find(|phase| *phase > 2)
+    {
+        return Err(Error::UnsupportedFeature(format!("Phase {phase} is not supported")));
+    }
+
+    if lookup_argument == LookupArgument::Classic
+        && cs.lookups().iter().any(|lookup| lookup.input_expressions().len() != 1)
+    {
+        return Err(Error::UnsupportedFeature(
+            "Classic lookup argument with multiple input sets".to_string(),
+        ));
+    }
+
+    let has_selector = |expression: &plonk::Expression<F>| {
+        expression.evaluate(
+            &|_| false,
+            &|_| true,
+            &|_| false,
+            &|_| false,
+            &|_| false,
+            &|_| false,
+            &|a| a,
+            &|a, b| a || b,
+            &|a, b| a || b,
+            &|a, _| a,
+        )
+    };
+    let expressions = iter::empty()
+        .chain(cs.gates().iter().flat_map(|gate| gate.polynomials()))
+        .chain(cs.lookups().iter().flat_map(|lookup| {
+            lookup.input_expressions().iter().flatten().chain(lookup.table_expressions())
+        }))
+        .chain(cs.shuffles().iter().flat_map(|shuffle| {
+            shuffle.input_expressions().iter().chain(shuffle.shuffle_expressions())
+        }));
+    for expression in expressions {
+        if has_selector(expression) {
+            return Err(Error::UnsupportedFeature(
+                "Uncompressed selector in expression".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 struct MockChallenge;
 
 impl<C: CurveAffine> EncodedChallenge<C> for MockChallenge {
@@ -628,7 +628,7 @@ pub fn create_snark_shplonk<T: TargetCircuit>(
     let pk = gen_pk(params, &circuits[0], T::name().as_str());
     // num_instance[i] is length of the i-th instance columns in circuit 0 (all circuits should have same shape of instances)
     let num_instance = instances[0].iter().map(|instance_column| instance_column.len()).collect();
-    let protocol = compile(params, pk.get_vk(), config.with_num_instance(num_instance));
+    let protocol = compile(params, pk.get_vk(), config.with_num_instance(num_instance)).unwrap();
 
     // usual shenanigans to turn nested Vec into nested slice
     let instances1: Vec<Vec<&[Fr]>> = instances
@@ -17,9 +17,10 @@ use rand_chacha::rand_core::RngCore;
 use std::{fs, io::Cursor};
 
 mod circuit;
+mod ipa;
 mod kzg;
 
-pub use circuit::standard::StandardPlonk;
+pub use circuit::{lookup::Lookup, shuffle::Shuffle, standard::StandardPlonk};
 
 pub fn read_or_create_srs<'a, C: CurveAffine, P: ParamsProver<'a, C>>(
     dir: &str,
@@ -107,14 +108,9 @@ macro_rules! halo2_prepare {
             .take($config.num_proof)
             .collect_vec();
 
-        let pk = if $config.zk {
-            let vk = keygen_vk(&params, &circuits[0]).unwrap();
-            let pk = keygen_pk(&params, vk, &circuits[0]).unwrap();
-            pk
-        } else {
-            // TODO: Re-enable optional-zk when it's merged in pse/halo2.
-            unimplemented!()
-        };
+        // Keys are the same in both modes, zk only affects how the proof is created.
+        let vk = keygen_vk(&params, &circuits[0]).unwrap();
+        let pk = keygen_pk(&params, vk, &circuits[0]).unwrap();
 
         let num_instance = circuits[0]
             .instances()
@@ -125,7 +121,7 @@ macro_rules! halo2_prepare {
             &params,
             pk.get_vk(),
             $config.with_num_instance(num_instance),
-        );
+        ).unwrap();
 
         /* assert fails when fixed column is all 0s
         assert_eq!(
@@ -209,8 +205,9 @@ macro_rules! halo2_native_verify {
     ) => {{
         use $crate::{halo2_proofs::poly::commitment::ParamsProver, verifier::PlonkVerifier};
 
-        let proof = <$plonk_verifier>::read_proof($svk, $protocol, $instances, $transcript);
-        assert!(<$plonk_verifier>::verify($svk, $dk, $protocol, $instances, &proof))
+        let proof =
+            <$plonk_verifier>::read_proof($svk, $protocol, $instances, $transcript).unwrap();
+        assert!(<$plonk_verifier>::verify($svk, $dk, $protocol, $instances, &proof).unwrap())
     }};
 }
 
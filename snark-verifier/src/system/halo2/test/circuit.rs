@@ -1,2 +1,4 @@
+pub mod lookup;
 // pub mod maingate;
+pub mod shuffle;
 pub mod standard;
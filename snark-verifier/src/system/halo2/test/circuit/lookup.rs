@@ -0,0 +1,110 @@
+use crate::halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance},
+    poly::Rotation,
+};
+use halo2_base::halo2_proofs::halo2curves::ff::PrimeField;
+use rand::RngCore;
+
+/// Size of the fixed table looked up into.
+const TABLE_SIZE: usize = 8;
+
+#[derive(Clone)]
+pub struct LookupConfig<const N: usize> {
+    inputs: [Column<Advice>; N],
+    table: Column<Fixed>,
+    q_instance: Column<Fixed>,
+    instance: Column<Instance>,
+}
+
+impl<const N: usize> LookupConfig<N> {
+    pub fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>) -> Self {
+        let inputs = [(); N].map(|_| meta.advice_column());
+        let [table, q_instance] = [(); 2].map(|_| meta.fixed_column());
+        let instance = meta.instance_column();
+
+        meta.create_gate("q_instance·(input - instance) = 0", |meta| {
+            let q_instance = meta.query_fixed(q_instance, Rotation::cur());
+            let input = meta.query_advice(inputs[0], Rotation::cur());
+            let instance = meta.query_instance(instance, Rotation::cur());
+            Some(q_instance * (input - instance))
+        });
+
+        // Lookups into the same table are batched into one argument with
+        // multiple input sets when the degree allows
+        for input in inputs {
+            meta.lookup_any("input ∈ table", |meta| {
+                let input = meta.query_advice(input, Rotation::cur());
+                let table = meta.query_fixed(table, Rotation::cur());
+                vec![(input, table)]
+            });
+        }
+
+        LookupConfig { inputs, table, q_instance, instance }
+    }
+}
+
+/// Circuit that looks up values of `N` advice columns in a fixed table of
+/// `0..TABLE_SIZE`, and exposes the first one as instance.
+#[derive(Clone)]
+pub struct Lookup<F, const N: usize>([Vec<F>; N]);
+
+impl<F: PrimeField, const N: usize> Lookup<F, N> {
+    pub fn rand<R: RngCore>(mut rng: R) -> Self {
+        Self([(); N].map(|_| {
+            (0..TABLE_SIZE).map(|_| F::from(rng.next_u32() as u64 % TABLE_SIZE as u64)).collect()
+        }))
+    }
+
+    pub fn instances(&self) -> Vec<Vec<F>> {
+        vec![vec![self.0[0][0]]]
+    }
+}
+
+impl<F: PrimeField, const N: usize> Default for Lookup<F, N> {
+    fn default() -> Self {
+        Self([(); N].map(|_| vec![F::ZERO; TABLE_SIZE]))
+    }
+}
+
+impl<F: PrimeField, const N: usize> Circuit<F> for Lookup<F, N> {
+    type Params = ();
+    type Config = LookupConfig<N>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        LookupConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "",
+            |mut region| {
+                region.assign_fixed(|| "", config.q_instance, 0, || Value::known(F::ONE))?;
+                for offset in 0..TABLE_SIZE {
+                    region.assign_fixed(
+                        || "",
+                        config.table,
+                        offset,
+                        || Value::known(F::from(offset as u64)),
+                    )?;
+                }
+                for (column, values) in config.inputs.iter().zip(self.0.iter()) {
+                    for (offset, value) in values.iter().enumerate() {
+                        region.assign_advice(|| "", *column, offset, || Value::known(*value))?;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
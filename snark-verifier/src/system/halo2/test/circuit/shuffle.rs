@@ -0,0 +1,103 @@
+use crate::halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance},
+    poly::Rotation,
+};
+use halo2_base::halo2_proofs::halo2curves::ff::PrimeField;
+use rand::RngCore;
+
+#[derive(Clone)]
+pub struct ShuffleConfig {
+    input: Column<Advice>,
+    shuffled: Column<Advice>,
+    q_shuffle: Column<Fixed>,
+    q_instance: Column<Fixed>,
+    instance: Column<Instance>,
+}
+
+impl ShuffleConfig {
+    pub fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>) -> Self {
+        let [input, shuffled] = [(); 2].map(|_| meta.advice_column());
+        let [q_shuffle, q_instance] = [(); 2].map(|_| meta.fixed_column());
+        let instance = meta.instance_column();
+
+        meta.create_gate("q_instance·(input - instance) = 0", |meta| {
+            let q_instance = meta.query_fixed(q_instance, Rotation::cur());
+            let input = meta.query_advice(input, Rotation::cur());
+            let instance = meta.query_instance(instance, Rotation::cur());
+            Some(q_instance * (input - instance))
+        });
+
+        meta.shuffle("q_shuffle·input ~ q_shuffle·shuffled", |meta| {
+            let q_shuffle = meta.query_fixed(q_shuffle, Rotation::cur());
+            let input = meta.query_advice(input, Rotation::cur());
+            let shuffled = meta.query_advice(shuffled, Rotation::cur());
+            vec![(q_shuffle.clone() * input, q_shuffle * shuffled)]
+        });
+
+        ShuffleConfig { input, shuffled, q_shuffle, q_instance, instance }
+    }
+}
+
+/// Circuit that shuffles its values in reverse order, and exposes the first
+/// one as instance.
+#[derive(Clone, Default)]
+pub struct Shuffle<F>(Vec<F>);
+
+impl<F: PrimeField> Shuffle<F> {
+    pub fn rand<R: RngCore>(mut rng: R) -> Self {
+        Self((0..8).map(|_| F::from(rng.next_u32() as u64)).collect())
+    }
+
+    pub fn instances(&self) -> Vec<Vec<F>> {
+        vec![vec![self.0[0]]]
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for Shuffle<F> {
+    type Params = ();
+    type Config = ShuffleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    #[cfg(feature = "circuit-params")]
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self(vec![F::ZERO; self.0.len()])
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        ShuffleConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "",
+            |mut region| {
+                region.assign_fixed(|| "", config.q_instance, 0, || Value::known(F::ONE))?;
+                for (offset, (input, shuffled)) in
+                    self.0.iter().zip(self.0.iter().rev()).enumerate()
+                {
+                    region.assign_fixed(
+                        || "",
+                        config.q_shuffle,
+                        offset,
+                        || Value::known(F::ONE),
+                    )?;
+                    region.assign_advice(|| "", config.input, offset, || Value::known(*input))?;
+                    region.assign_advice(
+                        || "",
+                        config.shuffled,
+                        offset,
+                        || Value::known(*shuffled),
+                    )?;
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
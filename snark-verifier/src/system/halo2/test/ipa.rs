@@ -0,0 +1,117 @@
+use crate::{
+    halo2_proofs::poly::{commitment::ParamsProver, ipa::commitment::ParamsIPA},
+    util::arithmetic::{Curve, CurveAffine, CurveExt},
+};
+
+mod native;
+
+#[cfg(feature = "loader_halo2")]
+mod halo2;
+
+#[allow(dead_code)]
+pub const TESTDATA_DIR: &str = "./src/system/halo2/test/data/ipa";
+
+pub const LIMBS: usize = 3;
+pub const BITS: usize = 88;
+
+pub fn setup<C: CurveAffine>(k: u32) -> ParamsIPA<C> {
+    ParamsIPA::new(k)
+}
+
+/// Returns the blinding base `W` and evaluation base `U` derived the same way as
+/// [`ParamsIPA::new`].
+pub fn w_u<C: CurveAffine>() -> (C, C) {
+    let hasher = C::CurveExt::hash_to_curve("Halo2-Parameters");
+    (hasher(&[1]).to_affine(), hasher(&[2]).to_affine())
+}
+
+macro_rules! halo2_ipa_config {
+    ($zk:expr, $num_proof:expr) => {
+        $crate::system::halo2::Config::ipa().set_zk($zk).with_num_proof($num_proof)
+    };
+    ($zk:expr, $num_proof:expr, $accumulator_indices:expr) => {
+        $crate::system::halo2::Config::ipa()
+            .set_zk($zk)
+            .with_num_proof($num_proof)
+            .with_accumulator_indices($accumulator_indices)
+    };
+}
+
+macro_rules! halo2_ipa_prepare {
+    ($curve:path, $k:expr, $config:expr, $create_circuit:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::system::halo2::test::halo2_prepare;
+        #[allow(unused_imports)]
+        use $crate::system::halo2::test::ipa::setup;
+        #[allow(unused_imports)]
+        use $crate::system::halo2::test::ipa::TESTDATA_DIR;
+
+        halo2_prepare!(TESTDATA_DIR, $k, setup::<$curve>, $config, $create_circuit)
+    }};
+}
+
+macro_rules! halo2_ipa_create_snark {
+    (
+        $prover:ty,
+        $verifier:ty,
+        $transcript_read:ty,
+        $transcript_write:ty,
+        $encoded_challenge:ty,
+        $params:expr,
+        $pk:expr,
+        $protocol:expr,
+        $circuits:expr
+    ) => {{
+        use $crate::{
+            halo2_proofs::poly::ipa::commitment::IPACommitmentScheme,
+            system::halo2::{strategy::ipa::SingleStrategy, test::halo2_create_snark},
+            util::arithmetic::GroupEncoding,
+        };
+
+        halo2_create_snark!(
+            IPACommitmentScheme<_>,
+            $prover,
+            $verifier,
+            SingleStrategy<_>,
+            $transcript_read,
+            $transcript_write,
+            $encoded_challenge,
+            |proof, g| { [proof, g.to_bytes().as_ref().to_vec()].concat() },
+            $params,
+            $pk,
+            $protocol,
+            $circuits
+        )
+    }};
+}
+
+macro_rules! halo2_ipa_native_verify {
+    (
+        $plonk_verifier:ty,
+        $params:expr,
+        $protocol:expr,
+        $instances:expr,
+        $transcript:expr
+    ) => {{
+        use $crate::{
+            pcs::ipa::{Bgh19SuccinctVerifyingKey, IpaDecidingKey},
+            system::halo2::test::{halo2_native_verify, ipa::w_u},
+        };
+
+        let (w, u) = w_u();
+        halo2_native_verify!(
+            $plonk_verifier,
+            $params,
+            $protocol,
+            $instances,
+            $transcript,
+            &Bgh19SuccinctVerifyingKey::new($protocol.domain.clone(), $params.get_g()[0], w, u),
+            &IpaDecidingKey::new($params.get_g().to_vec())
+        )
+    }};
+}
+
+pub(crate) use halo2_ipa_config;
+pub(crate) use halo2_ipa_create_snark;
+pub(crate) use halo2_ipa_native_verify;
+pub(crate) use halo2_ipa_prepare;
@@ -0,0 +1,376 @@
+use crate::{
+    halo2_curves::pasta::{pallas, Fp, Fq},
+    halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{
+            Circuit, Column, ConstraintSystem, Instance, {self},
+        },
+        poly::{
+            commitment::ParamsProver,
+            ipa::{
+                commitment::ParamsIPA,
+                multiopen::{ProverIPA, VerifierIPA},
+            },
+        },
+    },
+    loader::{
+        halo2::test::{Snark, SnarkWitness},
+        native::NativeLoader,
+        {self},
+    },
+    pcs::{
+        ipa::{
+            Bgh19, Bgh19SuccinctVerifyingKey, Ipa, IpaAccumulator, IpaAs, IpaDecidingKey,
+            IpaLimbsEncoding, IpaProvingKey, IpaSuccinctVerifyingKey,
+        },
+        AccumulationScheme, AccumulationSchemeProver, AccumulatorEncoding, Decider,
+    },
+    system::halo2::{
+        test::{
+            ipa::{halo2_ipa_config, halo2_ipa_create_snark, halo2_ipa_prepare, w_u, BITS, LIMBS},
+            StandardPlonk,
+        },
+        transcript::halo2::{ChallengeScalar, PoseidonTranscript as GenericPoseidonTranscript},
+    },
+    util::{arithmetic::fe_to_limbs, Itertools},
+    verifier::{
+        PlonkVerifier, {self},
+    },
+};
+use halo2_base::{Context, ContextParams};
+use halo2_ecc::{
+    ecc::EccChip,
+    fields::fp::{FpConfig, FpStrategy},
+};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use std::{iter, rc::Rc};
+
+const T: usize = 5;
+const RATE: usize = 4;
+const R_F: usize = 8;
+const R_P: usize = 60;
+
+const K: u32 = 21;
+
+type BaseFieldEccChip = halo2_ecc::ecc::BaseFieldEccChip<pallas::Affine>;
+type Halo2Loader<'a> = loader::halo2::Halo2Loader<'a, pallas::Affine, BaseFieldEccChip>;
+type Scalar<'a> = loader::halo2::Scalar<'a, pallas::Affine, BaseFieldEccChip>;
+type PoseidonTranscript<L, S> = GenericPoseidonTranscript<pallas::Affine, L, S, T, RATE, R_F, R_P>;
+
+type Pcs = Ipa<pallas::Affine, Bgh19>;
+type Svk = Bgh19SuccinctVerifyingKey<pallas::Affine>;
+type As = IpaAs<Pcs>;
+type AsPk = IpaProvingKey<pallas::Affine>;
+type AsVk = IpaSuccinctVerifyingKey<pallas::Affine>;
+type Plonk = verifier::Plonk<Pcs>;
+
+pub fn accumulate<'a>(
+    svk: &Svk,
+    loader: &Rc<Halo2Loader<'a>>,
+    snarks: &[SnarkWitness<pallas::Affine>],
+    prev_accumulator: Option<&[Scalar<'a>]>,
+    as_vk: &AsVk,
+    as_proof: Value<&'_ [u8]>,
+) -> IpaAccumulator<pallas::Affine, Rc<Halo2Loader<'a>>> {
+    let assign_instances = |instances: &[Vec<Value<Fq>>]| {
+        instances
+            .iter()
+            .map(|instances| {
+                instances.iter().map(|instance| loader.assign_scalar(*instance)).collect_vec()
+            })
+            .collect_vec()
+    };
+
+    let prev_accumulator = prev_accumulator.map(|repr| {
+        <IpaLimbsEncoding<LIMBS, BITS> as AccumulatorEncoding<_, Rc<Halo2Loader>, Pcs>>::from_repr(
+            &repr.iter().collect_vec(),
+        )
+        .unwrap()
+    });
+    let mut accumulators = snarks
+        .iter()
+        .flat_map(|snark| {
+            let protocol = snark.protocol.loaded(loader);
+            let instances = assign_instances(&snark.instances);
+            let mut transcript =
+                PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, snark.proof());
+            let proof = Plonk::read_proof(svk, &protocol, &instances, &mut transcript).unwrap();
+            Plonk::succinct_verify(svk, &protocol, &instances, &proof).unwrap()
+        })
+        .chain(prev_accumulator)
+        .collect_vec();
+
+    let accumulator = if accumulators.len() > 1 {
+        let mut transcript = PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, as_proof);
+        let proof = As::read_proof(as_vk, &accumulators, &mut transcript).unwrap();
+        As::verify(as_vk, &accumulators, &proof).unwrap()
+    } else {
+        accumulators.pop().unwrap()
+    };
+
+    accumulator
+}
+
+pub struct Accumulation {
+    svk: Svk,
+    snarks: Vec<SnarkWitness<pallas::Affine>>,
+    prev_accumulator: Option<Vec<Value<Fq>>>,
+    instances: Vec<Fq>,
+    as_vk: AsVk,
+    as_proof: Value<Vec<u8>>,
+}
+
+impl Accumulation {
+    /// Accumulates `snarks` together with `prev_accumulator` if any, which is
+    /// the representation of accumulator exposed by previous accumulation and
+    /// is exposed again ahead of the new accumulator.
+    pub fn new(
+        params: &ParamsIPA<pallas::Affine>,
+        snarks: impl IntoIterator<Item = Snark<pallas::Affine>>,
+        prev_accumulator: Option<Vec<Fq>>,
+    ) -> Self {
+        let snarks = snarks.into_iter().collect_vec();
+        let domain = snarks[0].protocol.domain.clone();
+        let (w, u) = w_u();
+        let svk = Svk::new(domain.clone(), params.get_g()[0], w, u);
+
+        let prev = prev_accumulator.as_ref().map(|repr| {
+            <IpaLimbsEncoding<LIMBS, BITS> as AccumulatorEncoding<_, NativeLoader, Pcs>>::from_repr(
+                &repr.iter().collect_vec(),
+            )
+            .unwrap()
+        });
+        let mut accumulators = snarks
+            .iter()
+            .flat_map(|snark| {
+                let mut transcript =
+                    PoseidonTranscript::<NativeLoader, _>::new(snark.proof.as_slice());
+                let proof =
+                    Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript)
+                        .unwrap();
+                Plonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof).unwrap()
+            })
+            .chain(prev)
+            .collect_vec();
+
+        let as_pk = AsPk::new(domain, params.get_g().to_vec(), u, Some(w));
+        let (accumulator, as_proof) = if accumulators.len() > 1 {
+            let mut transcript = PoseidonTranscript::<NativeLoader, _>::new(Vec::new());
+            let accumulator = As::create_proof(
+                &as_pk,
+                &accumulators,
+                &mut transcript,
+                ChaCha20Rng::from_seed(Default::default()),
+            )
+            .unwrap();
+            (accumulator, Value::known(transcript.finalize()))
+        } else {
+            (accumulators.pop().unwrap(), Value::unknown())
+        };
+
+        let IpaAccumulator { xi, u } = accumulator;
+        let repr = iter::empty()
+            .chain(xi)
+            .chain([u.x, u.y].map(fe_to_limbs::<_, _, LIMBS, BITS>).concat())
+            .collect_vec();
+
+        // The expensive MSM of the final check is deferred to the native
+        // decider, which works on the accumulator decoded from instances.
+        let accumulator = <IpaLimbsEncoding<LIMBS, BITS> as AccumulatorEncoding<
+            _,
+            NativeLoader,
+            Pcs,
+        >>::from_repr(&repr.iter().collect_vec())
+        .unwrap();
+        assert!(Pcs::decide(&IpaDecidingKey::new(params.get_g().to_vec()), accumulator));
+
+        let instances =
+            iter::empty().chain(prev_accumulator.iter().flatten().copied()).chain(repr).collect();
+        Self {
+            svk,
+            snarks: snarks.into_iter().map_into().collect(),
+            prev_accumulator: prev_accumulator
+                .map(|repr| repr.into_iter().map(Value::known).collect()),
+            instances,
+            as_vk: as_pk.svk(),
+            as_proof,
+        }
+    }
+
+    fn prepare_snarks(n: usize) -> (ParamsIPA<pallas::Affine>, Vec<Snark<pallas::Affine>>) {
+        let (params, pk, protocol, circuits) = halo2_ipa_prepare!(
+            pallas::Affine,
+            9,
+            halo2_ipa_config!(true, 1),
+            StandardPlonk::<_>::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        let snarks = iter::repeat_with(|| {
+            halo2_ipa_create_snark!(
+                ProverIPA<_>,
+                VerifierIPA<_>,
+                PoseidonTranscript<_, _>,
+                PoseidonTranscript<_, _>,
+                ChallengeScalar<_>,
+                &params,
+                &pk,
+                &protocol,
+                &circuits
+            )
+        })
+        .take(n)
+        .collect_vec();
+        (params, snarks)
+    }
+
+    pub fn two_snark() -> Self {
+        let (params, snarks) = Self::prepare_snarks(2);
+        Self::new(&params, snarks, None)
+    }
+
+    /// Accumulates a snark together with accumulator of another snark, which
+    /// is decoded from instances the same way as in recursion.
+    pub fn one_snark_with_prev_accumulator() -> Self {
+        let (params, mut snarks) = Self::prepare_snarks(2);
+        let prev = Self::new(&params, [snarks.remove(0)], None);
+        Self::new(&params, snarks, Some(prev.instances))
+    }
+
+    pub fn instances(&self) -> Vec<Vec<Fq>> {
+        vec![self.instances.clone()]
+    }
+
+    pub fn as_proof(&self) -> Value<&[u8]> {
+        self.as_proof.as_ref().map(Vec::as_slice)
+    }
+}
+
+#[derive(Clone)]
+pub struct AccumulationConfig {
+    pub base_field_config: FpConfig<Fq, Fp>,
+    pub instance: Column<Instance>,
+}
+
+impl Circuit<Fq> for Accumulation {
+    type Config = AccumulationConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            svk: self.svk.clone(),
+            snarks: self.snarks.iter().map(SnarkWitness::without_witnesses).collect(),
+            prev_accumulator: self
+                .prev_accumulator
+                .as_ref()
+                .map(|repr| vec![Value::unknown(); repr.len()]),
+            instances: Vec::new(),
+            as_vk: self.as_vk.clone(),
+            as_proof: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fq>) -> Self::Config {
+        let base_field_config = FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[8],
+            &[1],
+            1,
+            K as usize - 1,
+            BITS,
+            LIMBS,
+            halo2_base::utils::modulus::<Fp>(),
+            0,
+            K as usize,
+        );
+
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        AccumulationConfig { base_field_config, instance }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fq>,
+    ) -> Result<(), plonk::Error> {
+        let mut layouter = layouter.namespace(|| "accumulation");
+        config.base_field_config.load_lookup_table(&mut layouter)?;
+
+        // Need to trick layouter to skip first pass in get shape mode
+        let mut first_pass = halo2_base::SKIP_FIRST_PASS;
+        let mut assigned_instances = None;
+        layouter.assign_region(
+            || "",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let ctx = Context::new(
+                    region,
+                    ContextParams {
+                        max_rows: config.base_field_config.range.gate.max_rows,
+                        num_context_ids: 1,
+                        fixed_columns: config.base_field_config.range.gate.constants.clone(),
+                    },
+                );
+
+                let loader =
+                    Halo2Loader::new(EccChip::construct(config.base_field_config.clone()), ctx);
+                let prev_accumulator = self.prev_accumulator.as_ref().map(|repr| {
+                    repr.iter().map(|value| loader.assign_scalar(*value)).collect_vec()
+                });
+                let IpaAccumulator { xi, u } = accumulate(
+                    &self.svk,
+                    &loader,
+                    &self.snarks,
+                    prev_accumulator.as_deref(),
+                    &self.as_vk,
+                    self.as_proof(),
+                );
+
+                let prev_accumulator = prev_accumulator
+                    .into_iter()
+                    .flatten()
+                    .map(|scalar| scalar.into_assigned())
+                    .collect_vec();
+                let xi = xi.into_iter().map(|xi| xi.into_assigned()).collect_vec();
+                let u = u.assigned();
+                // REQUIRED STEP
+                config.base_field_config.finalize(&mut loader.ctx_mut());
+
+                let instances: Vec<_> = prev_accumulator
+                    .iter()
+                    .chain(xi.iter())
+                    .chain(u.x.truncation.limbs.iter())
+                    .chain(u.y.truncation.limbs.iter())
+                    .map(|assigned| assigned.cell().clone())
+                    .collect();
+                assigned_instances = Some(instances);
+
+                Ok(())
+            },
+        )?;
+
+        let mut layouter = layouter.namespace(|| "expose");
+        for (i, cell) in assigned_instances.unwrap().into_iter().enumerate() {
+            layouter.constrain_instance(cell, config.instance, i)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_ipa_accumulate_two_snarks() {
+    let circuit = Accumulation::two_snark();
+    MockProver::run(K, &circuit, circuit.instances()).unwrap().assert_satisfied();
+}
+
+#[test]
+fn test_ipa_accumulate_snark_with_prev_accumulator() {
+    let circuit = Accumulation::one_snark_with_prev_accumulator();
+    MockProver::run(K, &circuit, circuit.instances()).unwrap().assert_satisfied();
+}
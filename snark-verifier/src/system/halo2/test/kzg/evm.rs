@@ -1,6 +1,10 @@
 use crate::{
     halo2_curves, halo2_proofs,
-    loader::native::NativeLoader,
+    loader::{
+        evm::{assemble_yul, encode_calldata, execute, EvmLoader, ExecutorBuilder, MemoryChunk},
+        halo2::test::Snark,
+        native::NativeLoader,
+    },
     pcs::kzg::{Bdfg21, Gwc19, Kzg, LimbsEncoding},
     system::halo2::{
         test::{
@@ -12,12 +16,22 @@ use crate::{
         },
         transcript::evm::{ChallengeEvm, EvmTranscript},
     },
-    verifier::Plonk,
+    util::Itertools,
+    verifier::{Plonk, PlonkVerifier},
+    Error, Protocol,
+};
+use ethereum_types::Address;
+use halo2_curves::bn256::{Bn256, Fq, Fr, G1Affine};
+use halo2_proofs::poly::{
+    commitment::ParamsProver,
+    kzg::{
+        commitment::ParamsKZG,
+        multiopen::{ProverGWC, ProverSHPLONK, VerifierGWC, VerifierSHPLONK},
+    },
 };
-use halo2_curves::bn256::{Bn256, G1Affine};
-use halo2_proofs::poly::kzg::multiopen::{ProverGWC, ProverSHPLONK, VerifierGWC, VerifierSHPLONK};
 use paste::paste;
 use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use std::rc::Rc;
 
 macro_rules! halo2_kzg_evm_verify {
     ($plonk_verifier:ty, $params:expr, $protocol:expr, $instances:expr, $proof:expr) => {{
@@ -25,7 +39,7 @@ macro_rules! halo2_kzg_evm_verify {
         use halo2_proofs::poly::commitment::ParamsProver;
         use std::rc::Rc;
         use $crate::{
-            loader::evm::{compile_yul, encode_calldata, execute, EvmLoader},
+            loader::evm::{assemble_yul, encode_calldata, execute, EvmLoader},
             system::halo2::{
                 test::kzg::{BITS, LIMBS},
                 transcript::evm::EvmTranscript,
@@ -34,7 +48,7 @@ macro_rules! halo2_kzg_evm_verify {
             verifier::PlonkVerifier,
         };
 
-        let loader = EvmLoader::new::<Fq, Fr>();
+        let loader = EvmLoader::new_with_gas_metering::<Fq, Fr>();
         let deployment_code = {
             let svk = $params.get_g()[0].into();
             let dk = ($params.g2(), $params.s_g2()).into();
@@ -42,16 +56,19 @@ macro_rules! halo2_kzg_evm_verify {
             let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader);
             let instances = transcript
                 .load_instances($instances.iter().map(|instances| instances.len()).collect_vec());
-            let proof = <$plonk_verifier>::read_proof(&svk, &protocol, &instances, &mut transcript);
-            <$plonk_verifier>::verify(&svk, &dk, &protocol, &instances, &proof);
+            let proof = <$plonk_verifier>::read_proof(&svk, &protocol, &instances, &mut transcript)
+                .unwrap();
+            <$plonk_verifier>::verify(&svk, &dk, &protocol, &instances, &proof).unwrap();
 
-            compile_yul(&loader.yul_code())
+            assemble_yul(&loader.yul_code()).unwrap()
         };
 
-        let (accept, total_cost, costs) =
+        let (accept, total_cost, report) =
             execute(deployment_code, encode_calldata($instances, &$proof));
 
-        loader.print_gas_metering(costs);
+        for section in report.sections {
+            println!("{}: {}", section.name, section.gas);
+        }
         println!("Total gas cost: {}", total_cost);
 
         assert!(accept);
@@ -101,6 +118,7 @@ macro_rules! test {
         test!(@ #[test], plonk, $name, $k, $config, $create_circuit, ProverGWC<_>, VerifierGWC<_>, Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<LIMBS, BITS>>);
     };
     ($(#[$attr:meta],)* $name:ident, $k:expr, $config:expr, $create_circuit:expr) => {
+        test!(@ #[test] $(,#[$attr])*, shplonk, $name, $k, $config, $create_circuit, ProverSHPLONK<_>, VerifierSHPLONK<_>, Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<LIMBS, BITS>>);
         test!(@ #[test] $(,#[$attr])*, plonk, $name, $k, $config, $create_circuit, ProverGWC<_>, VerifierGWC<_>, Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<LIMBS, BITS>>);
     };
 }
@@ -111,6 +129,13 @@ test!(
     halo2_kzg_config!(true, 1),
     StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
 );
+test!(
+    #[ignore = "requires halo2 prover with zk disabled"],
+    standard_plonk_rand,
+    9,
+    halo2_kzg_config!(false, 1),
+    StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+);
 /*
 test!(
     zk_main_gate_with_range_with_mock_kzg_accumulator,
@@ -135,3 +160,372 @@ test!(
     halo2_kzg_config!(true, 1, Some((0..4 * LIMBS).map(|idx| (0, idx)).collect())),
     kzg::halo2::Accumulation::two_snark_with_accumulator()
 );
+
+type GwcVerifier = Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<LIMBS, BITS>>;
+
+/// Returns params and snark of [`StandardPlonk`] proven by GWC19 with
+/// [`EvmTranscript`], for tests of EVM verifier generation.
+fn gwc_standard_plonk_snark() -> (ParamsKZG<Bn256>, Snark<G1Affine>) {
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverGWC<_>,
+        VerifierGWC<_>,
+        EvmTranscript<G1Affine, _, _, _>,
+        EvmTranscript<G1Affine, _, _, _>,
+        ChallengeEvm<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+    (params, snark)
+}
+
+/// Generates verifier of `snark` reading instances and proof from
+/// `transcript`, with `protocol` loaded by the same loader.
+fn load_gwc_verifier(
+    params: &ParamsKZG<Bn256>,
+    snark: &Snark<G1Affine>,
+    protocol: &Protocol<G1Affine, Rc<EvmLoader>>,
+    transcript: &mut EvmTranscript<G1Affine, Rc<EvmLoader>, usize, MemoryChunk>,
+) {
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
+    let instances = transcript.load_instances(snark.instances.iter().map(Vec::len).collect_vec());
+    let proof = GwcVerifier::read_proof(&svk, protocol, &instances, transcript).unwrap();
+    GwcVerifier::verify(&svk, &dk, protocol, &instances, &proof).unwrap();
+}
+
+/// Returns `loader` with verifier of `snark` generated.
+fn gen_gwc_verifier(
+    params: &ParamsKZG<Bn256>,
+    snark: &Snark<G1Affine>,
+    loader: Rc<EvmLoader>,
+) -> Rc<EvmLoader> {
+    let protocol = snark.protocol.loaded(&loader);
+    load_gwc_verifier(params, snark, &protocol, &mut EvmTranscript::new(&loader));
+    loader
+}
+
+/// Returns `proof` with its last byte flipped, which is still well-formed but
+/// fails the pairing check.
+fn invalid_proof(proof: &[u8]) -> Vec<u8> {
+    let mut proof = proof.to_vec();
+    *proof.last_mut().unwrap() ^= 1;
+    proof
+}
+
+#[test]
+fn test_malformed_proof() {
+    let (params, snark) = gwc_standard_plonk_snark();
+    let truncated = snark.proof[..snark.proof.len() - 1].to_vec();
+    let garbage = vec![0xff; snark.proof.len()];
+
+    let svk = params.get_g()[0].into();
+    for proof in [&truncated, &garbage] {
+        let mut transcript = EvmTranscript::<_, NativeLoader, _, _>::new(proof.as_slice());
+        assert!(matches!(
+            GwcVerifier::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript),
+            Err(Error::Transcript(..))
+        ));
+    }
+
+    let loader = gen_gwc_verifier(&params, &snark, EvmLoader::new::<Fq, Fr>());
+    let deployment_code = assemble_yul(&loader.yul_code()).unwrap();
+    for proof in [truncated, garbage] {
+        let (accept, _, _) =
+            execute(deployment_code.clone(), encode_calldata(&snark.instances, &proof));
+        assert!(!accept);
+    }
+}
+
+#[test]
+fn test_solidity_and_yul_verifiers_agree() {
+    use crate::loader::evm::{compile_solidity, compile_yul, encode_verify_calldata, U256};
+
+    let (params, snark) = gwc_standard_plonk_snark();
+    let loader = gen_gwc_verifier(&params, &snark, EvmLoader::new::<Fq, Fr>());
+    let yul_deployment_code = compile_yul(&loader.yul_code()).unwrap();
+    let solidity_deployment_code = compile_solidity(&loader.solidity_code().unwrap()).unwrap();
+
+    for (proof, valid) in [(snark.proof.clone(), true), (invalid_proof(&snark.proof), false)] {
+        let (yul_accept, _, _) =
+            execute(yul_deployment_code.clone(), encode_calldata(&snark.instances, &proof));
+        let (solidity_accept, _, _) = execute(
+            solidity_deployment_code.clone(),
+            encode_verify_calldata(&snark.instances, &proof),
+        );
+        assert_eq!(yul_accept, valid);
+        assert_eq!(solidity_accept, valid);
+    }
+
+    // Calldata not in the canonical encoding is rejected, even if it decodes
+    // to the same arguments
+    let calldata = encode_verify_calldata(&snark.instances, &snark.proof);
+    let mut trailing = calldata.clone();
+    trailing.extend([0; 0x20]);
+    // Proof moved a word further with its offset updated accordingly
+    let proof_len_ptr = 0x64 + snark.instances.iter().map(Vec::len).sum::<usize>() * 0x20;
+    let mut moved_proof = calldata;
+    U256::from(proof_len_ptr - 0x4 + 0x20).to_big_endian(&mut moved_proof[0x24..0x44]);
+    moved_proof.splice(proof_len_ptr..proof_len_ptr, [0; 0x20]);
+    for calldata in [trailing, moved_proof] {
+        let (accept, _, _) = execute(solidity_deployment_code.clone(), calldata);
+        assert!(!accept);
+    }
+
+    let abi_loader = gen_gwc_verifier(&params, &snark, EvmLoader::new_with_abi::<Fq, Fr>());
+    assert!(abi_loader.solidity_code().is_err());
+}
+
+#[test]
+fn test_assembled_yul_verifier() {
+    let (params, snark) = gwc_standard_plonk_snark();
+    let loader = gen_gwc_verifier(&params, &snark, EvmLoader::new::<Fq, Fr>());
+    let deployment_code = assemble_yul(&loader.yul_code()).unwrap();
+
+    for (proof, valid) in [(snark.proof.clone(), true), (invalid_proof(&snark.proof), false)] {
+        let (accept, _, _) =
+            execute(deployment_code.clone(), encode_calldata(&snark.instances, &proof));
+        assert_eq!(accept, valid);
+    }
+}
+
+#[test]
+fn test_split_yul_verifier() {
+    use crate::loader::evm::{decode_revert_reason, get_contract_address, BAD_CALLER};
+
+    let (params, snark) = gwc_standard_plonk_snark();
+    let loader = gen_gwc_verifier(&params, &snark, EvmLoader::new::<Fq, Fr>());
+
+    // Driver is deployed first, so parts know who to accept calls from
+    let caller = Address::from_low_u64_be(0xfe);
+    let driver = get_contract_address(caller, 0);
+    let max_runtime_size = loader.runtime_size().unwrap() / 3;
+    let parts = loader.split_yul_code(max_runtime_size, driver).unwrap();
+    assert!(parts.len() >= 3);
+
+    let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+    let addresses =
+        (1..).take(parts.len()).map(|nonce| get_contract_address(caller, nonce)).collect_vec();
+    let mut deploy = |deployment_code: Vec<u8>| {
+        evm.deploy(caller, deployment_code.into(), 0.into()).address.unwrap()
+    };
+    assert_eq!(deploy(assemble_yul(&loader.driver_yul_code(&addresses)).unwrap()), driver);
+    for (part, address) in parts.iter().zip(addresses.iter()) {
+        assert_eq!(deploy(assemble_yul(part).unwrap()), *address);
+    }
+
+    for (proof, valid) in [(snark.proof.clone(), true), (invalid_proof(&snark.proof), false)] {
+        let result = evm.call_raw(
+            caller,
+            driver,
+            encode_calldata(&snark.instances, &proof).into(),
+            0.into(),
+        );
+        assert_eq!(!result.reverted, valid);
+    }
+
+    // Calling the last part directly with forged state is rejected
+    let state_len = loader.ptr() + 0x20;
+    let calldata = [encode_calldata(&snark.instances, &snark.proof), vec![0; state_len]].concat();
+    let result = evm.call_raw(caller, *addresses.last().unwrap(), calldata.into(), 0.into());
+    assert!(result.reverted);
+    assert_eq!(decode_revert_reason(&result.result).as_deref(), Some(BAD_CALLER));
+}
+
+#[test]
+fn test_abi_yul_verifier() {
+    use crate::loader::evm::{
+        calldata_layout, decode_revert_reason, encode_verify_calldata, BAD_CALLDATA_ENCODING,
+        BAD_CALLDATA_LENGTH, PAIRING_FAILED,
+    };
+
+    let (params, snark) = gwc_standard_plonk_snark();
+    let loader = gen_gwc_verifier(&params, &snark, EvmLoader::new_with_abi::<Fq, Fr>());
+    let deployment_code = assemble_yul(&loader.yul_code()).unwrap();
+
+    let caller = Address::from_low_u64_be(0xfe);
+    let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+    let verifier = evm.deploy(caller, deployment_code.into(), 0.into()).address.unwrap();
+
+    let calldata = encode_verify_calldata(&snark.instances, &snark.proof);
+    let result = evm.call_raw(caller, verifier, calldata.clone().into(), 0.into());
+    assert!(!result.reverted);
+    assert_eq!(result.result.as_ref(), [[0; 31].as_slice(), &[1]].concat());
+
+    // Offsets in packed layout are shifted by head of ABI encoding and length
+    // of proof
+    let layout = calldata_layout::<_, Kzg<Bn256, Gwc19>>(&snark.protocol);
+    let invalid_evaluation = {
+        let mut calldata = calldata.clone();
+        calldata[0x84 + layout.evaluations[0] + 31] ^= 1;
+        calldata
+    };
+    let invalid_selector = {
+        let mut calldata = calldata.clone();
+        calldata[0] ^= 1;
+        calldata
+    };
+    let invalid_proof_len = {
+        let mut calldata = calldata.clone();
+        calldata[0x64 + layout.instances.concat().len() * 0x20 + 31] ^= 1;
+        calldata
+    };
+    for (calldata, reason) in [
+        (invalid_evaluation, PAIRING_FAILED),
+        (invalid_selector, BAD_CALLDATA_ENCODING),
+        (invalid_proof_len, BAD_CALLDATA_ENCODING),
+        (encode_calldata(&snark.instances, &snark.proof), BAD_CALLDATA_LENGTH),
+    ] {
+        let result = evm.call_raw(caller, verifier, calldata.into(), 0.into());
+        assert!(result.reverted);
+        assert_eq!(decode_revert_reason(&result.result).as_deref(), Some(reason));
+    }
+}
+
+#[test]
+fn test_verifier_with_vk_contract() {
+    use crate::loader::evm::{
+        encode_calldata_with_vk_address, encode_vk_data, vk_contract_codehash,
+        vk_contract_deployment_code,
+    };
+
+    let (params, snark) = gwc_standard_plonk_snark();
+    // Protocol with the same shape but different verifying key
+    let other_protocol = Protocol {
+        preprocessed: snark.protocol.preprocessed.iter().rev().cloned().collect(),
+        transcript_initial_state: snark
+            .protocol
+            .transcript_initial_state
+            .map(|state| state + Fr::from(1)),
+        ..snark.protocol.clone()
+    };
+
+    let yul_code = |protocol: &Protocol<G1Affine>| {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let (mut transcript, protocol) =
+            EvmTranscript::<_, Rc<EvmLoader>, _, _>::new_with_vk_contract(&loader, protocol);
+        load_gwc_verifier(&params, &snark, &protocol, &mut transcript);
+        loader.yul_code()
+    };
+    let generic_yul_code = yul_code(&snark.protocol);
+    assert_eq!(generic_yul_code, yul_code(&other_protocol));
+
+    let caller = Address::from_low_u64_be(0xfe);
+    let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+    let mut deploy = |deployment_code: Vec<u8>| {
+        evm.deploy(caller, deployment_code.into(), 0.into()).address.unwrap()
+    };
+    let verifier = deploy(assemble_yul(&generic_yul_code).unwrap());
+    let [vk_data, other_vk_data] = [&snark.protocol, &other_protocol].map(encode_vk_data);
+    let vk = deploy(vk_contract_deployment_code(&vk_data));
+    let vk_copy = deploy(vk_contract_deployment_code(&vk_data));
+    let other_vk = deploy(vk_contract_deployment_code(&other_vk_data));
+
+    for (vk_address, valid) in
+        [(vk, true), (vk_copy, true), (other_vk, false), (Address::zero(), false)]
+    {
+        let calldata = encode_calldata_with_vk_address(&snark.instances, &snark.proof, vk_address);
+        let result = evm.call_raw(caller, verifier, calldata.into(), 0.into());
+        assert_eq!(!result.reverted, valid);
+        // Any copy of the expected VK contract is bound by code hash
+        if valid {
+            assert_eq!(result.result.as_ref(), vk_contract_codehash(&vk_data).as_bytes());
+        }
+    }
+    assert_ne!(vk_contract_codehash(&vk_data), vk_contract_codehash(&other_vk_data));
+}
+
+#[test]
+fn test_estimate_gas() {
+    use crate::loader::evm::Hardfork;
+
+    let (params, snark) = gwc_standard_plonk_snark();
+    let loader = gen_gwc_verifier(&params, &snark, EvmLoader::new::<Fq, Fr>());
+    let deployment_code = assemble_yul(&loader.yul_code()).unwrap();
+    let (accept, gas_used, _) =
+        execute(deployment_code, encode_calldata(&snark.instances, &snark.proof));
+    assert!(accept);
+
+    // Executor follows London gas schedule
+    let estimate = loader.estimate_gas(Hardfork::London).unwrap().total();
+    let error = (estimate as f64 - gas_used as f64).abs() / gas_used as f64;
+    assert!(error < 0.05, "Estimated {estimate} gas but used {gas_used} gas");
+
+    let istanbul = loader.estimate_gas(Hardfork::Istanbul).unwrap();
+    assert!(istanbul.total() > estimate);
+}
+
+#[test]
+fn test_compressed_limbs_encoding() {
+    use crate::{
+        pcs::{
+            kzg::{CompressedLimbsEncoding, KzgAccumulator},
+            AccumulatorEncoding, Decider,
+        },
+        util::arithmetic::Field,
+    };
+
+    type Pcs = Kzg<Bn256, Gwc19>;
+    type Encoding = CompressedLimbsEncoding<LIMBS, BITS>;
+
+    let params = kzg::setup::<Bn256>(4);
+
+    let loader = EvmLoader::new::<Fq, Fr>();
+    {
+        let dk = (params.g2(), params.s_g2()).into();
+        let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+        let limbs = transcript.load_instances(vec![2 * (LIMBS + 1)]).pop().unwrap();
+        let accumulator = <Encoding as AccumulatorEncoding<G1Affine, _, Pcs>>::from_repr(
+            &limbs.iter().collect_vec(),
+        )
+        .unwrap();
+        Pcs::decide(&dk, accumulator);
+    }
+    let deployment_code = assemble_yul(&loader.yul_code()).unwrap();
+
+    // Typed code of decompression is translated for untyped inline assembly
+    let solidity_code = loader.solidity_code().unwrap();
+    assert!(!solidity_code.contains(":bool"));
+    assert!(!solidity_code.contains("not("));
+
+    // `(s * g1, g1)` and its negation are valid accumulators, which cover both
+    // parities of y coordinate
+    let [g1, s_g1] = [params.get_g()[0], params.get_g()[1]];
+    for (lhs, rhs) in [(s_g1, g1), (-s_g1, -g1)] {
+        let repr = Encoding::to_repr(&KzgAccumulator::<G1Affine, NativeLoader>::new(lhs, rhs));
+        assert_eq!(repr.len(), 2 * (LIMBS + 1));
+
+        let accumulator =
+            <Encoding as AccumulatorEncoding<G1Affine, NativeLoader, Pcs>>::from_repr(
+                &repr.iter().collect_vec(),
+            )
+            .unwrap();
+        assert_eq!((accumulator.lhs, accumulator.rhs), (lhs, rhs));
+
+        let mut flipped = repr.clone();
+        flipped[LIMBS] = Fr::ONE - flipped[LIMBS];
+        let mut not_bit = repr.clone();
+        not_bit[LIMBS] = Fr::from(2);
+        assert!(<Encoding as AccumulatorEncoding<G1Affine, NativeLoader, Pcs>>::from_repr(
+            &not_bit.iter().collect_vec()
+        )
+        .is_err());
+
+        for (repr, valid) in [(repr, true), (flipped, false), (not_bit, false)] {
+            let (accept, _, _) = execute(deployment_code.clone(), encode_calldata(&[repr], &[]));
+            assert_eq!(accept, valid);
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "No accumulator is returned since pairing is never performed")]
+fn test_deferred_pairing_without_pairing() {
+    EvmLoader::new_with_deferred_pairing::<Fq, Fr>().yul_code();
+}
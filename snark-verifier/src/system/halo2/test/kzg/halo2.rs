@@ -2,6 +2,7 @@ use crate::{
     halo2_curves::bn256::{Bn256, Fq, Fr, G1Affine},
     halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
         plonk::{
             create_proof, verify_proof, Circuit, Column, ConstraintSystem, Instance, {self},
         },
@@ -24,10 +25,10 @@ use crate::{
     },
     pcs::{
         kzg::{
-            Bdfg21, Kzg, KzgAccumulator, KzgAs, KzgAsProvingKey, KzgAsVerifyingKey,
-            KzgSuccinctVerifyingKey, LimbsEncoding,
+            Bdfg21, CompressedLimbsEncoding, Kzg, KzgAccumulator, KzgAs, KzgAsProvingKey,
+            KzgAsVerifyingKey, KzgSuccinctVerifyingKey, LimbsEncoding,
         },
-        AccumulationScheme, AccumulationSchemeProver,
+        AccumulationScheme, AccumulationSchemeProver, AccumulatorEncoding,
     },
     system::halo2::{
         test::{
@@ -39,7 +40,10 @@ use crate::{
         },
         transcript::halo2::{ChallengeScalar, PoseidonTranscript as GenericPoseidonTranscript},
     },
-    util::{arithmetic::fe_to_limbs, Itertools},
+    util::{
+        arithmetic::{fe_to_limbs, Curve, CurveAffine, Field, PrimeCurveAffine},
+        Itertools,
+    },
     verifier::{
         PlonkVerifier, {self},
     },
@@ -152,8 +156,8 @@ pub fn accumulate<'a>(
             let instances = assign_instances(&snark.instances);
             let mut transcript =
                 PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, snark.proof());
-            let proof = Plonk::read_proof(svk, &protocol, &instances, &mut transcript);
-            Plonk::succinct_verify(svk, &protocol, &instances, &proof)
+            let proof = Plonk::read_proof(svk, &protocol, &instances, &mut transcript).unwrap();
+            Plonk::succinct_verify(svk, &protocol, &instances, &proof).unwrap()
         })
         .collect_vec();
 
@@ -194,8 +198,9 @@ impl Accumulation {
                 let mut transcript =
                     PoseidonTranscript::<NativeLoader, _>::new(snark.proof.as_slice());
                 let proof =
-                    Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript);
-                Plonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof)
+                    Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript)
+                        .unwrap();
+                Plonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof).unwrap()
             })
             .collect_vec();
 
@@ -227,11 +232,16 @@ impl Accumulation {
     }
 
     pub fn two_snark() -> Self {
+        Self::two_snark_with_zk(true)
+    }
+
+    /// Returns [`Accumulation`] of two snarks created with `zk`.
+    pub fn two_snark_with_zk(zk: bool) -> Self {
         let (params, snark1) = {
             const K: u32 = 9;
             let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
                 K,
-                halo2_kzg_config!(true, 1),
+                halo2_kzg_config!(zk, 1),
                 StandardPlonk::<_>::rand(ChaCha20Rng::from_seed(Default::default()))
             );
             let snark = halo2_kzg_create_snark!(
@@ -251,7 +261,7 @@ impl Accumulation {
             const K: u32 = 9;
             let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
                 K,
-                halo2_kzg_config!(true, 1),
+                halo2_kzg_config!(zk, 1),
                 StandardPlonk::<_>::rand(ChaCha20Rng::from_seed(Default::default()))
             );
             halo2_kzg_create_snark!(
@@ -462,6 +472,151 @@ test!(
     halo2_kzg_config!(true, 1, Some(Accumulation::accumulator_indices())),
     Accumulation::two_snark_with_accumulator()
 );
+test!(
+    #[ignore = "requires halo2 prover with zk disabled"],
+    zk_aggregate_two_non_zk_snarks,
+    21,
+    halo2_kzg_config!(true, 1, Some(Accumulation::accumulator_indices())),
+    Accumulation::two_snark_with_zk(false)
+);
+
+/// Degree of [`DecompressAccumulator`].
+const DECOMPRESS_K: u32 = 13;
+
+/// Circuit decoding an accumulator from witnesses encoded by
+/// [`CompressedLimbsEncoding`], which exposes limbs of both coordinates of
+/// the decoded points as instances.
+struct DecompressAccumulator {
+    compressed: Vec<Value<Fr>>,
+}
+
+impl Circuit<Fr> for DecompressAccumulator {
+    type Config = Halo2VerifierCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self { compressed: vec![Value::unknown(); self.compressed.len()] }
+    }
+
+    fn configure(meta: &mut plonk::ConstraintSystem<Fr>) -> Self::Config {
+        Halo2VerifierCircuitConfig::configure(
+            meta,
+            Halo2VerifierCircuitConfigParams {
+                strategy: halo2_ecc::fields::fp::FpStrategy::Simple,
+                degree: DECOMPRESS_K,
+                num_advice: 2,
+                num_lookup_advice: 1,
+                num_fixed: 1,
+                lookup_bits: DECOMPRESS_K as usize - 1,
+                limb_bits: BITS,
+                num_limbs: LIMBS,
+            },
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), plonk::Error> {
+        config.base_field_config.load_lookup_table(&mut layouter)?;
+
+        let mut first_pass = halo2_base::SKIP_FIRST_PASS;
+        let mut assigned_instances = None;
+        layouter.assign_region(
+            || "",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let ctx = Context::new(
+                    region,
+                    ContextParams {
+                        max_rows: config.base_field_config.range.gate.max_rows,
+                        num_context_ids: 1,
+                        fixed_columns: config.base_field_config.range.gate.constants.clone(),
+                    },
+                );
+
+                let loader =
+                    Halo2Loader::new(EccChip::construct(config.base_field_config.clone()), ctx);
+                let compressed =
+                    self.compressed.iter().map(|limb| loader.assign_scalar(*limb)).collect_vec();
+                let KzgAccumulator { lhs, rhs } =
+                    <CompressedLimbsEncoding<LIMBS, BITS> as AccumulatorEncoding<
+                        G1Affine,
+                        Rc<Halo2Loader>,
+                        Pcs,
+                    >>::from_repr(&compressed.iter().collect_vec())
+                    .unwrap();
+
+                let instances = [lhs, rhs]
+                    .iter()
+                    .flat_map(|ec_point| {
+                        let assigned = ec_point.assigned();
+                        assigned
+                            .x
+                            .truncation
+                            .limbs
+                            .iter()
+                            .chain(assigned.y.truncation.limbs.iter())
+                            .map(|assigned| assigned.cell().clone())
+                            .collect_vec()
+                    })
+                    .collect_vec();
+                config.base_field_config.finalize(&mut loader.ctx_mut());
+                assigned_instances = Some(instances);
+
+                Ok(())
+            },
+        )?;
+        for (i, cell) in assigned_instances.unwrap().into_iter().enumerate() {
+            layouter.constrain_instance(cell, config.instance, i)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_compressed_limbs_encoding_instructions() {
+    let limbs = |ec_points: [G1Affine; 2]| {
+        ec_points
+            .iter()
+            .flat_map(|ec_point| {
+                let coordinates = ec_point.coordinates().unwrap();
+                [*coordinates.x(), *coordinates.y()]
+            })
+            .flat_map(fe_to_limbs::<_, Fr, LIMBS, BITS>)
+            .collect_vec()
+    };
+    let verify = |compressed: &[Fr], ec_points: [G1Affine; 2]| {
+        let circuit = DecompressAccumulator {
+            compressed: compressed.iter().cloned().map(Value::known).collect(),
+        };
+        MockProver::run(DECOMPRESS_K, &circuit, vec![limbs(ec_points)]).unwrap().verify()
+    };
+
+    let g1 = G1Affine::generator();
+    let ec_points = [g1, (g1 * Fr::from(3)).to_affine()];
+    let compressed = CompressedLimbsEncoding::<LIMBS, BITS>::to_repr(&KzgAccumulator::new(
+        ec_points[0],
+        ec_points[1],
+    ));
+    assert!(verify(&compressed, ec_points).is_ok());
+
+    // Flipped parity decodes to the negated point
+    let mut flipped = compressed.clone();
+    flipped[LIMBS] = Fr::ONE - flipped[LIMBS];
+    assert!(verify(&flipped, ec_points).is_err());
+    assert!(verify(&flipped, [-ec_points[0], ec_points[1]]).is_ok());
+
+    // Parity which is not a bit is rejected, even if its lowest bit matches
+    let mut non_bit = compressed;
+    non_bit[LIMBS] += Fr::from(2);
+    assert!(verify(&non_bit, ec_points).is_err());
+}
 
 pub trait TargetCircuit: Circuit<Fr> {
     const TARGET_CIRCUIT_K: u32;
@@ -10,17 +10,19 @@ use crate::{
             halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_native_verify, halo2_kzg_prepare,
             BITS, LIMBS,
         },
-        StandardPlonk,
+        Lookup, Shuffle, StandardPlonk,
     },
-    verifier::Plonk,
+    verifier::{Plonk, PlonkVerifier},
+    Error, Protocol,
 };
 use paste::paste;
 use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use std::collections::BTreeSet;
 
 macro_rules! test {
-    (@ $prefix:ident, $name:ident, $k:expr, $config:expr, $create_cirucit:expr, $prover:ty, $verifier:ty, $plonk_verifier:ty) => {
+    (@ $(#[$attr:meta],)* $prefix:ident, $name:ident, $k:expr, $config:expr, $create_cirucit:expr, $prover:ty, $verifier:ty, $plonk_verifier:ty) => {
         paste! {
-            #[test]
+            $(#[$attr])*
             fn [<test_ $prefix _ $name>]() {
                 let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
                     $k,
@@ -48,9 +50,9 @@ macro_rules! test {
             }
         }
     };
-    ($name:ident, $k:expr, $config:expr, $create_cirucit:expr) => {
-        test!(@ shplonk, $name, $k, $config, $create_cirucit, ProverSHPLONK<_>, VerifierSHPLONK<_>, Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<LIMBS, BITS>>);
-        test!(@ plonk, $name, $k, $config, $create_cirucit, ProverGWC<_>, VerifierGWC<_>, Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<LIMBS, BITS>>);
+    ($(#[$attr:meta],)* $name:ident, $k:expr, $config:expr, $create_cirucit:expr) => {
+        test!(@ #[test] $(,#[$attr])*, shplonk, $name, $k, $config, $create_cirucit, ProverSHPLONK<_>, VerifierSHPLONK<_>, Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<LIMBS, BITS>>);
+        test!(@ #[test] $(,#[$attr])*, plonk, $name, $k, $config, $create_cirucit, ProverGWC<_>, VerifierGWC<_>, Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<LIMBS, BITS>>);
     }
 }
 
@@ -60,6 +62,254 @@ test!(
     halo2_kzg_config!(true, 2),
     StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
 );
+test!(
+    #[ignore = "requires halo2 prover with zk disabled"],
+    standard_plonk_rand,
+    9,
+    halo2_kzg_config!(false, 2),
+    StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+);
+test!(
+    zk_shuffle_rand,
+    9,
+    halo2_kzg_config!(true, 2),
+    Shuffle::rand(ChaCha20Rng::from_seed(Default::default()))
+);
+
+#[test]
+fn test_compile_non_zk_standard_plonk() {
+    let compile = |zk| {
+        let (_, pk, protocol, _) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(zk, 1),
+            StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        (pk, protocol)
+    };
+    let ((pk, zk), (_, non_zk)) = (compile(true), compile(false));
+
+    // No random polynomial
+    assert_eq!(non_zk.num_witness.iter().sum::<usize>() + 1, zk.num_witness.iter().sum::<usize>());
+    // No `l_last` nor `l_blind`, only `l_0` is used
+    assert_eq!(non_zk.quotient.numerator.used_langrange(), BTreeSet::from([0]));
+    // Quotient is split as the prover does, which is the same in both modes,
+    // and `StandardPlonk` sets minimum degree to 4
+    assert_eq!(non_zk.quotient.num_chunk(), pk.get_vk().get_domain().get_quotient_poly_degree());
+    assert_eq!(non_zk.quotient.num_chunk(), zk.quotient.num_chunk());
+    assert_eq!(non_zk.quotient.num_chunk(), 3);
+}
+
+#[test]
+fn test_protocol_digest() {
+    use crate::system::halo2::protocol_digest;
+
+    let compile = |zk| {
+        let (_, _, protocol, _) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(zk, 1),
+            StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        protocol
+    };
+    let protocol = compile(true);
+
+    // Computed directly from verifying key
+    let (params, pk, _, _) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    assert_eq!(
+        protocol_digest(&params, pk.get_vk(), halo2_kzg_config!(true, 1)).unwrap(),
+        protocol.digest()
+    );
+    assert_ne!(
+        protocol_digest(&params, pk.get_vk(), halo2_kzg_config!(false, 1)).unwrap(),
+        protocol.digest()
+    );
+
+    // Stable across serialization
+    let deserialized: Protocol<G1Affine> =
+        serde_json::from_str(&serde_json::to_string(&protocol).unwrap()).unwrap();
+    assert_eq!(protocol.digest(), deserialized.digest());
+    assert_eq!(protocol.digest(), compile(true).digest());
+    assert_ne!(protocol.digest(), compile(false).digest());
+}
+
+#[test]
+fn test_protocol_diff() {
+    let compile = |zk| {
+        let (_, _, protocol, _) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(zk, 1),
+            StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        protocol
+    };
+    let (zk, non_zk) = (compile(true), compile(false));
+
+    assert!(zk.diff(&compile(true)).is_empty());
+    let diff = zk.diff(&non_zk);
+    assert!(diff.num_witness.is_some());
+    assert!(!diff.removed_queries.is_empty());
+    assert!(diff.added_queries.is_empty());
+}
+
+#[test]
+fn test_malformed_proof() {
+    use crate::halo2_proofs::poly::commitment::ParamsProver;
+
+    type Verifier = Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<LIMBS, BITS>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        Blake2bWrite<_, _, _>,
+        Blake2bRead<_, _, _>,
+        Challenge255<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
+    let verify = |proof: &[u8]| {
+        let mut transcript = Blake2bRead::<_, G1Affine, _>::init(proof);
+        let proof = Verifier::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript)?;
+        Verifier::verify(&svk, &dk, &snark.protocol, &snark.instances, &proof)
+    };
+
+    assert!(verify(&snark.proof).unwrap());
+    let truncated = &snark.proof[..snark.proof.len() - 1];
+    let garbage = vec![0xff; snark.proof.len()];
+    for proof in [truncated, garbage.as_slice()] {
+        assert!(matches!(verify(proof), Err(Error::Transcript(..))));
+    }
+}
+
+#[test]
+fn test_compile_unsupported() {
+    use crate::{
+        halo2_curves::bn256::Fr,
+        halo2_proofs::{
+            plonk::{keygen_vk, ConstraintSystem},
+            poly::Rotation,
+        },
+        system::halo2::{check_supported, compile, test::kzg::setup, Config, LookupArgument},
+    };
+
+    // Lookups into the same table are batched into one argument with multiple
+    // input sets, which only the log-derivative lookup argument supports
+    let params = setup::<Bn256>(5);
+    let vk = keygen_vk(&params, &Lookup::<Fr, 2>::default()).unwrap();
+    assert!(vk.cs().lookups().iter().any(|lookup| lookup.input_expressions().len() > 1));
+    let config = |lookup_argument| {
+        Config::kzg().set_lookup_argument(lookup_argument).with_num_instance(vec![1])
+    };
+    assert!(compile(&params, &vk, config(LookupArgument::LogDerivative)).is_ok());
+    assert!(matches!(
+        compile(&params, &vk, config(LookupArgument::Classic)),
+        Err(Error::UnsupportedFeature(_))
+    ));
+
+    // Selectors are compressed into fixed columns by keygen, so they only
+    // remain in a constraint system that hasn't been through it
+    let mut gate = ConstraintSystem::<Fr>::default();
+    let (q, a) = (gate.selector(), gate.advice_column());
+    gate.create_gate("q·a = 0", |meta| {
+        vec![meta.query_selector(q) * meta.query_advice(a, Rotation::cur())]
+    });
+    let mut shuffle = ConstraintSystem::<Fr>::default();
+    let (q, a, b) = (shuffle.complex_selector(), shuffle.advice_column(), shuffle.advice_column());
+    shuffle.shuffle("q·a ~ q·b", |meta| {
+        let q = meta.query_selector(q);
+        let [a, b] = [a, b].map(|column| meta.query_advice(column, Rotation::cur()));
+        vec![(q.clone() * a, q * b)]
+    });
+    for cs in [gate, shuffle] {
+        assert!(matches!(
+            check_supported(&cs, LookupArgument::LogDerivative),
+            Err(Error::UnsupportedFeature(_))
+        ));
+    }
+}
+
+#[test]
+fn test_compile_classic_lookup() {
+    use crate::{
+        halo2_curves::bn256::Fr,
+        halo2_proofs::plonk::keygen_vk,
+        system::halo2::{compile, test::kzg::setup, Config, LookupArgument},
+        util::protocol::{Expression, Query},
+    };
+
+    let params = setup::<Bn256>(5);
+    let vk = keygen_vk(&params, &Lookup::<Fr, 1>::default()).unwrap();
+    let compile = |zk, lookup_argument| {
+        let config = Config::kzg()
+            .set_zk(zk)
+            .set_lookup_argument(lookup_argument)
+            .with_num_instance(vec![1]);
+        compile(&params, &vk, config).unwrap()
+    };
+    let num_constraint = |protocol: &Protocol<G1Affine>| match &protocol.quotient.numerator {
+        Expression::DistributePowers(constraints, _) => constraints.len(),
+        _ => unreachable!(),
+    };
+
+    // Polynomials are ordered as table and `q_instance` fixed columns, instance,
+    // advice, permuted input and table, grand product `z`, random and quotient.
+    let classic = compile(true, LookupArgument::Classic);
+    assert_eq!(classic.num_witness, vec![1, 2, 2]);
+    assert_eq!(classic.num_challenge, vec![1, 2, 1]);
+    let (permuted_input, permuted_table, z) = (4, 5, 6);
+    // Queries and evaluations follow `halo2_proofs::plonk::lookup::verifier`
+    assert_eq!(
+        classic.queries[1..6],
+        [
+            Query::new(z, 0),
+            Query::new(permuted_input, 0),
+            Query::new(permuted_table, 0),
+            Query::new(permuted_input, -1),
+            Query::new(z, 1),
+        ]
+    );
+    assert_eq!(
+        classic.evaluations[4..],
+        [
+            Query::new(z, 0),
+            Query::new(z, 1),
+            Query::new(permuted_input, 0),
+            Query::new(permuted_input, -1),
+            Query::new(permuted_table, 0),
+        ]
+    );
+    // Besides the gate, `l_0·(1 - z)`, `l_last·(z^2 - z)`, grand product,
+    // `l_0·(a' - s')` and `(a' - s')·(a' - a'(ω^-1))`
+    assert_eq!(num_constraint(&classic), 1 + 5);
+    // Numerator fits in the quotient chunks of verifying key
+    assert_eq!(classic.quotient.num_chunk(), vk.get_domain().get_quotient_poly_degree());
+    assert!(classic.quotient.numerator.degree() <= classic.quotient.num_chunk() + 1);
+
+    // Without zk, there is no random polynomial nor `l_last·(z^2 - z)`
+    let non_zk = compile(false, LookupArgument::Classic);
+    assert_eq!(non_zk.num_witness, vec![1, 2, 1]);
+    assert_eq!(num_constraint(&non_zk), 1 + 4);
+
+    // Log-derivative lookup commits to `m` and `phi` instead with 3 constraints
+    let log_derivative = compile(true, LookupArgument::LogDerivative);
+    assert_eq!(log_derivative.num_witness, vec![1, 1, 2]);
+    assert_eq!(num_constraint(&log_derivative), 1 + 3);
+    assert_eq!(log_derivative.quotient.num_chunk(), classic.quotient.num_chunk());
+}
+
 /*
 test!(
     zk_main_gate_with_range_with_mock_kzg_accumulator,
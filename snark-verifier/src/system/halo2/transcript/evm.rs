@@ -14,7 +14,7 @@ use crate::{
         transcript::{Transcript, TranscriptRead},
         Itertools,
     },
-    Error,
+    Error, Protocol,
 };
 use ethereum_types::U256;
 use halo2_proofs::transcript::EncodedChallenge;
@@ -50,13 +50,88 @@ where
         Self { loader: loader.clone(), stream: 0, buf, _marker: PhantomData }
     }
 
-    /// Load `num_instance` instances from calldata to memory.
+    /// Initialize [`EvmTranscript`] given [`Rc<EvmLoader>`] with `protocol`
+    /// loaded, whose preprocessed commitments and `transcript_initial_state`
+    /// are copied from VK contract at runtime instead of being inlined, so the
+    /// verifier is shared by circuits with the same constraint system.
+    ///
+    /// The VK contract should be deployed with [`encode_vk_data`] of
+    /// `protocol`, and its address is expected to be the last word of calldata
+    /// (see [`encode_calldata_with_vk_address`]). This must be called before
+    /// anything else is allocated.
+    ///
+    /// Since the verifier trusts whichever VK contract it's given, it returns
+    /// the code hash of the VK contract as a word, which the caller must
+    /// compare with [`vk_contract_codehash`] of the expected verifying key,
+    /// otherwise a proof of any circuit with the same constraint system is
+    /// accepted.
+    ///
+    /// [`encode_vk_data`]: crate::loader::evm::encode_vk_data
+    /// [`vk_contract_codehash`]: crate::loader::evm::vk_contract_codehash
+    /// [`encode_calldata_with_vk_address`]: crate::loader::evm::encode_calldata_with_vk_address
+    pub fn new_with_vk_contract(
+        loader: &Rc<EvmLoader>,
+        protocol: &Protocol<C>,
+    ) -> (Self, Protocol<C, Rc<EvmLoader>>) {
+        let num_preprocessed = protocol.preprocessed.len();
+        let len = num_preprocessed * 0x40
+            + protocol.transcript_initial_state.as_ref().map(|_| 0x20).unwrap_or_default();
+        let ptr = loader.allocate(len);
+        assert_eq!(ptr, 0);
+        loader.extcodecopy_vk(ptr, len);
+
+        let mut loaded = protocol.loaded(loader);
+        loaded.preprocessed = (0..num_preprocessed)
+            .map(|idx| loader.ec_point(Value::Memory(ptr + idx * 0x40)))
+            .collect();
+        // Pre-allocated u256 for `transcript_initial_state` is the last word
+        // of VK data if there is one
+        let buf_ptr = if protocol.transcript_initial_state.is_some() {
+            let state_ptr = ptr + num_preprocessed * 0x40;
+            loaded.transcript_initial_state = Some(loader.scalar(Value::Memory(state_ptr)));
+            state_ptr
+        } else {
+            loader.allocate(0x20)
+        };
+        let mut buf = MemoryChunk::new(buf_ptr);
+        buf.extend(0x20);
+
+        (Self { loader: loader.clone(), stream: 0, buf, _marker: PhantomData }, loaded)
+    }
+
+    /// Initialize [`EvmTranscript`] given [`Rc<EvmLoader>`] with `protocol`
+    /// loaded, which reads calldata from `offset` and can be initialized after
+    /// other allocations, so proofs packed one after another in calldata (see
+    /// [`encode_batch_calldata`]) can be verified in one call. The constant
+    /// `transcript_initial_state` is copied into the pre-allocated u256.
+    ///
+    /// [`encode_batch_calldata`]: crate::loader::evm::encode_batch_calldata
+    pub fn new_at(
+        loader: &Rc<EvmLoader>,
+        protocol: &Protocol<C>,
+        offset: usize,
+    ) -> (Self, Protocol<C, Rc<EvmLoader>>) {
+        let ptr = loader.allocate(0x20);
+        let mut buf = MemoryChunk::new(ptr);
+        buf.extend(0x20);
+
+        let mut loaded = protocol.loaded(loader);
+        if let Some(transcript_initial_state) = loaded.transcript_initial_state.as_ref() {
+            loader.copy_scalar(transcript_initial_state, ptr);
+            loaded.transcript_initial_state = Some(loader.scalar(Value::Memory(ptr)));
+        }
+
+        (Self { loader: loader.clone(), stream: offset, buf, _marker: PhantomData }, loaded)
+    }
+
+    /// Load `num_instance` instances from calldata to memory, where the
+    /// verification reverts if any of them is not less than scalar modulus.
     pub fn load_instances(&mut self, num_instance: Vec<usize>) -> Vec<Vec<Scalar>> {
         num_instance
             .into_iter()
             .map(|len| {
                 iter::repeat_with(|| {
-                    let scalar = self.loader.calldataload_scalar(self.stream);
+                    let scalar = self.loader.calldataload_instance(self.stream);
                     self.stream += 0x20;
                     scalar
                 })
@@ -120,6 +195,9 @@ where
             Value::Constant(_) if self.buf.ptr() == 0 => {
                 self.loader.copy_scalar(scalar, self.buf.ptr());
             }
+            // `transcript_initial_state` loaded from VK contract is already
+            // in place
+            Value::Memory(ptr) if self.buf.ptr() == ptr && self.buf.len() == 0x20 => {}
             Value::Memory(ptr) => {
                 assert_eq!(self.buf.end(), ptr);
                 self.buf.extend(0x20);
@@ -4,7 +4,5 @@ mod poseidon;
 
 pub use crate::util::hash::poseidon::Poseidon;
 
-#[cfg(feature = "loader_evm")]
 pub use sha3::Digest;
-#[cfg(feature = "loader_evm")]
 pub use sha3::Keccak256;
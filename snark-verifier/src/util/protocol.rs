@@ -1,7 +1,8 @@
 use crate::{
     loader::{LoadedScalar, Loader},
     util::{
-        arithmetic::{CurveAffine, Domain, Field, Fraction, Rotation},
+        arithmetic::{CurveAffine, Domain, Field, Fraction, GroupEncoding, PrimeField, Rotation},
+        hash::{Digest, Keccak256},
         Itertools,
     },
     Protocol,
@@ -19,6 +20,10 @@ use std::{
     ops::{Add, Mul, Neg, Sub},
 };
 
+mod inspect;
+
+pub use inspect::ProtocolDiff;
+
 impl<C> Protocol<C>
 where
     C: CurveAffine,
@@ -48,6 +53,138 @@ where
             accumulator_indices: self.accumulator_indices.clone(),
         }
     }
+
+    /// Returns Keccak256 digest of everything in [`Protocol`] that affects
+    /// verification, which is stable across serialization and can be used to
+    /// check if a cached [`Protocol`] still matches the circuit.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = DigestHasher::default();
+
+        hasher.usize(self.domain.k);
+        hasher.scalar(&self.domain.gen);
+        hasher.slice(&self.preprocessed, DigestHasher::point);
+        hasher.slice(&self.num_instance, |hasher, num| hasher.usize(*num));
+        hasher.slice(&self.num_witness, |hasher, num| hasher.usize(*num));
+        hasher.slice(&self.num_challenge, |hasher, num| hasher.usize(*num));
+        hasher.slice(&self.evaluations, DigestHasher::query);
+        hasher.slice(&self.queries, DigestHasher::query);
+        hasher.usize(self.quotient.num_chunk());
+        hasher.usize(self.quotient.chunk_degree);
+        hasher.expression(&self.quotient.numerator);
+        hasher.option(&self.transcript_initial_state, DigestHasher::scalar);
+        hasher.option(&self.instance_committing_key, |hasher, key| {
+            hasher.slice(&key.bases, DigestHasher::point);
+            hasher.option(&key.constant, DigestHasher::point);
+        });
+        hasher.option(&self.linearization, |hasher, linearization| {
+            hasher.usize(match linearization {
+                LinearizationStrategy::WithoutConstant => 0,
+                LinearizationStrategy::MinusVanishingTimesQuotient => 1,
+            })
+        });
+        hasher.slice(&self.accumulator_indices, |hasher, indices| {
+            hasher.slice(indices, |hasher, (poly, row)| {
+                hasher.usize(*poly);
+                hasher.usize(*row);
+            })
+        });
+
+        hasher.0.finalize().into()
+    }
+}
+
+#[derive(Default)]
+struct DigestHasher(Keccak256);
+
+impl DigestHasher {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn usize(&mut self, value: usize) {
+        self.bytes(&(value as u64).to_le_bytes());
+    }
+
+    fn scalar<F: PrimeField>(&mut self, scalar: &F) {
+        self.bytes(scalar.to_repr().as_ref());
+    }
+
+    fn point<C: CurveAffine>(&mut self, point: &C) {
+        self.bytes(point.to_bytes().as_ref());
+    }
+
+    fn query(&mut self, query: &Query) {
+        self.usize(query.poly);
+        self.bytes(&query.rotation.0.to_le_bytes());
+    }
+
+    fn option<T>(&mut self, value: &Option<T>, f: impl FnOnce(&mut Self, &T)) {
+        match value {
+            Some(value) => {
+                self.bytes(&[1]);
+                f(self, value);
+            }
+            None => self.bytes(&[0]),
+        }
+    }
+
+    fn slice<T>(&mut self, values: &[T], mut f: impl FnMut(&mut Self, &T)) {
+        self.usize(values.len());
+        for value in values {
+            f(self, value);
+        }
+    }
+
+    fn expression<F: PrimeField>(&mut self, expression: &Expression<F>) {
+        match expression {
+            Expression::Constant(scalar) => {
+                self.bytes(&[0]);
+                self.scalar(scalar);
+            }
+            Expression::CommonPolynomial(poly) => {
+                self.bytes(&[1]);
+                match poly {
+                    CommonPolynomial::Identity => self.bytes(&[0]),
+                    CommonPolynomial::Lagrange(i) => {
+                        self.bytes(&[1]);
+                        self.bytes(&i.to_le_bytes());
+                    }
+                }
+            }
+            Expression::Polynomial(query) => {
+                self.bytes(&[2]);
+                self.query(query);
+            }
+            Expression::Challenge(index) => {
+                self.bytes(&[3]);
+                self.usize(*index);
+            }
+            Expression::Negated(a) => {
+                self.bytes(&[4]);
+                self.expression(a);
+            }
+            Expression::Sum(a, b) => {
+                self.bytes(&[5]);
+                self.expression(a);
+                self.expression(b);
+            }
+            Expression::Product(a, b) => {
+                self.bytes(&[6]);
+                self.expression(a);
+                self.expression(b);
+            }
+            Expression::Scaled(a, scalar) => {
+                self.bytes(&[7]);
+                self.expression(a);
+                self.scalar(scalar);
+            }
+            Expression::DistributePowers(exprs, scalar) => {
+                self.bytes(&[8]);
+                self.slice(exprs, Self::expression);
+                self.expression(scalar);
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -0,0 +1,216 @@
+//! Human-readable report of [`Protocol`] and structural diff between two of
+//! them, which helps to track down why verification fails after a circuit
+//! change.
+
+use crate::{
+    util::{
+        arithmetic::CurveAffine,
+        protocol::{CommonPolynomial, Expression, Query},
+        Itertools,
+    },
+    Protocol,
+};
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Debug, Display},
+};
+
+impl Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rotation.0 {
+            0 => write!(f, "p_{}", self.poly),
+            rotation => write!(f, "p_{}[{rotation}]", self.poly),
+        }
+    }
+}
+
+impl<F: Clone + Debug> Display for Expression<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Constant(constant) => write!(f, "{constant:?}"),
+            Expression::CommonPolynomial(CommonPolynomial::Identity) => write!(f, "identity"),
+            Expression::CommonPolynomial(CommonPolynomial::Lagrange(i)) => write!(f, "l_{i}"),
+            Expression::Polynomial(query) => write!(f, "{query}"),
+            Expression::Challenge(index) => write!(f, "c_{index}"),
+            Expression::Negated(a) => write!(f, "-({a})"),
+            Expression::Sum(a, b) => write!(f, "({a} + {b})"),
+            Expression::Product(a, b) => write!(f, "{a} * {b}"),
+            Expression::Scaled(a, scalar) => write!(f, "{a} * {scalar:?}"),
+            Expression::DistributePowers(exprs, scalar) => {
+                write!(f, "distribute_powers([{}], {scalar})", exprs.iter().join(", "))
+            }
+        }
+    }
+}
+
+/// Returns constraints combined into the quotient numerator, which is usually
+/// a [`Expression::DistributePowers`] of them by `alpha`.
+fn constraints<F>(numerator: &Expression<F>) -> Vec<&Expression<F>> {
+    match numerator {
+        Expression::DistributePowers(exprs, _) => exprs.iter().collect(),
+        _ => vec![numerator],
+    }
+}
+
+impl<C: CurveAffine> Display for Protocol<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "domain: k = {}", self.domain.k)?;
+        writeln!(f, "preprocessed: {}", self.preprocessed.len())?;
+        for (idx, preprocessed) in self.preprocessed.iter().enumerate() {
+            writeln!(f, "  {idx}: {preprocessed:?}")?;
+        }
+        writeln!(f, "num_instance: {:?}", self.num_instance)?;
+        writeln!(f, "num_witness: {:?}", self.num_witness)?;
+        writeln!(f, "num_challenge: {:?}", self.num_challenge)?;
+        writeln!(f, "evaluations: [{}]", self.evaluations.iter().join(", "))?;
+        writeln!(f, "queries: [{}]", self.queries.iter().join(", "))?;
+        writeln!(
+            f,
+            "quotient: {} chunks of degree {}",
+            self.quotient.num_chunk(),
+            self.quotient.chunk_degree
+        )?;
+        for (idx, constraint) in constraints(&self.quotient.numerator).into_iter().enumerate() {
+            writeln!(f, "  {idx} (degree {}): {constraint}", constraint.degree())?;
+        }
+        if let Some(transcript_initial_state) = self.transcript_initial_state.as_ref() {
+            writeln!(f, "transcript_initial_state: {transcript_initial_state:?}")?;
+        }
+        if let Some(linearization) = self.linearization {
+            writeln!(f, "linearization: {linearization:?}")?;
+        }
+        write!(f, "accumulator_indices: {:?}", self.accumulator_indices)
+    }
+}
+
+/// Structural difference between two [`Protocol`]s, from the left one to the
+/// right one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolDiff {
+    /// Changed `k` of domain.
+    pub k: Option<(usize, usize)>,
+    /// Indices of preprocessed commitments that are changed, added or removed.
+    pub preprocessed: Vec<usize>,
+    /// Changed `num_instance`.
+    pub num_instance: Option<(Vec<usize>, Vec<usize>)>,
+    /// Changed `num_witness`.
+    pub num_witness: Option<(Vec<usize>, Vec<usize>)>,
+    /// Changed `num_challenge`.
+    pub num_challenge: Option<(Vec<usize>, Vec<usize>)>,
+    /// Evaluations only in the right one.
+    pub added_evaluations: Vec<Query>,
+    /// Evaluations only in the left one.
+    pub removed_evaluations: Vec<Query>,
+    /// Queries only in the right one.
+    pub added_queries: Vec<Query>,
+    /// Queries only in the left one.
+    pub removed_queries: Vec<Query>,
+    /// Changed number of quotient chunks.
+    pub num_chunk: Option<(usize, usize)>,
+    /// Index of constraints with different degree, where `None` means the
+    /// constraint doesn't exist on that side.
+    pub constraint_degrees: Vec<(usize, Option<usize>, Option<usize>)>,
+    /// Whether `transcript_initial_state` is changed.
+    pub transcript_initial_state: bool,
+}
+
+impl ProtocolDiff {
+    /// Returns `true` if no difference is found.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl<C: CurveAffine> Protocol<C> {
+    /// Returns structural difference from `self` to `other`.
+    pub fn diff(&self, other: &Self) -> ProtocolDiff {
+        fn changed<T: Clone + PartialEq>(lhs: &T, rhs: &T) -> Option<(T, T)> {
+            (lhs != rhs).then(|| (lhs.clone(), rhs.clone()))
+        }
+        fn difference(lhs: &[Query], rhs: &[Query]) -> Vec<Query> {
+            let rhs = rhs.iter().collect::<BTreeSet<_>>();
+            lhs.iter().filter(|query| !rhs.contains(query)).sorted().dedup().copied().collect()
+        }
+
+        let preprocessed = (0..self.preprocessed.len().max(other.preprocessed.len()))
+            .filter(|idx| self.preprocessed.get(*idx) != other.preprocessed.get(*idx))
+            .collect();
+        let constraint_degrees = {
+            let degrees = |protocol: &Self| {
+                constraints(&protocol.quotient.numerator)
+                    .into_iter()
+                    .map(Expression::degree)
+                    .collect_vec()
+            };
+            let (lhs, rhs) = (degrees(self), degrees(other));
+            (0..lhs.len().max(rhs.len()))
+                .map(|idx| (idx, lhs.get(idx).copied(), rhs.get(idx).copied()))
+                .filter(|(_, lhs, rhs)| lhs != rhs)
+                .collect()
+        };
+
+        ProtocolDiff {
+            k: changed(&self.domain.k, &other.domain.k),
+            preprocessed,
+            num_instance: changed(&self.num_instance, &other.num_instance),
+            num_witness: changed(&self.num_witness, &other.num_witness),
+            num_challenge: changed(&self.num_challenge, &other.num_challenge),
+            added_evaluations: difference(&other.evaluations, &self.evaluations),
+            removed_evaluations: difference(&self.evaluations, &other.evaluations),
+            added_queries: difference(&other.queries, &self.queries),
+            removed_queries: difference(&self.queries, &other.queries),
+            num_chunk: changed(&self.quotient.num_chunk(), &other.quotient.num_chunk()),
+            constraint_degrees,
+            transcript_initial_state: self.transcript_initial_state
+                != other.transcript_initial_state,
+        }
+    }
+}
+
+impl Display for ProtocolDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no difference");
+        }
+
+        let mut lines = Vec::new();
+        if let Some((lhs, rhs)) = self.k {
+            lines.push(format!("k: {lhs} -> {rhs}"));
+        }
+        if !self.preprocessed.is_empty() {
+            lines.push(format!("preprocessed changed: {:?}", self.preprocessed));
+        }
+        for (name, changed) in [
+            ("num_instance", &self.num_instance),
+            ("num_witness", &self.num_witness),
+            ("num_challenge", &self.num_challenge),
+        ] {
+            if let Some((lhs, rhs)) = changed {
+                lines.push(format!("{name}: {lhs:?} -> {rhs:?}"));
+            }
+        }
+        for (name, queries) in [
+            ("evaluations added", &self.added_evaluations),
+            ("evaluations removed", &self.removed_evaluations),
+            ("queries added", &self.added_queries),
+            ("queries removed", &self.removed_queries),
+        ] {
+            if !queries.is_empty() {
+                lines.push(format!("{name}: [{}]", queries.iter().join(", ")));
+            }
+        }
+        if let Some((lhs, rhs)) = self.num_chunk {
+            lines.push(format!("quotient chunks: {lhs} -> {rhs}"));
+        }
+        for (idx, lhs, rhs) in self.constraint_degrees.iter() {
+            let degree = |degree: &Option<usize>| {
+                degree.map(|degree| degree.to_string()).unwrap_or_else(|| "none".to_string())
+            };
+            lines.push(format!("constraint {idx} degree: {} -> {}", degree(lhs), degree(rhs)));
+        }
+        if self.transcript_initial_state {
+            lines.push("transcript_initial_state changed".to_string());
+        }
+        write!(f, "{}", lines.iter().join("\n"))
+    }
+}
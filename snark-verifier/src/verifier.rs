@@ -2,7 +2,7 @@ use crate::{
     loader::Loader,
     pcs::{Decider, MultiOpenScheme},
     util::{arithmetic::CurveAffine, transcript::TranscriptRead},
-    Protocol,
+    Error, Protocol,
 };
 use std::fmt::Debug;
 
@@ -23,7 +23,7 @@ where
         protocol: &Protocol<C, L>,
         instances: &[Vec<L::LoadedScalar>],
         transcript: &mut T,
-    ) -> Self::Proof
+    ) -> Result<Self::Proof, Error>
     where
         T: TranscriptRead<C, L>;
 
@@ -32,7 +32,7 @@ where
         protocol: &Protocol<C, L>,
         instances: &[Vec<L::LoadedScalar>],
         proof: &Self::Proof,
-    ) -> Vec<MOS::Accumulator>;
+    ) -> Result<Vec<MOS::Accumulator>, Error>;
 
     fn verify(
         svk: &MOS::SuccinctVerifyingKey,
@@ -40,11 +40,11 @@ where
         protocol: &Protocol<C, L>,
         instances: &[Vec<L::LoadedScalar>],
         proof: &Self::Proof,
-    ) -> MOS::Output
+    ) -> Result<MOS::Output, Error>
     where
         MOS: Decider<C, L>,
     {
-        let accumulators = Self::succinct_verify(svk, protocol, instances, proof);
-        MOS::decide_all(dk, accumulators)
+        let accumulators = Self::succinct_verify(svk, protocol, instances, proof)?;
+        Ok(MOS::decide_all(dk, accumulators))
     }
 }
@@ -35,11 +35,15 @@ where
         protocol: &Protocol<C, L>,
         instances: &[Vec<L::LoadedScalar>],
         transcript: &mut T,
-    ) -> Self::Proof
+    ) -> Result<Self::Proof, Error>
     where
         T: TranscriptRead<C, L>,
     {
-        PlonkProof::read::<T, AE>(svk, protocol, instances, transcript)
+        let loader = transcript.loader().clone();
+        loader.start_cost_metering("read_proof");
+        let proof = PlonkProof::read::<T, AE>(svk, protocol, instances, transcript);
+        loader.end_cost_metering();
+        proof
     }
 
     fn succinct_verify(
@@ -47,7 +51,10 @@ where
         protocol: &Protocol<C, L>,
         instances: &[Vec<L::LoadedScalar>],
         proof: &Self::Proof,
-    ) -> Vec<MOS::Accumulator> {
+    ) -> Result<Vec<MOS::Accumulator>, Error> {
+        let loader = proof.z.loader();
+
+        loader.start_cost_metering("common_polynomial_evaluation");
         let common_poly_eval = {
             let mut common_poly_eval = CommonPolynomialEvaluation::new(
                 &protocol.domain,
@@ -60,19 +67,22 @@ where
 
             common_poly_eval
         };
+        loader.end_cost_metering();
 
+        loader.start_cost_metering("msm");
         let mut evaluations = proof.evaluations(protocol, instances, &common_poly_eval);
-        let commitments = proof.commitments(protocol, &common_poly_eval, &mut evaluations);
-        let queries = proof.queries(protocol, evaluations);
+        let commitments = proof.commitments(protocol, &common_poly_eval, &mut evaluations)?;
+        let queries = proof.queries(protocol, evaluations)?;
 
-        let accumulator = MOS::succinct_verify(svk, &commitments, &proof.z, &queries, &proof.pcs);
+        let accumulator = MOS::succinct_verify(svk, &commitments, &proof.z, &queries, &proof.pcs)?;
+        loader.end_cost_metering();
 
         let accumulators = iter::empty()
             .chain(Some(accumulator))
             .chain(proof.old_accumulators.iter().cloned())
             .collect();
 
-        accumulators
+        Ok(accumulators)
     }
 }
 
@@ -104,20 +114,19 @@ where
         protocol: &Protocol<C, L>,
         instances: &[Vec<L::LoadedScalar>],
         transcript: &mut T,
-    ) -> Self
+    ) -> Result<Self, Error>
     where
         T: TranscriptRead<C, L>,
         AE: AccumulatorEncoding<C, L, MOS>,
     {
         if let Some(transcript_initial_state) = &protocol.transcript_initial_state {
-            transcript.common_scalar(transcript_initial_state).unwrap();
+            transcript.common_scalar(transcript_initial_state)?;
         }
 
-        debug_assert_eq!(
-            protocol.num_instance,
-            instances.iter().map(|instances| instances.len()).collect_vec(),
-            "Invalid Instances"
-        );
+        if protocol.num_instance != instances.iter().map(|instances| instances.len()).collect_vec()
+        {
+            return Err(Error::InvalidInstances);
+        }
 
         let committed_instances = if let Some(ick) = &protocol.instance_committing_key {
             let loader = transcript.loader();
@@ -138,14 +147,14 @@ where
                 })
                 .collect_vec();
             for committed_instance in committed_instances.iter() {
-                transcript.common_ec_point(committed_instance).unwrap();
+                transcript.common_ec_point(committed_instance)?;
             }
 
             Some(committed_instances)
         } else {
             for instances in instances.iter() {
                 for instance in instances.iter() {
-                    transcript.common_scalar(instance).unwrap();
+                    transcript.common_scalar(instance)?;
                 }
             }
 
@@ -153,40 +162,41 @@ where
         };
 
         let (witnesses, challenges) = {
-            let (witnesses, challenges): (Vec<_>, Vec<_>) = protocol
-                .num_witness
-                .iter()
-                .zip(protocol.num_challenge.iter())
-                .map(|(&n, &m)| {
-                    (transcript.read_n_ec_points(n).unwrap(), transcript.squeeze_n_challenges(m))
-                })
-                .unzip();
+            let mut witnesses = Vec::new();
+            let mut challenges = Vec::new();
+            for (&n, &m) in protocol.num_witness.iter().zip(protocol.num_challenge.iter()) {
+                witnesses.extend(transcript.read_n_ec_points(n)?);
+                challenges.extend(transcript.squeeze_n_challenges(m));
+            }
 
-            (
-                witnesses.into_iter().flatten().collect_vec(),
-                challenges.into_iter().flatten().collect_vec(),
-            )
+            (witnesses, challenges)
         };
 
-        let quotients = transcript.read_n_ec_points(protocol.quotient.num_chunk()).unwrap();
+        let quotients = transcript.read_n_ec_points(protocol.quotient.num_chunk())?;
 
         let z = transcript.squeeze_challenge();
-        let evaluations = transcript.read_n_scalars(protocol.evaluations.len()).unwrap();
+        let evaluations = transcript.read_n_scalars(protocol.evaluations.len())?;
 
-        let pcs = MOS::read_proof(svk, &Self::empty_queries(protocol), transcript);
+        let pcs = MOS::read_proof(svk, &Self::empty_queries(protocol), transcript)?;
 
         let old_accumulators = protocol
             .accumulator_indices
             .iter()
             .map(|accumulator_indices| {
-                AE::from_repr(
-                    &accumulator_indices.iter().map(|&(i, j)| &instances[i][j]).collect_vec(),
-                )
-                .unwrap()
+                let repr = accumulator_indices
+                    .iter()
+                    .map(|&(i, j)| {
+                        instances
+                            .get(i)
+                            .and_then(|instances| instances.get(j))
+                            .ok_or(Error::InvalidInstances)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                AE::from_repr(&repr)
             })
-            .collect_vec();
+            .collect::<Result<Vec<_>, _>>()?;
 
-        Self {
+        Ok(Self {
             committed_instances,
             witnesses,
             challenges,
@@ -195,7 +205,7 @@ where
             evaluations,
             pcs,
             old_accumulators,
-        }
+        })
     }
 
     pub fn empty_queries(protocol: &Protocol<C, L>) -> Vec<pcs::Query<C::Scalar>> {
@@ -214,11 +224,16 @@ where
         &self,
         protocol: &Protocol<C, L>,
         mut evaluations: FxHashMap<Query, L::LoadedScalar>,
-    ) -> Vec<pcs::Query<C::Scalar, L::LoadedScalar>> {
+    ) -> Result<Vec<pcs::Query<C::Scalar, L::LoadedScalar>>, Error> {
         Self::empty_queries(protocol)
             .into_iter()
-            .zip(protocol.queries.iter().map(|query| evaluations.remove(query).unwrap()))
-            .map(|(query, eval)| query.with_evaluation(eval))
+            .zip(protocol.queries.iter())
+            .map(|(empty_query, query)| {
+                evaluations
+                    .remove(query)
+                    .map(|eval| empty_query.with_evaluation(eval))
+                    .ok_or(Error::InvalidQuery(*query))
+            })
             .collect()
     }
 
@@ -227,7 +242,7 @@ where
         protocol: &'a Protocol<C, L>,
         common_poly_eval: &CommonPolynomialEvaluation<C, L>,
         evaluations: &mut FxHashMap<Query, L::LoadedScalar>,
-    ) -> Vec<Msm<C, L>> {
+    ) -> Result<Vec<Msm<C, L>>, Error> {
         let loader = common_poly_eval.zn().loader();
         let mut commitments = iter::empty()
             .chain(protocol.preprocessed.iter().map(Msm::base))
@@ -247,8 +262,8 @@ where
             .collect_vec();
 
         let numerator = protocol.quotient.numerator.evaluate(
-            &|scalar| Msm::constant(loader.load_const(&scalar)),
-            &|poly| Msm::constant(common_poly_eval.get(poly).clone()),
+            &|scalar| Ok(Msm::constant(loader.load_const(&scalar))),
+            &|poly| Ok(Msm::constant(common_poly_eval.get(poly).clone())),
             &|query| {
                 evaluations
                     .get(&query)
@@ -260,7 +275,6 @@ where
                             .flatten()
                     })
                     .ok_or(Error::InvalidQuery(query))
-                    .unwrap()
             },
             &|index| {
                 self.challenges
@@ -268,17 +282,19 @@ where
                     .cloned()
                     .map(Msm::constant)
                     .ok_or(Error::InvalidChallenge(index))
-                    .unwrap()
             },
-            &|a| -a,
-            &|a, b| a + b,
-            &|a, b| match (a.size(), b.size()) {
-                (0, _) => b * &a.try_into_constant().unwrap(),
-                (_, 0) => a * &b.try_into_constant().unwrap(),
-                (_, _) => panic!("{:?}", Error::InvalidLinearization),
+            &|a| Ok(-a?),
+            &|a, b| Ok(a? + b?),
+            &|a, b| {
+                let (a, b) = (a?, b?);
+                match (a.size(), b.size()) {
+                    (0, _) => Ok(b * &a.try_into_constant().unwrap()),
+                    (_, 0) => Ok(a * &b.try_into_constant().unwrap()),
+                    (_, _) => Err(Error::InvalidLinearization),
+                }
             },
-            &|a, scalar| a * &loader.load_const(&scalar),
-        );
+            &|a, scalar| Ok(a? * &loader.load_const(&scalar)),
+        )?;
 
         let quotient_query = Query::new(
             protocol.preprocessed.len() + protocol.num_instance.len() + self.witnesses.len(),
@@ -298,10 +314,13 @@ where
                 let (msm, constant) = numerator.split();
                 commitments.push(quotient);
                 commitments.push(msm);
+                let linearization_eval = evaluations
+                    .get(&linearization_query)
+                    .cloned()
+                    .ok_or(Error::InvalidQuery(linearization_query))?;
                 evaluations.insert(
                     quotient_query,
-                    (constant.unwrap_or_else(|| loader.load_zero())
-                        + evaluations.get(&linearization_query).unwrap())
+                    (constant.unwrap_or_else(|| loader.load_zero()) + linearization_eval)
                         * common_poly_eval.zn_minus_one_inv(),
                 );
             }
@@ -315,13 +334,13 @@ where
                 commitments.push(quotient);
                 evaluations.insert(
                     quotient_query,
-                    numerator.try_into_constant().ok_or(Error::InvalidLinearization).unwrap()
+                    numerator.try_into_constant().ok_or(Error::InvalidLinearization)?
                         * common_poly_eval.zn_minus_one_inv(),
                 );
             }
         }
 
-        commitments
+        Ok(commitments)
     }
 
     fn evaluations(